@@ -0,0 +1,103 @@
+// Timings for the canonicalization hot path, for before/after numbers on
+// performance changes: canonicalizing a batch of raw rows, and extracting
+// rows from one large works table. Inputs are the committed fixtures in
+// benches/fixtures, never the network.
+//
+//   cargo bench -p get_wikipedia_info
+//   cargo bench -p get_wikipedia_info -- extract   # only names containing "extract"
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use get_wikipedia_info::works::extract_raw_data;
+use get_wikipedia_info::{FieldCanonicalizer, RawCompositionData, canonicalize_raw_data_with};
+use scraper::Html;
+
+// Brahms, Bach, Mozart and Stravinsky rows: keys, opus and catalogue
+// numbers, circa and "(comp.) / (pub.)" dates, multi-value genres
+const RAW_ROWS: &str = include_str!("fixtures/raw-rows.jsonl");
+// One 800-row table with sort keys and citation markers in the cells
+const LARGE_TABLE: &str = include_str!("fixtures/large-table.html");
+
+const WARM_UP: Duration = Duration::from_millis(500);
+const MEASURE: Duration = Duration::from_secs(3);
+
+// Run `f` repeatedly for MEASURE after a warm-up and print the mean and
+// fastest time per call
+fn bench(name: &str, filter: Option<&str>, mut f: impl FnMut()) {
+    if filter.is_some_and(|filter| !name.contains(filter)) {
+        return;
+    }
+
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < WARM_UP {
+        f();
+    }
+
+    let mut samples = Vec::new();
+    let measure = Instant::now();
+    while measure.elapsed() < MEASURE {
+        let start = Instant::now();
+        f();
+        samples.push(start.elapsed());
+    }
+
+    let mean = measure.elapsed() / samples.len() as u32;
+    let fastest = samples.iter().min().copied().unwrap_or_default();
+    println!(
+        "{:<48} {:>12.3?} mean {:>12.3?} fastest ({} runs)",
+        name,
+        mean,
+        fastest,
+        samples.len()
+    );
+}
+
+fn main() {
+    // cargo bench passes "--bench" along with any filter
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let filter = filter.as_deref();
+
+    let rows: Vec<RawCompositionData> = RAW_ROWS
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("fixture rows parse"))
+        .collect();
+
+    let canonicalizer = FieldCanonicalizer::new();
+    bench("canonicalize_raw_data/batch", filter, || {
+        for raw in &rows {
+            black_box(canonicalize_raw_data_with(raw.clone(), &canonicalizer));
+        }
+    });
+    // As canonicalize_raw_data does it, building the canonicalizer per row
+    bench(
+        "canonicalize_raw_data/batch_fresh_canonicalizer",
+        filter,
+        || {
+            for raw in &rows {
+                black_box(canonicalize_raw_data_with(
+                    raw.clone(),
+                    &FieldCanonicalizer::new(),
+                ));
+            }
+        },
+    );
+
+    let document = Html::parse_document(LARGE_TABLE);
+    bench("extract_raw_table_data/large_table", filter, || {
+        black_box(extract_raw_data(
+            &document,
+            "Carl Czerny",
+            "https://en.wikipedia.org/wiki/Carl_Czerny",
+            "https://en.wikipedia.org/wiki/List_of_compositions_by_Carl_Czerny",
+        ));
+    });
+    bench("extract_raw_table_data/parse_and_extract", filter, || {
+        black_box(extract_raw_data(
+            &Html::parse_document(LARGE_TABLE),
+            "Carl Czerny",
+            "https://en.wikipedia.org/wiki/Carl_Czerny",
+            "https://en.wikipedia.org/wiki/List_of_compositions_by_Carl_Czerny",
+        ));
+    });
+}
@@ -0,0 +1,218 @@
+// Wikipedia text is licensed CC BY-SA, so a dataset built from it has to
+// say where it came from. get_works keeps ATTRIBUTION.json beside its other
+// outputs: the license, and for each composer scraped the pages used and
+// the date they were retrieved. A composer scraped again replaces its
+// earlier entry. Batch runs and the server scrape several composers at
+// once, so updates are serialized and each save replaces the file whole.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+pub const ATTRIBUTION_FILENAME: &str = "ATTRIBUTION.json";
+pub const LICENSE: &str = "CC BY-SA 4.0";
+pub const LICENSE_URL: &str = "https://creativecommons.org/licenses/by-sa/4.0/";
+
+// Held for each load-record-save, so concurrent composers don't overwrite
+// each other's entries
+static UPDATE_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SourceRecord {
+    pub composer_name: String,
+    pub source_urls: Vec<String>,
+    // UTC, as YYYY-MM-DD
+    pub retrieved: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Attribution {
+    pub license: String,
+    pub license_url: String,
+    pub notice: String,
+    pub sources: Vec<SourceRecord>,
+}
+
+impl Default for Attribution {
+    fn default() -> Self {
+        Self {
+            license: LICENSE.to_string(),
+            license_url: LICENSE_URL.to_string(),
+            notice: "Derived from Wikipedia articles by their contributors; see each source URL \
+                     for the article history"
+                .to_string(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Attribution {
+    // The file at `path`, or a fresh record if there isn't one yet
+    pub async fn load_or_default(path: &str) -> Result<Self> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Written beside `path` and renamed over it, so a reader never sees a
+    // half-written file
+    pub async fn save(&self, path: &str) -> Result<()> {
+        let temp_path = format!("{}.tmp-{}", path, std::process::id());
+        tokio::fs::write(&temp_path, serde_json::to_string_pretty(self)?).await?;
+        tokio::fs::rename(&temp_path, path).await?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, source: SourceRecord) {
+        match self
+            .sources
+            .iter_mut()
+            .find(|existing| existing.composer_name == source.composer_name)
+        {
+            Some(existing) => *existing = source,
+            None => self.sources.push(source),
+        }
+    }
+}
+
+// Add today's entry for a composer to the attribution file at `path`
+pub async fn record_sources(
+    path: &str,
+    composer_name: &str,
+    source_urls: Vec<String>,
+) -> Result<()> {
+    let _update = UPDATE_LOCK.lock().await;
+    let mut attribution = Attribution::load_or_default(path).await?;
+    attribution.record(SourceRecord {
+        composer_name: composer_name.to_string(),
+        source_urls,
+        retrieved: utc_date(SystemTime::now()),
+    });
+    attribution.save(path).await
+}
+
+// YYYY-MM-DD (UTC) for a point in time, by the days-to-civil conversion
+// from Howard Hinnant's date algorithms
+pub fn utc_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_utc_dates() {
+        assert_eq!(utc_date(UNIX_EPOCH), "1970-01-01");
+        // 2000-02-29T12:00:00Z
+        assert_eq!(
+            utc_date(UNIX_EPOCH + Duration::from_secs(951_825_600)),
+            "2000-02-29"
+        );
+        // 2024-12-31T23:59:59Z
+        assert_eq!(
+            utc_date(UNIX_EPOCH + Duration::from_secs(1_735_689_599)),
+            "2024-12-31"
+        );
+    }
+
+    #[tokio::test]
+    async fn a_composer_scraped_again_replaces_its_entry() {
+        let path = std::env::temp_dir().join(format!("attribution-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        record_sources(path, "Johannes Brahms", vec!["https://a/1".to_string()])
+            .await
+            .unwrap();
+        record_sources(path, "Clara Schumann", vec!["https://a/2".to_string()])
+            .await
+            .unwrap();
+        record_sources(path, "Johannes Brahms", vec!["https://a/3".to_string()])
+            .await
+            .unwrap();
+
+        let attribution = Attribution::load_or_default(path).await.unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(attribution.license, LICENSE);
+        let sources: Vec<(&str, &[String])> = attribution
+            .sources
+            .iter()
+            .map(|source| (source.composer_name.as_str(), source.source_urls.as_slice()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("Johannes Brahms", &["https://a/3".to_string()][..]),
+                ("Clara Schumann", &["https://a/2".to_string()][..]),
+            ]
+        );
+        assert_eq!(attribution.sources[0].retrieved.len(), 10);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_composers_all_keep_their_entries() {
+        let path = std::env::temp_dir().join(format!(
+            "attribution-concurrent-{}.json",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let composers = [
+            "Clara Schumann",
+            "Amy Beach",
+            "Lili Boulanger",
+            "Ethel Smyth",
+        ];
+        let tasks: Vec<_> = composers
+            .iter()
+            .map(|composer| {
+                let (path, composer) = (path.clone(), composer.to_string());
+                tokio::spawn(async move {
+                    let url = format!("https://a/{}", composer.replace(' ', "_"));
+                    record_sources(&path, &composer, vec![url]).await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let attribution = Attribution::load_or_default(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut recorded: Vec<&str> = attribution
+            .sources
+            .iter()
+            .map(|source| source.composer_name.as_str())
+            .collect();
+        recorded.sort_unstable();
+        assert_eq!(
+            recorded,
+            vec![
+                "Amy Beach",
+                "Clara Schumann",
+                "Ethel Smyth",
+                "Lili Boulanger"
+            ]
+        );
+    }
+}
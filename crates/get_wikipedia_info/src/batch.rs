@@ -0,0 +1,407 @@
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{SeedableRng, seq::index};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::composers::read_composers;
+use crate::http::BudgetExhausted;
+use crate::works::{LifeYears, SharedSink, WorksError, WorksOptions, get_works_into, output_sink};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ComposerOutcome {
+    Success {
+        works: usize,
+    },
+    NotFound {
+        message: String,
+    },
+    // The works page was fetched but yielded no rows
+    NoContent {
+        message: String,
+    },
+    HttpError {
+        http_status: Option<u16>,
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl ComposerOutcome {
+    pub fn from_result(result: &Result<usize>) -> Self {
+        let e = match result {
+            Ok(works) => return ComposerOutcome::Success { works: *works },
+            Err(e) => e,
+        };
+
+        if let Some(WorksError::NotFound { .. }) = e.downcast_ref::<WorksError>() {
+            ComposerOutcome::NotFound {
+                message: e.to_string(),
+            }
+        } else if let Some(WorksError::NoContentExtracted { .. }) = e.downcast_ref::<WorksError>() {
+            ComposerOutcome::NoContent {
+                message: e.to_string(),
+            }
+        } else if let Some(http_error) = e.downcast_ref::<reqwest::Error>() {
+            ComposerOutcome::HttpError {
+                http_status: http_error.status().map(|s| s.as_u16()),
+                message: e.to_string(),
+            }
+        } else {
+            ComposerOutcome::Error {
+                message: format!("{:#}", e),
+            }
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, ComposerOutcome::Success { .. })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ComposerRun {
+    pub composer: String,
+    #[serde(flatten)]
+    pub outcome: ComposerOutcome,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RunReport {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub composers: Vec<ComposerRun>,
+    // Composers not scraped because the request budget ran out, starting
+    // with the one it ran out on; --retry-failed picks them up
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remaining: Vec<String>,
+}
+
+impl RunReport {
+    pub async fn load(path: &str) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub async fn save(&self, path: &str) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    pub fn failed_composers(&self) -> Vec<String> {
+        self.composers
+            .iter()
+            .filter(|run| !run.outcome.is_success())
+            .map(|run| run.composer.clone())
+            .collect()
+    }
+
+    // The failed composers, then those never reached: what a resumed run
+    // still has to scrape
+    pub fn unfinished_composers(&self) -> Vec<String> {
+        let mut composers = self.failed_composers();
+        composers.extend(self.remaining.iter().cloned());
+        composers
+    }
+}
+
+// Run `scrape` for every composer, up to `concurrency` at a time, recording
+// every outcome instead of stopping at the first failure. Outcomes are
+// reported in the composers' order whatever order they finish in. Only a
+// spent request budget stops the run: no further composers are started,
+// and those it ran out on plus the rest are left in `remaining`.
+pub async fn run_batch<F, Fut>(
+    composers: Vec<String>,
+    concurrency: usize,
+    mut scrape: F,
+) -> RunReport
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<usize>> + Send + 'static,
+{
+    let mut report = RunReport::default();
+    let mut results: Vec<Option<Result<usize>>> = composers.iter().map(|_| None).collect();
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut pending = composers.iter().cloned().enumerate();
+    let mut stopped = false;
+
+    loop {
+        while !stopped && tasks.len() < concurrency.max(1) {
+            let Some((index, composer)) = pending.next() else {
+                break;
+            };
+            let scraping = scrape(composer);
+            tasks.spawn(async move { (index, scraping.await) });
+        }
+        let Some(joined) = tasks.join_next().await else {
+            break;
+        };
+        let (index, result) = joined.unwrap_or_else(|e| panic!("composer task failed: {e}"));
+        if let Err(e) = &result
+            && e.downcast_ref::<BudgetExhausted>().is_some()
+            && !stopped
+        {
+            warn!("{}; stopping before {}", e, composers[index]);
+            stopped = true;
+        }
+        results[index] = Some(result);
+    }
+
+    for (composer, result) in composers.into_iter().zip(results) {
+        let result = match result {
+            Some(Err(e)) if e.downcast_ref::<BudgetExhausted>().is_some() => None,
+            result => result,
+        };
+        let Some(result) = result else {
+            report.remaining.push(composer);
+            continue;
+        };
+        let outcome = ComposerOutcome::from_result(&result);
+
+        match &result {
+            Ok(works) => {
+                info!("{}: {} works", composer, works);
+                report.succeeded += 1;
+            }
+            Err(e) => {
+                error!("{}: {}", composer, e);
+                report.failed += 1;
+            }
+        }
+        report.composers.push(ComposerRun { composer, outcome });
+    }
+
+    report
+}
+
+// Pick n composers uniformly at random, keeping their original order. The
+// same seed always yields the same selection.
+pub fn sample_composers(composers: Vec<String>, n: usize, seed: u64) -> Vec<String> {
+    if n >= composers.len() {
+        return composers;
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut picked = index::sample(&mut rng, composers.len(), n).into_vec();
+    picked.sort_unstable();
+
+    picked.into_iter().map(|i| composers[i].clone()).collect()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BatchOptions {
+    pub composers_file: String,
+    pub limit: Option<usize>,
+    // Scrape a random subset of this many composers instead of all of them
+    pub sample: Option<usize>,
+    // Seed for --sample; a random one is chosen (and logged) when unset
+    pub seed: Option<u64>,
+    // Re-run only the composers that failed, or were never reached, in this
+    // earlier run-report.json
+    pub retry_failed: Option<String>,
+    // Composers scraped at once; they share the client, request budget
+    // and output files
+    pub concurrency: usize,
+    pub report_file: String,
+    pub works: WorksOptions,
+}
+
+// Scrape works for every composer in composers.json (or only the failures
+// from an earlier report) and write a run-report.json of per-composer outcomes.
+pub async fn get_works_for_composers(options: &BatchOptions) -> Result<RunReport> {
+    // composers.json is optional when retrying, where it only supplies years
+    let known_composers = match &options.retry_failed {
+        Some(_) => read_composers(&options.composers_file)
+            .await
+            .unwrap_or_default(),
+        None => read_composers(&options.composers_file).await?,
+    };
+    let mut composers = match &options.retry_failed {
+        Some(previous_report) => RunReport::load(previous_report)
+            .await?
+            .unfinished_composers(),
+        None => known_composers
+            .iter()
+            .map(|composer| composer.full_name.clone())
+            .collect(),
+    };
+
+    let mut works = options.works.clone();
+    works
+        .composer_years
+        .extend(known_composers.iter().map(|composer| {
+            (
+                composer.full_name.clone(),
+                LifeYears {
+                    birth: composer.birth_year,
+                    death: composer.death_year,
+                },
+            )
+        }));
+    let works = Arc::new(works);
+    if let Some(n) = options.sample {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        info!("Sampling {} composers with seed {}", n, seed);
+        composers = sample_composers(composers, n, seed);
+    }
+    if let Some(limit) = options.limit {
+        composers.truncate(limit);
+    }
+
+    info!("Scraping works for {} composers", composers.len());
+
+    // All composers share one set of output files
+    let (sink, writer) = SharedSink::spawn(output_sink(&works).await?);
+    let report = run_batch(composers, options.concurrency, |composer| {
+        let (sink, works) = (sink.clone(), works.clone());
+        async move { get_works_into(&composer, &works, &sink).await }
+    })
+    .await;
+    drop(sink);
+    report.save(&options.report_file).await?;
+    let filenames = writer.await??;
+    info!("Compositions written to {}", filenames.join(", "));
+
+    info!(
+        "{} composers succeeded, {} failed; report written to {}",
+        report.succeeded, report.failed, options.report_file
+    );
+    if !report.remaining.is_empty() {
+        warn!(
+            "Request budget exhausted with {} composers left; resume with --retry-failed {}",
+            report.remaining.len(),
+            options.report_file
+        );
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_success_and_failure_outcomes() {
+        let composers = vec![
+            "Johann Sebastian Bach".to_string(),
+            "Nobody In Particular".to_string(),
+            "Broken Page".to_string(),
+        ];
+
+        let report = run_batch(composers, 1, |composer| async move {
+            match composer.as_str() {
+                "Johann Sebastian Bach" => Ok(1128),
+                "Nobody In Particular" => Err(WorksError::NotFound {
+                    url: "https://en.wikipedia.org/wiki/List_of_compositions_by_Nobody".into(),
+                }
+                .into()),
+                _ => Err(anyhow::anyhow!("unexpected table layout")),
+            }
+        })
+        .await;
+
+        assert_eq!(report.succeeded, 1);
+        assert_eq!(report.failed, 2);
+        assert_eq!(
+            report.composers[0].outcome,
+            ComposerOutcome::Success { works: 1128 }
+        );
+        assert!(matches!(
+            report.composers[1].outcome,
+            ComposerOutcome::NotFound { .. }
+        ));
+        assert_eq!(
+            report.composers[2].outcome,
+            ComposerOutcome::Error {
+                message: "unexpected table layout".to_string()
+            }
+        );
+        assert_eq!(
+            report.failed_composers(),
+            vec!["Nobody In Particular", "Broken Page"]
+        );
+
+        let json = serde_json::to_string(&report).unwrap();
+        let reloaded: RunReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.composers, report.composers);
+    }
+
+    #[tokio::test]
+    async fn runs_composers_concurrently_and_reports_them_in_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let composers: Vec<String> = ["Hildegard von Bingen", "Barbara Strozzi", "Lili Boulanger"]
+            .map(String::from)
+            .to_vec();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let report = run_batch(composers.clone(), 3, |composer| {
+            let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                most_in_flight.fetch_max(now, Ordering::SeqCst);
+                // The first composer finishes last
+                let delay = if composer == "Hildegard von Bingen" {
+                    30
+                } else {
+                    10
+                };
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(composer.len())
+            }
+        })
+        .await;
+
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 3);
+        assert_eq!(report.succeeded, 3);
+        let order: Vec<&str> = report
+            .composers
+            .iter()
+            .map(|run| run.composer.as_str())
+            .collect();
+        assert_eq!(order, composers);
+    }
+
+    #[tokio::test]
+    async fn a_spent_budget_stops_starting_composers() {
+        let composers: Vec<String> = (0..6).map(|i| format!("Composer {i}")).collect();
+        let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let report = run_batch(composers, 2, |composer| {
+            started.lock().unwrap().push(composer.clone());
+            async move {
+                if composer == "Composer 1" {
+                    Err(BudgetExhausted { limit: 1 }.into())
+                } else {
+                    Ok(1)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(report.succeeded, started.lock().unwrap().len() - 1);
+        assert!(started.lock().unwrap().len() < 6);
+        assert_eq!(report.remaining[0], "Composer 1");
+        assert_eq!(report.remaining.last().unwrap(), "Composer 5");
+        assert_eq!(report.composers.len() + report.remaining.len(), 6);
+    }
+
+    #[test]
+    fn sampling_is_deterministic_for_a_seed() {
+        let composers: Vec<String> = (0..50).map(|i| format!("Composer {i}")).collect();
+
+        let first = sample_composers(composers.clone(), 5, 42);
+        assert_eq!(first.len(), 5);
+        assert_eq!(first, sample_composers(composers.clone(), 5, 42));
+        assert_ne!(first, sample_composers(composers.clone(), 5, 43));
+        assert_eq!(sample_composers(composers.clone(), 100, 42), composers);
+    }
+}
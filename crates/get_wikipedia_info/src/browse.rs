@@ -0,0 +1,282 @@
+use anyhow::Result;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Row, Table, TableState},
+};
+use std::process::Command;
+
+use crate::Composition;
+use crate::works::read_compositions;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Editing {
+    Composer,
+    Genre,
+}
+
+// Browser state: every loaded composition plus the composer/genre filters.
+// Filters are case-insensitive substring matches.
+struct App {
+    compositions: Vec<Composition>,
+    composer_filter: String,
+    genre_filter: String,
+    editing: Option<Editing>,
+    visible: Vec<usize>,
+    table_state: TableState,
+    status: String,
+}
+
+impl App {
+    fn new(compositions: Vec<Composition>) -> Self {
+        let mut app = Self {
+            compositions,
+            composer_filter: String::new(),
+            genre_filter: String::new(),
+            editing: None,
+            visible: Vec::new(),
+            table_state: TableState::default(),
+            status: String::new(),
+        };
+        app.apply_filters();
+        app
+    }
+
+    fn apply_filters(&mut self) {
+        let composer_filter = self.composer_filter.to_lowercase();
+        let genre_filter = self.genre_filter.to_lowercase();
+
+        self.visible = self
+            .compositions
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.composer_name.to_lowercase().contains(&composer_filter))
+            .filter(|(_, c)| {
+                genre_filter.is_empty()
+                    || c.genre
+                        .as_deref()
+                        .is_some_and(|genre| genre.to_lowercase().contains(&genre_filter))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.table_state
+            .select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn selected(&self) -> Option<&Composition> {
+        let index = *self.visible.get(self.table_state.selected()?)?;
+        self.compositions.get(index)
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.table_state.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        self.table_state
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    fn open_selected(&mut self) {
+        let Some(url) = self.selected().and_then(|c| c.work_url.clone()) else {
+            self.status = "No work_url for this composition".to_string();
+            return;
+        };
+        self.status = match open_in_browser(&url) {
+            Ok(()) => format!("Opened {}", url),
+            Err(e) => format!("Could not open {}: {}", url, e),
+        };
+    }
+
+    // Returns false once the user quits
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        if let Some(editing) = self.editing {
+            let filter = match editing {
+                Editing::Composer => &mut self.composer_filter,
+                Editing::Genre => &mut self.genre_filter,
+            };
+            match code {
+                KeyCode::Enter | KeyCode::Esc => self.editing = None,
+                KeyCode::Backspace => {
+                    filter.pop();
+                }
+                KeyCode::Char(c) => filter.push(c),
+                _ => {}
+            }
+            self.apply_filters();
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') => self.editing = Some(Editing::Composer),
+            KeyCode::Char('g') => self.editing = Some(Editing::Genre),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(20),
+            KeyCode::PageUp => self.move_selection(-20),
+            KeyCode::Enter => self.open_selected(),
+            _ => {}
+        }
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [filters_area, table_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let marker = |editing| {
+            if self.editing == Some(editing) {
+                "_"
+            } else {
+                ""
+            }
+        };
+        frame.render_widget(
+            Line::from(format!(
+                "composer: {}{}   genre: {}{}   ({} of {})",
+                self.composer_filter,
+                marker(Editing::Composer),
+                self.genre_filter,
+                marker(Editing::Genre),
+                self.visible.len(),
+                self.compositions.len()
+            )),
+            filters_area,
+        );
+
+        let rows = self.visible.iter().map(|&index| {
+            let c = &self.compositions[index];
+            Row::new(vec![
+                c.composer_name.clone(),
+                c.title.clone(),
+                c.year.clone().unwrap_or_default(),
+                c.genre.clone().unwrap_or_default(),
+                c.key.clone().unwrap_or_default(),
+                c.opus.clone().unwrap_or_default(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(40),
+                Constraint::Length(6),
+                Constraint::Percentage(15),
+                Constraint::Percentage(12),
+                Constraint::Length(8),
+            ],
+        )
+        .header(
+            Row::new(vec!["Composer", "Title", "Year", "Genre", "Key", "Opus"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.table_state);
+
+        let help = if self.status.is_empty() {
+            "↑/↓ scroll  c composer filter  g genre filter  Enter open work_url  q quit"
+        } else {
+            self.status.as_str()
+        };
+        frame.render_widget(Line::from(help), help_area);
+    }
+}
+
+fn open_in_browser(url: &str) -> std::io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url).spawn().map(|_| ())
+}
+
+fn run(terminal: &mut DefaultTerminal, mut app: App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && !app.handle_key(key.code)
+        {
+            return Ok(());
+        }
+    }
+}
+
+// Load a compositions JSONL file and browse it in the terminal until the
+// user quits.
+pub async fn browse(path: &str) -> Result<()> {
+    let mut compositions = Vec::new();
+    let mut reader = read_compositions(path).await?;
+    while let Some(composition) = reader.next().await {
+        compositions.push(composition?);
+    }
+
+    let app = App::new(compositions);
+    tokio::task::spawn_blocking(move || {
+        let mut terminal = ratatui::init();
+        let result = run(&mut terminal, app);
+        ratatui::restore();
+        result
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composition(composer_name: &str, title: &str, genre: Option<&str>) -> Composition {
+        serde_json::from_value(serde_json::json!({
+            "composer_name": composer_name,
+            "composer_url": "",
+            "source_url": "",
+            "title": title,
+            "work_url": null,
+            "year": null,
+            "key": null,
+            "opus": null,
+            "genre": genre,
+            "catalog_number": null,
+            "instrumentation": null,
+            "duration": null,
+            "additional_info": {}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn filters_by_composer_and_genre() {
+        let mut app = App::new(vec![
+            composition("Igor Stravinsky", "The Firebird", Some("Ballet")),
+            composition("Igor Stravinsky", "Octet", None),
+            composition("Maurice Ravel", "Boléro", Some("Ballet")),
+        ]);
+        assert_eq!(app.visible, vec![0, 1, 2]);
+
+        for key in [KeyCode::Char('g'), KeyCode::Char('b'), KeyCode::Enter] {
+            app.handle_key(key);
+        }
+        assert_eq!(app.visible, vec![0, 2]);
+
+        for key in "cravel".chars() {
+            app.handle_key(KeyCode::Char(key));
+        }
+        assert_eq!(app.visible, vec![2]);
+        assert_eq!(app.selected().unwrap().title, "Boléro");
+    }
+}
@@ -1,18 +1,28 @@
 // use reqwest;
 use anyhow::Result;
-use regex;
-use scraper::{Html, Selector};
-use serde::Serialize;
+use regex::Regex;
+use scraper::{CaseSensitivity, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
-use tracing::{error, info};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::http::{HttpOptions, ServiceLimit, Throttle, build_client, get_with_retry};
+use crate::text::{normalize_dashes, wiki_slug};
+use tracing::{error, info, warn};
 // use tracing_subscriber::fmt::init;
 
+use std::sync::Arc;
 use tokio::{
     fs::OpenOptions,
     io::{AsyncWriteExt, BufWriter},
     sync::mpsc,
+    task::JoinSet,
 };
 
+use crate::infobox::{parse_birth_death_places, parse_infobox_image};
+use crate::works::fetch_html;
+
 // -----
 #[derive(Debug, PartialEq)]
 struct ParsedYears {
@@ -20,14 +30,25 @@ struct ParsedYears {
     death_year: Option<i32>,
     approximate: bool,
     flourished: bool,
+    // Explicit "born YYYY" / "b. YYYY" marker or an open-ended "YYYY–" range
+    alive: bool,
 }
 
-#[derive(Debug, PartialEq, Serialize, Clone)]
-enum QualityOfYearInfo {
+// Serialized as stable lowercase names; the aliases read files written
+// before the rename.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityOfYearInfo {
+    #[serde(alias = "Exact")]
     Exact,
+    #[serde(alias = "Approximate")]
     Approximate,
+    #[serde(alias = "Flourished")]
     Flourished,
+    #[serde(alias = "AliveToday")]
     AliveToday,
+    #[serde(rename = "unknown", alias = "YearInfoUnknown")]
+    YearInfoUnknown,
 }
 impl fmt::Display for QualityOfYearInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -36,16 +57,19 @@ impl fmt::Display for QualityOfYearInfo {
             QualityOfYearInfo::Approximate => "Approximate",
             QualityOfYearInfo::Flourished => "Flourished",
             QualityOfYearInfo::AliveToday => "Alive Today",
+            QualityOfYearInfo::YearInfoUnknown => "Year Info Unknown",
         };
         write!(f, "{}", s)
     }
 }
 // -----
 
-#[derive(Serialize, Clone)]
-struct Composer {
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Composer {
     pub url: String,
     pub full_name: String,
+    // Index form of the name, e.g. "Beethoven, Ludwig van"
+    pub sort_name: String,
     // pub years_info: Option<String>,
     pub list_of_compositions_url: String,
     //pub last_name: Option<String>,
@@ -53,6 +77,21 @@ struct Composer {
     pub birth_year: Option<i32>,
     pub death_year: Option<i32>,
     pub years_qualifier: QualityOfYearInfo,
+    // Lead paragraph of the composer's article, fetched with --summaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    // Places from the article's infobox "Born"/"Died" rows, fetched with
+    // --places; death_place stays None for living composers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub birth_place: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub death_place: Option<String>,
+    // From the nationality column of table-layout composer lists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nationality: Option<String>,
+    // The article infobox's portrait, fetched with --images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
 }
 
 async fn composer_writer_task(
@@ -78,6 +117,190 @@ async fn composer_writer_task(
     Ok(())
 }
 
+// Read a composers.json (JSONL) file back, skipping lines that don't parse.
+pub async fn read_composers(filename: &str) -> Result<Vec<Composer>> {
+    let contents = tokio::fs::read_to_string(filename).await?;
+    let composers = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<Composer>(line) {
+            Ok(composer) => Some(composer),
+            Err(e) => {
+                warn!("Skipping malformed composer line in {}: {}", filename, e);
+                None
+            }
+        })
+        .collect();
+    Ok(composers)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ComposerOptions {
+    // Keep composers.json and append only composers not already in it
+    pub append: bool,
+    // Fetch each composer's article for its lead paragraph, cut to at most
+    // this many characters.
+    pub summary_max_chars: Option<usize>,
+    // Fetch each composer's article for the birth and death places in its
+    // infobox.
+    pub places: bool,
+    // Fetch each composer's article for its infobox image. With none of
+    // these options set no composer articles are fetched.
+    pub images: bool,
+    // Budget for those article fetches, separate from the list pages
+    pub article_limit: ServiceLimit,
+}
+
+// Text of an element without citation markers ("[1]", "[note 2]"),
+// pronunciation/coordinate widgets or inline styles
+fn paragraph_text(paragraph: scraper::ElementRef) -> String {
+    let skipped = |element: scraper::ElementRef| {
+        let value = element.value();
+        value.name() == "style"
+            || (value.name() == "sup"
+                && value.has_class("reference", CaseSensitivity::CaseSensitive))
+            || value.id() == Some("coordinates")
+    };
+
+    let text: String = paragraph
+        .descendants()
+        .filter_map(|node| {
+            let text = node.value().as_text()?;
+            let inside_skipped = node
+                .ancestors()
+                .filter_map(scraper::ElementRef::wrap)
+                .take_while(|ancestor| ancestor.id() != paragraph.id())
+                .any(skipped);
+            (!inside_skipped).then_some(&**text)
+        })
+        .collect();
+    let citation_regex = Regex::new(r"\[(?:\d+|[a-z]|note \d+|citation needed)\]").unwrap();
+    citation_regex
+        .replace_all(&text, "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// First non-empty paragraph of an article body. Hatnotes, infoboxes and
+// the empty placeholder paragraphs Wikipedia puts before the lead are not
+// <p> children of the body, or have no text, so they are passed over.
+fn lead_paragraph(document: &Html) -> Option<String> {
+    let body_selector = Selector::parse("div.mw-parser-output > p").unwrap();
+    let any_selector = Selector::parse("p").unwrap();
+
+    let mut paragraphs = document.select(&body_selector).peekable();
+    let paragraphs: Box<dyn Iterator<Item = scraper::ElementRef>> = if paragraphs.peek().is_some() {
+        Box::new(paragraphs)
+    } else {
+        Box::new(document.select(&any_selector))
+    };
+    paragraphs.map(paragraph_text).find(|text| !text.is_empty())
+}
+
+// Cut at a word boundary, marking the cut with "…"
+fn truncate_summary(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > 0 => &cut[..space],
+        _ => &cut,
+    };
+    format!("{}…", cut.trim_end_matches([',', ';', ':']))
+}
+
+// What fetch_composer_pages reads from one composer article
+#[derive(Default)]
+struct ComposerPageDetails {
+    summary: Option<String>,
+    birth_place: Option<String>,
+    death_place: Option<String>,
+    image_url: Option<String>,
+}
+
+fn composer_page_details(html: &str, options: &ComposerOptions) -> ComposerPageDetails {
+    let document = Html::parse_document(html);
+    let (birth_place, death_place) = if options.places {
+        parse_birth_death_places(&document)
+    } else {
+        (None, None)
+    };
+    ComposerPageDetails {
+        summary: options.summary_max_chars.and_then(|max_chars| {
+            lead_paragraph(&document).map(|summary| truncate_summary(&summary, max_chars))
+        }),
+        birth_place,
+        death_place,
+        image_url: options
+            .images
+            .then(|| parse_infobox_image(&document))
+            .flatten(),
+    }
+}
+
+// Fill in the summary, places and/or image (as `options` asks) from each
+// composer's article. Fetch failures are logged and leave them empty.
+async fn fetch_composer_pages(
+    composers: &mut [Composer],
+    http: &HttpOptions,
+    options: &ComposerOptions,
+) -> Result<()> {
+    let client = build_client(http)?;
+    let throttle = Arc::new(Throttle::new(options.article_limit));
+    let mut tasks = JoinSet::new();
+
+    info!("Fetching articles for {} composers", composers.len());
+
+    for (index, composer) in composers.iter().enumerate() {
+        let client = client.clone();
+        let http = http.clone();
+        let url = composer.url.clone();
+        let throttle = throttle.clone();
+        let options = options.clone();
+        tasks.spawn(async move {
+            let _permit = throttle.acquire().await;
+            let details = match fetch_html(&client, &url, &http).await {
+                Ok(Some(html)) => composer_page_details(&html, &options),
+                Ok(None) => {
+                    warn!("Composer page {} not found", url);
+                    ComposerPageDetails::default()
+                }
+                Err(e) => {
+                    warn!("Error fetching composer page {}: {}", url, e);
+                    ComposerPageDetails::default()
+                }
+            };
+            (index, details)
+        });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok((index, details)) => {
+                let composer = &mut composers[index];
+                composer.summary = details.summary;
+                composer.birth_place = details.birth_place;
+                composer.death_place = details.death_place;
+                composer.image_url = details.image_url;
+            }
+            Err(e) => warn!("Composer page task failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+// Composers from `scraped` whose URL isn't already in `existing` (or earlier
+// in `scraped`), in scraped order.
+fn new_composers(existing: &[Composer], scraped: Vec<Composer>) -> Vec<Composer> {
+    let mut seen_urls: HashSet<String> = existing.iter().map(|c| c.url.clone()).collect();
+    scraped
+        .into_iter()
+        .filter(|composer| seen_urls.insert(composer.url.clone()))
+        .collect()
+}
+
 async fn write_composers_via_channel(
     composers: Vec<Composer>,
     filename: &str,
@@ -114,7 +337,8 @@ async fn write_composers_via_channel(
 // }
 
 fn parse_year_range(s: &str, approximate: bool, flourished: bool) -> Option<ParsedYears> {
-    let re = regex::Regex::new(r"(?i)(\d{3,4})\s*[-–]\s*(\d{3,4})").ok()?;
+    let s = &normalize_dashes(s);
+    let re = regex::Regex::new(r"(?i)(\d{3,4})\s*-\s*(\d{3,4})").ok()?;
     if let Some(caps) = re.captures(s) {
         let birth = caps.get(1)?.as_str().parse::<i32>().ok()?;
         let death = caps.get(2)?.as_str().parse::<i32>().ok()?;
@@ -123,10 +347,12 @@ fn parse_year_range(s: &str, approximate: bool, flourished: bool) -> Option<Pars
             death_year: Some(death),
             approximate,
             flourished,
+            alive: false,
         })
     } else {
-        // Try single year
+        // Try single year; "1932–" with nothing after the dash is open-ended
         let re_single = regex::Regex::new(r"(?i)(\d{3,4})").ok()?;
+        let re_open_ended = regex::Regex::new(r"(\d{3,4})\s*-\s*$").ok()?;
         if let Some(cap) = re_single.captures(s) {
             let birth = cap.get(1)?.as_str().parse::<i32>().ok()?;
             Some(ParsedYears {
@@ -134,6 +360,7 @@ fn parse_year_range(s: &str, approximate: bool, flourished: bool) -> Option<Pars
                 death_year: None,
                 approximate,
                 flourished,
+                alive: re_open_ended.is_match(s),
             })
         } else {
             None
@@ -157,13 +384,19 @@ fn extract_years_from_parentheses(text: &str) -> Option<ParsedYears> {
                 s if s.starts_with("fl.") || s.starts_with("fl ") => {
                     parse_year_range(&normalized, false, true)
                 }
-                s if s.starts_with("born ") => {
-                    let year = s[5..].trim().parse::<i32>().ok()?;
+                s if s.starts_with("born ") || s.starts_with("b. ") => {
+                    let year = s
+                        .trim_start_matches("born ")
+                        .trim_start_matches("b. ")
+                        .trim()
+                        .parse::<i32>()
+                        .ok()?;
                     Some(ParsedYears {
                         birth_year: Some(year),
                         death_year: None,
                         approximate: false,
                         flourished: false,
+                        alive: true,
                     })
                 }
                 s => parse_year_range(s, false, false),
@@ -176,10 +409,115 @@ fn extract_years_from_parentheses(text: &str) -> Option<ParsedYears> {
     }
 }
 
-async fn read_parse(url: &str) -> Result<Vec<Composer>> {
-    let response = reqwest::get(url).await?;
-    let html = response.text().await?;
-    let document = Html::parse_document(&html);
+fn years_qualifier(years: &ParsedYears) -> QualityOfYearInfo {
+    if years.approximate {
+        QualityOfYearInfo::Approximate
+    } else if years.flourished {
+        QualityOfYearInfo::Flourished
+    } else if years.alive && years.birth_year.is_some() {
+        QualityOfYearInfo::AliveToday
+    } else if years.death_year.is_none() {
+        QualityOfYearInfo::YearInfoUnknown
+    } else {
+        QualityOfYearInfo::Exact
+    }
+}
+
+// Build an index sort key by moving the surname (the last token, ignoring
+// generational suffixes) to the front. Particles stay with the given names,
+// the way Wikipedia sorts them: "Ludwig van Beethoven" becomes
+// "Beethoven, Ludwig van". Single names and "X of Y" bynames such as
+// "Hildegard of Bingen" are left as they are.
+fn sort_name(full_name: &str) -> String {
+    let name = full_name
+        .split(" (")
+        .next()
+        .unwrap_or(full_name)
+        .trim()
+        .to_string();
+    let tokens: Vec<&str> = name.split_whitespace().collect();
+
+    if tokens.len() < 2 || tokens.iter().any(|t| t.eq_ignore_ascii_case("of")) {
+        return name;
+    }
+
+    let is_suffix = |token: &str| {
+        matches!(
+            token.trim_end_matches(','),
+            "Jr." | "Sr." | "II" | "III" | "IV"
+        )
+    };
+    let surname_index = match tokens.iter().rposition(|t| !is_suffix(t)) {
+        Some(0) | None => return name,
+        Some(index) => index,
+    };
+
+    let rest: Vec<&str> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != surname_index)
+        .map(|(_, token)| token.trim_end_matches(','))
+        .collect();
+
+    format!("{}, {}", tokens[surname_index], rest.join(" "))
+}
+
+// Earliest year we accept as a real birth/death year; anything before this
+// is almost certainly a stray number picked up by the regex.
+const EARLIEST_PLAUSIBLE_YEAR: i32 = 800;
+
+fn current_year() -> i32 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    1970 + (secs / 31_556_952) as i32
+}
+
+// Sanity-check parsed years: values outside the plausible range are cleared,
+// and a death year before the birth year is dropped with the record marked
+// approximate. Returns true if anything was adjusted.
+fn check_year_consistency(name: &str, years: &mut ParsedYears) -> bool {
+    let plausible = EARLIEST_PLAUSIBLE_YEAR..=current_year();
+    let mut adjusted = false;
+
+    if let Some(birth) = years.birth_year
+        && !plausible.contains(&birth)
+    {
+        warn!("{}: implausible birth year {}, clearing it", name, birth);
+        years.birth_year = None;
+        adjusted = true;
+    }
+    if let Some(death) = years.death_year
+        && !plausible.contains(&death)
+    {
+        warn!("{}: implausible death year {}, clearing it", name, death);
+        years.death_year = None;
+        adjusted = true;
+    }
+    if let (Some(birth), Some(death)) = (years.birth_year, years.death_year)
+        && death < birth
+    {
+        warn!(
+            "{}: death year {} is before birth year {}, keeping birth year only",
+            name, death, birth
+        );
+        years.death_year = None;
+        years.approximate = true;
+        adjusted = true;
+    }
+
+    adjusted
+}
+
+pub const DEFAULT_COMPOSER_LIST_URL: &str =
+    "https://en.wikipedia.org/wiki/List_of_composers_by_name";
+
+// Upper bound on pages followed from one list URL, in case pagination loops
+// through URLs that differ only in tracking parameters.
+const MAX_LIST_PAGES: usize = 500;
+
+fn parse_composers(document: &Html) -> Vec<Composer> {
     let li_selector = Selector::parse("li").unwrap();
     let a_selector = Selector::parse("a[href^=\"/wiki\"][title]").unwrap();
 
@@ -187,93 +525,96 @@ async fn read_parse(url: &str) -> Result<Vec<Composer>> {
         .select(&li_selector)
         .filter_map(|li_element| {
             // Find the anchor tag with href starting with "/wiki" and title attribute
-            if let Some(anchor) = li_element.select(&a_selector).next() {
-                if let (Some(title), Some(href)) =
+            if let Some(anchor) = li_element.select(&a_selector).next()
+                && let (Some(title), Some(href)) =
                     (anchor.value().attr("title"), anchor.value().attr("href"))
-                {
-                    // let anchor_text = anchor.text().collect::<String>();
-
-                    // // Check if title matches the anchor text content
-                    // if title == anchor_text {
-                    //     // Extract years info from parentheses in the li element
-                    //     let li_text = li_element.text().collect::<String>();
-                    //     let years_info = extract_years_from_parentheses(&li_text).unwrap();
-
-                    //     let birth_year = years_info.birth_year;
-                    //     let death_year = years_info.death_year;
-
-                    //     let years_qualifier = if years_info.approximate {
-                    //         QualityOfYearInfo::Approximate
-                    //     } else if years_info.flourished {
-                    //         QualityOfYearInfo::Flourished
-                    //     } else if years_info.death_year.is_none() {
-                    //         QualityOfYearInfo::AliveToday
-                    //     } else {
-                    //         QualityOfYearInfo::Exact
-                    //     };
-
-                    //     let list_of_compositions_url =
-                    //         format!("/wiki/List_of_compositions_by_{}", title.to_string())
-                    //             .replace(" ", "_");
-
-                    //     return Some(Composer {
-                    //         full_name: title.to_string(),
-                    //         birth_year,
-                    //         death_year,
-                    //         years_qualifier,
-                    //         url: href.to_string(),
-                    //         list_of_compositions_url,
-                    //     });
-                    // }
-
-                    let anchor_text = anchor.text().collect::<String>();
-
-                    // Check if title matches the anchor text content
-                    if title == anchor_text {
-                        // Extract years info from parentheses in the li element
-                        let li_text = li_element.text().collect::<String>();
-
-                        if let Some(years_info) = extract_years_from_parentheses(&li_text) {
-                            let birth_year = years_info.birth_year;
-                            let death_year = years_info.death_year;
-
-                            let years_qualifier = if years_info.approximate {
-                                QualityOfYearInfo::Approximate
-                            } else if years_info.flourished {
-                                QualityOfYearInfo::Flourished
-                            } else if years_info.death_year.is_none() {
-                                QualityOfYearInfo::AliveToday
-                            } else {
-                                QualityOfYearInfo::Exact
-                            };
-
-                            let list_of_compositions_url =
-                                format!("/wiki/List_of_compositions_by_{}", title.to_string())
-                                    .replace(" ", "_");
-
-                            return Some(Composer {
-                                full_name: title.to_string(),
-                                birth_year,
-                                death_year,
-                                years_qualifier,
-                                url: href.to_string(),
-                                list_of_compositions_url,
-                            });
-                        } else {
-                            // Handle composers without year information
-                            let list_of_compositions_url =
-                                format!("/wiki/List_of_compositions_by_{}", title.to_string())
-                                    .replace(" ", "_");
-
-                            return Some(Composer {
-                                full_name: title.to_string(),
-                                birth_year: None,
-                                death_year: None,
-                                years_qualifier: QualityOfYearInfo::AliveToday, // Default assumption
-                                url: href.to_string(),
-                                list_of_compositions_url,
-                            });
-                        }
+            {
+                // let anchor_text = anchor.text().collect::<String>();
+
+                // // Check if title matches the anchor text content
+                // if title == anchor_text {
+                //     // Extract years info from parentheses in the li element
+                //     let li_text = li_element.text().collect::<String>();
+                //     let years_info = extract_years_from_parentheses(&li_text).unwrap();
+
+                //     let birth_year = years_info.birth_year;
+                //     let death_year = years_info.death_year;
+
+                //     let years_qualifier = if years_info.approximate {
+                //         QualityOfYearInfo::Approximate
+                //     } else if years_info.flourished {
+                //         QualityOfYearInfo::Flourished
+                //     } else if years_info.death_year.is_none() {
+                //         QualityOfYearInfo::AliveToday
+                //     } else {
+                //         QualityOfYearInfo::Exact
+                //     };
+
+                //     let list_of_compositions_url =
+                //         format!("/wiki/List_of_compositions_by_{}", title)
+                //             .replace(" ", "_");
+
+                //     return Some(Composer {
+                //         full_name: title.to_string(),
+                //         birth_year,
+                //         death_year,
+                //         years_qualifier,
+                //         url: href.to_string(),
+                //         list_of_compositions_url,
+                //     });
+                // }
+
+                let anchor_text = anchor.text().collect::<String>();
+
+                // Check if title matches the anchor text content
+                if title == anchor_text {
+                    // Extract years info from parentheses in the li element
+                    let li_text = li_element.text().collect::<String>();
+
+                    if let Some(mut years_info) = extract_years_from_parentheses(&li_text) {
+                        check_year_consistency(title, &mut years_info);
+
+                        let birth_year = years_info.birth_year;
+                        let death_year = years_info.death_year;
+
+                        let years_qualifier = years_qualifier(&years_info);
+
+                        let list_of_compositions_url =
+                            format!("/wiki/List_of_compositions_by_{}", wiki_slug(title));
+
+                        return Some(Composer {
+                            full_name: title.to_string(),
+                            sort_name: sort_name(title),
+                            birth_year,
+                            death_year,
+                            years_qualifier,
+                            url: href.to_string(),
+                            list_of_compositions_url,
+                            summary: None,
+                            birth_place: None,
+                            death_place: None,
+                            nationality: None,
+                            image_url: None,
+                        });
+                    } else {
+                        // Handle composers without year information
+                        let list_of_compositions_url =
+                            format!("/wiki/List_of_compositions_by_{}", wiki_slug(title));
+
+                        return Some(Composer {
+                            full_name: title.to_string(),
+                            sort_name: sort_name(title),
+                            birth_year: None,
+                            death_year: None,
+                            years_qualifier: QualityOfYearInfo::YearInfoUnknown,
+                            url: href.to_string(),
+                            list_of_compositions_url,
+                            summary: None,
+                            birth_place: None,
+                            death_place: None,
+                            nationality: None,
+                            image_url: None,
+                        });
                     }
                 }
             }
@@ -283,17 +624,266 @@ async fn read_parse(url: &str) -> Result<Vec<Composer>> {
 
     // <li><a href="/wiki/Clamor_Heinrich_Abel" title="Clamor Heinrich Abel">Clamor Heinrich Abel</a> (1634–1696)</li>
 
+    composers
+}
+
+// Columns of a table-layout composer list, by header
+struct ComposerTableColumns {
+    name: usize,
+    // One "Dates"/"Lifetime" column, or separate "Born"/"Died" ones
+    dates: Option<usize>,
+    born: Option<usize>,
+    died: Option<usize>,
+    birth_place: Option<usize>,
+    death_place: Option<usize>,
+    nationality: Option<usize>,
+}
+
+impl ComposerTableColumns {
+    // Whole words only, and place columns are told apart from date ones,
+    // so "Birthplace" or "Birth place" is never read as the birth year
+    fn from_headers(headers: &[String]) -> Option<Self> {
+        let find = |pattern: &str, taken: &[Option<usize>]| {
+            let regex = Regex::new(pattern).unwrap();
+            headers
+                .iter()
+                .enumerate()
+                .position(|(index, header)| regex.is_match(header) && !taken.contains(&Some(index)))
+        };
+        let birth_place = find(r"(?i)^(birth\s*place|place of birth)\b", &[]);
+        let death_place = find(r"(?i)^(death\s*place|place of death)\b", &[]);
+        let places = [birth_place, death_place];
+        let columns = Self {
+            name: find(r"(?i)^(composer|name)\b", &[])?,
+            dates: find(r"(?i)^(dates|years|lifetime|life)\b", &[]),
+            born: find(r"(?i)^(born|birth)\b", &places),
+            died: find(r"(?i)^(died|death)\b", &places),
+            birth_place,
+            death_place,
+            nationality: find(r"(?i)^(nationality|country|origin)\b", &[]),
+        };
+        (columns.dates.is_some() || columns.born.is_some()).then_some(columns)
+    }
+}
+
+// Sortable tables whose header row names a composer column and a dates (or
+// born) column: the layout some composer index pages use instead of <li>s.
+// Columns are counted over th and td cells alike, as the data rows are,
+// since name cells are often <th scope="row">.
+fn composer_tables(document: &Html) -> Vec<(scraper::ElementRef<'_>, ComposerTableColumns)> {
+    let table_selector = Selector::parse("table.wikitable").unwrap();
+    let tr_selector = Selector::parse("tr").unwrap();
+    let th_selector = Selector::parse("th").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+
+    document
+        .select(&table_selector)
+        .filter_map(|table| {
+            let header_row = table
+                .select(&tr_selector)
+                .find(|row| row.select(&th_selector).next().is_some())?;
+            let headers: Vec<String> = header_row
+                .select(&cell_selector)
+                .map(|cell| cell.text().collect::<String>().trim().to_string())
+                .collect();
+            ComposerTableColumns::from_headers(&headers).map(|columns| (table, columns))
+        })
+        .collect()
+}
+
+fn parse_composer_tables(document: &Html) -> Vec<Composer> {
+    let tr_selector = Selector::parse("tr").unwrap();
+    let td_selector = Selector::parse("td").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let a_selector = Selector::parse("a[href^=\"/wiki\"][title]").unwrap();
+    let mut composers = Vec::new();
+
+    for (table, columns) in composer_tables(document) {
+        for row in table.select(&tr_selector) {
+            // Header rows have no data cells
+            if row.select(&td_selector).next().is_none() {
+                continue;
+            }
+            let cells: Vec<_> = row.select(&cell_selector).collect();
+            let text = |index: Option<usize>| {
+                index
+                    .and_then(|index| cells.get(index))
+                    .map(|cell| cell.text().collect::<String>().trim().to_string())
+                    .filter(|text| !text.is_empty())
+            };
+            let Some(anchor) = cells
+                .get(columns.name)
+                .and_then(|cell| cell.select(&a_selector).next())
+            else {
+                continue;
+            };
+            let (Some(title), Some(href)) =
+                (anchor.value().attr("title"), anchor.value().attr("href"))
+            else {
+                continue;
+            };
+
+            // Put the dates in the "(...)" form the list parser reads
+            let years_text = match (text(columns.dates), text(columns.born), text(columns.died)) {
+                (Some(dates), _, _) => Some(format!("({})", dates)),
+                (None, Some(born), Some(died)) => Some(format!("({}–{})", born, died)),
+                (None, Some(born), None) => Some(format!("(born {})", born)),
+                (None, None, _) => None,
+            };
+            let mut years = years_text
+                .as_deref()
+                .and_then(extract_years_from_parentheses);
+            if let Some(years) = &mut years {
+                check_year_consistency(title, years);
+            }
+
+            composers.push(Composer {
+                full_name: title.to_string(),
+                sort_name: sort_name(title),
+                birth_year: years.as_ref().and_then(|y| y.birth_year),
+                death_year: years.as_ref().and_then(|y| y.death_year),
+                years_qualifier: years
+                    .as_ref()
+                    .map_or(QualityOfYearInfo::YearInfoUnknown, years_qualifier),
+                url: href.to_string(),
+                list_of_compositions_url: format!(
+                    "/wiki/List_of_compositions_by_{}",
+                    wiki_slug(title)
+                ),
+                summary: None,
+                birth_place: text(columns.birth_place),
+                death_place: text(columns.death_place),
+                nationality: text(columns.nationality),
+                image_url: None,
+            });
+        }
+    }
+    composers
+}
+
+// Composer index pages come as bulleted lists or, on some pages, as a
+// sortable table with name, dates and nationality columns
+fn parse_composer_page(document: &Html) -> Vec<Composer> {
+    if composer_tables(document).is_empty() {
+        parse_composers(document)
+    } else {
+        parse_composer_tables(document)
+    }
+}
+
+// Category listings and paged indexes link onward with a "next page" anchor,
+// e.g. <a href="/w/index.php?title=Category:...&pagefrom=B">next page</a>.
+pub(crate) fn find_next_page_url(document: &Html, current_url: &str) -> Option<String> {
+    let a_selector = Selector::parse("a[href]").unwrap();
+
+    let href = document
+        .select(&a_selector)
+        .find(|a| {
+            a.text()
+                .collect::<String>()
+                .trim()
+                .eq_ignore_ascii_case("next page")
+        })?
+        .value()
+        .attr("href")?;
+
+    reqwest::Url::parse(current_url)
+        .ok()?
+        .join(href)
+        .ok()
+        .map(|url| url.to_string())
+}
+
+// Resolve a "/wiki/..." href against the page it was found on, so stored URLs
+// are absolute like the ones in RawCompositionData. Unparseable input is kept.
+fn absolute_url(page_url: &str, href: &str) -> String {
+    reqwest::Url::parse(page_url)
+        .and_then(|base| base.join(href))
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| href.to_string())
+}
+
+// Fetch the composer list starting at `url`, following "next page" links and
+// de-duplicating composers (by URL) across pages.
+async fn read_parse(url: &str, http: &HttpOptions) -> Result<Vec<Composer>> {
+    let client = build_client(http)?;
+    let mut composers = Vec::new();
+    let mut seen_urls = HashSet::new();
+    let mut visited_pages = HashSet::new();
+    let mut next_url = Some(url.to_string());
+
+    while let Some(page_url) = next_url.take() {
+        if !visited_pages.insert(page_url.clone()) || visited_pages.len() > MAX_LIST_PAGES {
+            break;
+        }
+
+        info!("Fetching composer list page {}", page_url);
+        let response = get_with_retry(&client, &page_url, http).await?;
+        let html = response.error_for_status()?.text().await?;
+        let document = Html::parse_document(&html);
+
+        for mut composer in parse_composer_page(&document) {
+            composer.url = absolute_url(&page_url, &composer.url);
+            composer.list_of_compositions_url =
+                absolute_url(&page_url, &composer.list_of_compositions_url);
+            if seen_urls.insert(composer.url.clone()) {
+                composers.push(composer);
+            }
+        }
+
+        next_url = find_next_page_url(&document, &page_url);
+    }
+
     Ok(composers)
 }
 
-pub async fn get_composers() {
-    let url = "https://en.wikipedia.org/wiki/List_of_composers_by_name";
+// With `append`, composers already in composers.json (by URL) are skipped and
+// only new ones are added, e.g. when scraping a supplemental list.
+pub async fn get_composers(url: &str, http: &HttpOptions, options: &ComposerOptions) {
     let jsonl_output_filename = "composers.json";
 
-    match read_parse(url).await {
-        Ok(composers) => {
+    match read_parse(url, http).await {
+        Ok(mut composers) => {
             info!("Found {} <li> elements:", composers.len());
 
+            if options.append {
+                let existing = if tokio::fs::try_exists(jsonl_output_filename)
+                    .await
+                    .unwrap_or(false)
+                {
+                    match read_composers(jsonl_output_filename).await {
+                        Ok(existing) => existing,
+                        Err(e) => {
+                            error!("Error reading {}: {}", jsonl_output_filename, e);
+                            return;
+                        }
+                    }
+                } else {
+                    Vec::new()
+                };
+                // Older files stored relative "/wiki/..." URLs
+                let existing: Vec<Composer> = existing
+                    .into_iter()
+                    .map(|mut composer| {
+                        composer.url = absolute_url(url, &composer.url);
+                        composer
+                    })
+                    .collect();
+                composers = new_composers(&existing, composers);
+                info!(
+                    "{} composers already in {}, appending {} new",
+                    existing.len(),
+                    jsonl_output_filename,
+                    composers.len()
+                );
+            }
+
+            if (options.summary_max_chars.is_some() || options.places || options.images)
+                && let Err(e) = fetch_composer_pages(&mut composers, http, options).await
+            {
+                error!("Error fetching composer articles: {}", e);
+            }
+
             if let Err(e) =
                 write_composers_via_channel(composers.clone(), jsonl_output_filename).await
             {
@@ -326,3 +916,336 @@ pub async fn get_composers() {
         Err(e) => error!("Error fetching li elements: {}", e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn death_before_birth_is_dropped_and_marked_approximate() {
+        let mut years = extract_years_from_parentheses("Jane Doe (1750–1685)").unwrap();
+
+        assert!(check_year_consistency("Jane Doe", &mut years));
+        assert_eq!(years.birth_year, Some(1750));
+        assert_eq!(years.death_year, None);
+        assert!(years.approximate);
+    }
+
+    #[test]
+    fn implausible_years_are_cleared() {
+        let mut years = extract_years_from_parentheses("John Doe (250–1790)").unwrap();
+
+        assert!(check_year_consistency("John Doe", &mut years));
+        assert_eq!(years.birth_year, None);
+        assert_eq!(years.death_year, Some(1790));
+    }
+
+    #[test]
+    fn consistent_years_are_left_alone() {
+        let mut years = extract_years_from_parentheses("J. S. Bach (1685–1750)").unwrap();
+
+        assert!(!check_year_consistency("J. S. Bach", &mut years));
+        assert_eq!(years.birth_year, Some(1685));
+        assert_eq!(years.death_year, Some(1750));
+    }
+
+    #[test]
+    fn year_ranges_parse_with_any_dash() {
+        for dash in ["-", "\u{2013}", "\u{2014}", "\u{2212}"] {
+            let years =
+                extract_years_from_parentheses(&format!("J. S. Bach (1685{dash}1750)")).unwrap();
+            assert_eq!(years.birth_year, Some(1685), "dash {dash:?}");
+            assert_eq!(years.death_year, Some(1750), "dash {dash:?}");
+
+            let years =
+                extract_years_from_parentheses(&format!("John Williams (1932{dash})")).unwrap();
+            assert!(years.alive, "dash {dash:?}");
+        }
+    }
+
+    #[test]
+    fn only_explicit_markers_count_as_alive_today() {
+        let qualifier =
+            |text: &str| years_qualifier(&extract_years_from_parentheses(text).unwrap());
+
+        assert_eq!(
+            qualifier("Philip Glass (born 1937)"),
+            QualityOfYearInfo::AliveToday
+        );
+        assert_eq!(
+            qualifier("Arvo Pärt (b. 1935)"),
+            QualityOfYearInfo::AliveToday
+        );
+        assert_eq!(
+            qualifier("John Williams (1932–)"),
+            QualityOfYearInfo::AliveToday
+        );
+        assert_eq!(
+            qualifier("Someone (1685)"),
+            QualityOfYearInfo::YearInfoUnknown
+        );
+        assert_eq!(
+            qualifier("J. S. Bach (1685–1750)"),
+            QualityOfYearInfo::Exact
+        );
+    }
+
+    #[test]
+    fn sort_name_moves_surname_first_and_keeps_particles() {
+        assert_eq!(sort_name("Johann Sebastian Bach"), "Bach, Johann Sebastian");
+        assert_eq!(sort_name("Ludwig van Beethoven"), "Beethoven, Ludwig van");
+        assert_eq!(sort_name("Carl Maria von Weber"), "Weber, Carl Maria von");
+        assert_eq!(sort_name("Johann Strauss II"), "Strauss, Johann II");
+        assert_eq!(sort_name("John Adams (composer)"), "Adams, John");
+    }
+
+    #[test]
+    fn sort_name_leaves_single_names_and_bynames_alone() {
+        assert_eq!(sort_name("Pérotin"), "Pérotin");
+        assert_eq!(sort_name("Hildegard of Bingen"), "Hildegard of Bingen");
+    }
+
+    #[test]
+    fn composers_without_years_are_unknown_not_alive() {
+        let document = Html::parse_document(
+            r#"<ul><li><a href="/wiki/Anonymous_4" title="Anonymous 4">Anonymous 4</a></li></ul>"#,
+        );
+
+        let composers = parse_composers(&document);
+
+        assert_eq!(composers.len(), 1);
+        assert_eq!(
+            composers[0].years_qualifier,
+            QualityOfYearInfo::YearInfoUnknown
+        );
+    }
+
+    #[test]
+    fn parses_table_layout_composer_lists() {
+        let html = r#"<html><body>
+            <ul><li><a href="/wiki/Main_Page" title="Main Page">Main Page</a></li></ul>
+            <table class="wikitable sortable">
+              <tr><th>Name</th><th>Dates</th><th>Nationality</th><th>Notes</th></tr>
+              <tr><td><a href="/wiki/Johann_Pachelbel" title="Johann Pachelbel">Johann Pachelbel</a></td><td>1653–1706</td><td>German</td><td></td></tr>
+              <tr><td><a href="/wiki/Barbara_Strozzi" title="Barbara Strozzi">Barbara Strozzi</a></td><td>c. 1619–1677</td><td>Italian</td><td>Singer</td></tr>
+              <tr><td><a href="/wiki/Arvo_P%C3%A4rt" title="Arvo Pärt">Arvo Pärt</a></td><td>born 1935</td><td>Estonian</td><td></td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Composer</th><th>Born</th><th>Died</th></tr>
+              <tr><td><a href="/wiki/Henry_Purcell" title="Henry Purcell">Henry Purcell</a></td><td>1659</td><td>1695</td></tr>
+            </table>
+            </body></html>"#;
+
+        let composers = parse_composer_page(&Html::parse_document(html));
+        let names: Vec<&str> = composers.iter().map(|c| c.full_name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Johann Pachelbel",
+                "Barbara Strozzi",
+                "Arvo Pärt",
+                "Henry Purcell"
+            ]
+        );
+
+        let pachelbel = &composers[0];
+        assert_eq!(pachelbel.url, "/wiki/Johann_Pachelbel");
+        assert_eq!(pachelbel.sort_name, "Pachelbel, Johann");
+        assert_eq!(
+            (pachelbel.birth_year, pachelbel.death_year),
+            (Some(1653), Some(1706))
+        );
+        assert_eq!(pachelbel.years_qualifier, QualityOfYearInfo::Exact);
+        assert_eq!(pachelbel.nationality.as_deref(), Some("German"));
+
+        assert_eq!(composers[1].years_qualifier, QualityOfYearInfo::Approximate);
+        assert_eq!(composers[2].birth_year, Some(1935));
+        assert_eq!(composers[2].years_qualifier, QualityOfYearInfo::AliveToday);
+        assert_eq!(
+            (composers[3].birth_year, composers[3].death_year),
+            (Some(1659), Some(1695))
+        );
+        assert_eq!(composers[3].nationality, None);
+
+        // Name cells as row headers, and place columns beside the dates
+        let html = r#"<html><body>
+            <table class="wikitable sortable">
+              <tr><th>Composer</th><th>Birthplace</th><th>Born</th><th>Deathplace</th><th>Died</th></tr>
+              <tr><th scope="row"><a href="/wiki/Fanny_Mendelssohn" title="Fanny Mendelssohn">Fanny Mendelssohn</a></th><td>Hamburg</td><td>1805</td><td>Berlin</td><td>1847</td></tr>
+            </table>
+            </body></html>"#;
+        let composers = parse_composer_page(&Html::parse_document(html));
+        assert_eq!(composers.len(), 1);
+        assert_eq!(
+            (composers[0].birth_year, composers[0].death_year),
+            (Some(1805), Some(1847))
+        );
+        assert_eq!(composers[0].birth_place.as_deref(), Some("Hamburg"));
+        assert_eq!(composers[0].death_place.as_deref(), Some("Berlin"));
+
+        // Bulleted pages still go through the list parser
+        let list = r#"<ul><li><a href="/wiki/Clamor_Heinrich_Abel" title="Clamor Heinrich Abel">Clamor Heinrich Abel</a> (1634–1696)</li></ul>"#;
+        let composers = parse_composer_page(&Html::parse_document(list));
+        assert_eq!(composers[0].birth_year, Some(1634));
+    }
+
+    #[test]
+    fn extracts_the_lead_paragraph_without_citations() {
+        // Trimmed from the article "Clara Schumann"
+        let html = r##"<html><body><div class="mw-parser-output">
+            <div role="note" class="hatnote">For the German banknote, see ...</div>
+            <table class="infobox"><tr><td><p>Clara Schumann in 1853</p></td></tr></table>
+            <p class="mw-empty-elt">
+            </p>
+            <p><b>Clara Josephine Schumann</b> (<a href="/wiki/N%C3%A9e">née</a> <b>Wieck</b>; 13 September 1819 – 20 May 1896) was a German pianist, composer and piano teacher.<sup id="cite_ref-1" class="reference"><a href="#cite_note-1">[1]</a></sup> Regarded as one of the most distinguished pianists of the Romantic era,<sup class="noprint Inline-Template">[<i>citation needed</i>]</sup> she exerted her influence over a 61-year concert career.</p>
+            <p>Second paragraph.</p>
+            </div></body></html>"##;
+
+        let summary = lead_paragraph(&Html::parse_document(html)).unwrap();
+        assert_eq!(
+            summary,
+            "Clara Josephine Schumann (née Wieck; 13 September 1819 – 20 May 1896) was a German \
+             pianist, composer and piano teacher. Regarded as one of the most distinguished \
+             pianists of the Romantic era, she exerted her influence over a 61-year concert career."
+        );
+        assert_eq!(
+            truncate_summary(&summary, 60),
+            "Clara Josephine Schumann (née Wieck; 13 September 1819 –…"
+        );
+        // Counted in characters: no space to cut at, so the cut falls
+        // between two CJK characters
+        assert_eq!(
+            truncate_summary("武満徹は日本の作曲家である。", 6),
+            "武満徹は日…"
+        );
+    }
+
+    #[test]
+    fn composers_round_trip_with_lowercase_year_qualifiers() {
+        let composer = Composer {
+            url: "https://en.wikipedia.org/wiki/Arvo_P%C3%A4rt".to_string(),
+            full_name: "Arvo Pärt".to_string(),
+            sort_name: "Pärt, Arvo".to_string(),
+            list_of_compositions_url:
+                "https://en.wikipedia.org/wiki/List_of_compositions_by_Arvo_P%C3%A4rt".to_string(),
+            birth_year: Some(1935),
+            death_year: None,
+            years_qualifier: QualityOfYearInfo::AliveToday,
+            summary: None,
+            birth_place: None,
+            death_place: None,
+            nationality: None,
+            image_url: None,
+        };
+
+        let json = serde_json::to_string(&composer).unwrap();
+        assert!(json.contains(r#""years_qualifier":"alive_today""#));
+        assert_eq!(serde_json::from_str::<Composer>(&json).unwrap(), composer);
+
+        let names: Vec<String> = [
+            QualityOfYearInfo::Exact,
+            QualityOfYearInfo::Approximate,
+            QualityOfYearInfo::Flourished,
+            QualityOfYearInfo::YearInfoUnknown,
+        ]
+        .iter()
+        .map(|q| serde_json::to_string(q).unwrap())
+        .collect();
+        assert_eq!(
+            names,
+            vec![
+                r#""exact""#,
+                r#""approximate""#,
+                r#""flourished""#,
+                r#""unknown""#
+            ]
+        );
+
+        // Files written before the rename still load
+        assert_eq!(
+            serde_json::from_str::<QualityOfYearInfo>(r#""YearInfoUnknown""#).unwrap(),
+            QualityOfYearInfo::YearInfoUnknown
+        );
+    }
+
+    #[test]
+    fn appending_skips_composers_already_on_file() {
+        let composer = |name: &str| Composer {
+            url: format!("/wiki/{}", name.replace(' ', "_")),
+            full_name: name.to_string(),
+            sort_name: sort_name(name),
+            list_of_compositions_url: String::new(),
+            birth_year: None,
+            death_year: None,
+            years_qualifier: QualityOfYearInfo::YearInfoUnknown,
+            summary: None,
+            birth_place: None,
+            death_place: None,
+            nationality: None,
+            image_url: None,
+        };
+        let existing = vec![composer("Carl Friedrich Abel"), composer("Samuel Barber")];
+        let scraped = vec![
+            composer("Samuel Barber"),
+            composer("Béla Bartók"),
+            composer("Carl Friedrich Abel"),
+            composer("Béla Bartók"),
+        ];
+
+        let added = new_composers(&existing, scraped);
+        let names: Vec<&str> = added.iter().map(|c| c.full_name.as_str()).collect();
+
+        assert_eq!(names, vec!["Béla Bartók"]);
+    }
+
+    #[tokio::test]
+    async fn read_parse_follows_next_page_links_and_dedupes() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let first_page = r#"<html><body><ul>
+            <li><a href="/wiki/Carl_Friedrich_Abel" title="Carl Friedrich Abel">Carl Friedrich Abel</a> (1723–1787)</li>
+            <li><a href="/wiki/Johann_Sebastian_Bach" title="Johann Sebastian Bach">Johann Sebastian Bach</a> (1685–1750)</li>
+            </ul><a href="/page2">next page</a></body></html>"#;
+        let second_page = r#"<html><body><ul>
+            <li><a href="/wiki/Johann_Sebastian_Bach" title="Johann Sebastian Bach">Johann Sebastian Bach</a> (1685–1750)</li>
+            <li><a href="/wiki/Samuel_Barber" title="Samuel Barber">Samuel Barber</a> (1910–1981)</li>
+            </ul></body></html>"#;
+        Mock::given(method("GET"))
+            .and(path("/page1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(first_page))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/page2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(second_page))
+            .mount(&server)
+            .await;
+
+        let composers = read_parse(&format!("{}/page1", server.uri()), &HttpOptions::default())
+            .await
+            .unwrap();
+        let names: Vec<&str> = composers.iter().map(|c| c.full_name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "Carl Friedrich Abel",
+                "Johann Sebastian Bach",
+                "Samuel Barber"
+            ]
+        );
+        assert_eq!(
+            composers[0].url,
+            format!("{}/wiki/Carl_Friedrich_Abel", server.uri())
+        );
+        assert_eq!(
+            composers[0].list_of_compositions_url,
+            format!(
+                "{}/wiki/List_of_compositions_by_Carl_Friedrich_Abel",
+                server.uri()
+            )
+        );
+    }
+}
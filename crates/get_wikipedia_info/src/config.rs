@@ -0,0 +1,596 @@
+// Settings from a config file and the environment, layered under the
+// command line. Precedence, lowest first:
+//
+//   built-in defaults < config file < GET_WIKIPEDIA_INFO_* env vars < flags
+//
+// The file is TOML. Top-level keys are the global flags, and a table per
+// subcommand holds that subcommand's flags, each spelled as the flag with
+// underscores for dashes:
+//
+//   timeout_secs = 60
+//
+//   [batch]
+//   composers_file = "composers.json"
+//   deep = true
+//   section_composer = ["Henry Purcell", "John Blow"]
+//
+// An env var is the same name upper-cased with the prefix, e.g.
+// GET_WIKIPEDIA_INFO_MAX_TABLES=5, and applies to whichever subcommand runs.
+//
+// Values are turned back into flags (see layered_args) so clap still does
+// all the parsing and validation. Only the TOML the config needs is read:
+// tables, strings, integers, floats, booleans and arrays; anything else is
+// an error rather than a silent misreading.
+
+use anyhow::{Context, Result, bail};
+use clap::parser::ValueSource;
+use serde_json::{Map, Value};
+
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+pub const ENV_PREFIX: &str = "GET_WIKIPEDIA_INFO_";
+
+// The flag naming the config file itself; never read from the file
+const CONFIG_ARG: &str = "config";
+
+#[derive(Debug, Default)]
+pub struct Config {
+    values: Map<String, Value>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(Self {
+            values: parse_toml(text)?,
+        })
+    }
+
+    pub async fn load(path: &str) -> Result<Self> {
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("reading config file {}", path))?;
+        Self::parse(&text).with_context(|| format!("parsing config file {}", path))
+    }
+
+    fn global(&self, key: &str) -> Option<&Value> {
+        self.values.get(key).filter(|value| !value.is_object())
+    }
+
+    fn section(&self, name: &str) -> Option<&Map<String, Value>> {
+        self.values.get(name).and_then(Value::as_object)
+    }
+
+    // Top-level keys, split into settings and [table] names
+    fn keys(&self) -> (Vec<&String>, Vec<&String>) {
+        self.values
+            .keys()
+            .partition(|key| !self.values[*key].is_object())
+    }
+}
+
+// The --config path given on the command line, or config.toml if there is
+// one in the working directory
+pub fn config_path(command: &clap::Command, argv: &[String]) -> Option<String> {
+    let matches = command.clone().ignore_errors(true).get_matches_from(argv);
+    matches
+        .try_get_one::<String>(CONFIG_ARG)
+        .ok()
+        .flatten()
+        .cloned()
+        .or_else(|| {
+            std::path::Path::new(DEFAULT_CONFIG_FILE)
+                .exists()
+                .then(|| DEFAULT_CONFIG_FILE.to_string())
+        })
+}
+
+// argv with flags appended for every setting that the config file or
+// environment provides and the command line doesn't. Top-level keys that
+// match no global flag, tables that name no subcommand and keys in any
+// subcommand's table that match no flag are all errors, so typos don't
+// pass silently, even in a section this run doesn't use.
+pub fn layered_args(
+    command: &clap::Command,
+    argv: Vec<String>,
+    config: &Config,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<String>> {
+    let matches = command.clone().ignore_errors(true).get_matches_from(&argv);
+    let Some((name, sub_matches)) = matches.subcommand() else {
+        // Nothing to layer under; let clap report the missing subcommand
+        return Ok(argv);
+    };
+    let subcommand = command
+        .find_subcommand(name)
+        .expect("matched subcommand is defined");
+
+    let (global_keys, tables) = config.keys();
+    for key in global_keys {
+        if !command
+            .get_arguments()
+            .any(|arg| arg.is_global_set() && setting_name(arg) == *key)
+        {
+            bail!("Unknown global setting {}", key);
+        }
+    }
+    for table in tables {
+        let Some(table_command) = command.find_subcommand(table) else {
+            bail!("Unknown section [{}]", table);
+        };
+        let section = config
+            .section(table)
+            .into_iter()
+            .flat_map(|section| section.keys());
+        for key in section {
+            if !table_command
+                .get_arguments()
+                .any(|arg| setting_name(arg) == *key)
+            {
+                bail!("Unknown setting {} in [{}]", key, table);
+            }
+        }
+    }
+
+    let mut extra = Vec::new();
+    let global_args = command.get_arguments().filter(|arg| arg.is_global_set());
+    for arg in global_args {
+        let value = env_value(arg, &env).or_else(|| config.global(&setting_name(arg)).cloned());
+        push_flag(&mut extra, arg, sub_matches, value)?;
+    }
+
+    let section = config.section(name);
+    for arg in subcommand.get_arguments() {
+        let value = env_value(arg, &env).or_else(|| {
+            section
+                .and_then(|section| section.get(&setting_name(arg)))
+                .cloned()
+        });
+        push_flag(&mut extra, arg, sub_matches, value)?;
+    }
+
+    Ok(argv.into_iter().chain(extra).collect())
+}
+
+// "--section-composer" is set as section_composer
+fn setting_name(arg: &clap::Arg) -> String {
+    arg.get_long()
+        .unwrap_or(arg.get_id().as_str())
+        .replace('-', "_")
+}
+
+fn env_value(arg: &clap::Arg, env: &impl Fn(&str) -> Option<String>) -> Option<Value> {
+    let name = format!("{}{}", ENV_PREFIX, setting_name(arg).to_uppercase());
+    env(&name).map(Value::String)
+}
+
+fn push_flag(
+    extra: &mut Vec<String>,
+    arg: &clap::Arg,
+    matches: &clap::ArgMatches,
+    value: Option<Value>,
+) -> Result<()> {
+    let id = arg.get_id().as_str();
+    let given = matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    );
+    let (Some(long), Some(value)) = (arg.get_long(), value) else {
+        return Ok(());
+    };
+    if given || id == CONFIG_ARG {
+        return Ok(());
+    }
+
+    let flag = format!("--{}", long);
+    if !arg.get_action().takes_values() {
+        let on = match &value {
+            Value::Bool(on) => *on,
+            Value::String(text) => matches!(text.as_str(), "1" | "true" | "yes"),
+            _ => bail!("{} takes true or false", id),
+        };
+        if on {
+            extra.push(flag);
+        }
+        return Ok(());
+    }
+
+    let values = match value {
+        Value::Array(values) => values,
+        value => vec![value],
+    };
+    for value in values {
+        let text = match value {
+            Value::String(text) => text,
+            Value::Number(number) => number.to_string(),
+            Value::Bool(on) => on.to_string(),
+            _ => bail!("Unsupported value for {}", id),
+        };
+        extra.push(flag.clone());
+        extra.push(text);
+    }
+    Ok(())
+}
+
+// Top-level keys, and one object per [table]. TOML outside that subset
+// ([[arrays of tables]], inline tables, dotted keys, multi-line strings) is
+// an error naming the construct rather than being misread.
+fn parse_toml(text: &str) -> Result<Map<String, Value>> {
+    let mut root = Map::new();
+    let mut table: Option<String> = None;
+    let mut lines = text.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let mut line = strip_comment(line).trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("[[") {
+            bail!(
+                "line {}: arrays of tables ({}) are not supported",
+                line_number,
+                line
+            );
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = parse_key(name.trim()).with_context(|| format!("line {}", line_number))?;
+            root.entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            table = Some(name);
+            continue;
+        }
+
+        // An array may go on over several lines until its brackets close
+        while unclosed_brackets(&line) > 0 {
+            let Some((_, next)) = lines.next() else {
+                bail!("line {}: unterminated array", line_number);
+            };
+            line.push(' ');
+            line.push_str(strip_comment(next).trim());
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            bail!("line {}: expected key = value", line_number);
+        };
+        let key = parse_key(key.trim()).with_context(|| format!("line {}", line_number))?;
+        let value = parse_value(value.trim()).with_context(|| format!("line {}", line_number))?;
+        let values = match &table {
+            Some(name) => root
+                .get_mut(name)
+                .and_then(Value::as_object_mut)
+                .with_context(|| format!("line {}: {} is not a table", line_number, name))?,
+            None => &mut root,
+        };
+        values.insert(key, value);
+    }
+    Ok(root)
+}
+
+// A bare or quoted key; dotted keys would nest tables, which isn't read
+fn parse_key(text: &str) -> Result<String> {
+    if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        return unescape(inner);
+    }
+    if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return Ok(inner.to_string());
+    }
+    if text.contains('.') {
+        bail!("dotted keys ({}) are not supported", text);
+    }
+    Ok(text.to_string())
+}
+
+// Calls `each` with every character outside quoted strings and its byte
+// index, until it returns false. Escaped quotes in "basic" strings don't
+// end them.
+fn for_each_unquoted(text: &str, mut each: impl FnMut(usize, char) -> bool) {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (Some('"'), _) if escaped => escaped = false,
+            (Some('"'), '\\') => escaped = true,
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, c) => {
+                if !each(index, c) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut end = line.len();
+    for_each_unquoted(line, |index, c| {
+        if c == '#' {
+            end = index;
+        }
+        c != '#'
+    });
+    &line[..end]
+}
+
+// Arrays opened but not yet closed on this line
+fn unclosed_brackets(line: &str) -> i32 {
+    let mut depth = 0;
+    for_each_unquoted(line, |_, c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {}
+        }
+        true
+    });
+    depth
+}
+
+// The escapes of a TOML basic string, read left to right so "C:\\new" is
+// C:\new rather than a newline
+fn unescape(text: &str) -> Result<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('"') => unescaped.push('"'),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('b') => unescaped.push('\u{8}'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some(kind @ ('u' | 'U')) => {
+                let digits: String = chars
+                    .by_ref()
+                    .take(if kind == 'u' { 4 } else { 8 })
+                    .collect();
+                let code = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .with_context(|| format!("invalid escape \\{}{}", kind, digits))?;
+                unescaped.push(code);
+            }
+            Some(other) => bail!("invalid escape \\{}", other),
+            None => bail!("string ends in a lone backslash"),
+        }
+    }
+    Ok(unescaped)
+}
+
+fn parse_value(text: &str) -> Result<Value> {
+    if text.starts_with("\"\"\"") || text.starts_with("'''") {
+        bail!("multi-line strings are not supported");
+    }
+    if text.starts_with('{') {
+        bail!("inline tables are not supported");
+    }
+    if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        return unescape(inner).map(Value::String);
+    }
+    if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return Ok(Value::String(inner.to_string()));
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        return split_array(inner)
+            .into_iter()
+            .map(parse_value)
+            .collect::<Result<_>>()
+            .map(Value::Array);
+    }
+    match text {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    let number = text.replace('_', "");
+    if let Ok(integer) = number.parse::<i64>() {
+        return Ok(Value::from(integer));
+    }
+    if let Ok(float) = number.parse::<f64>() {
+        return Ok(Value::from(float));
+    }
+    bail!("unsupported value {}", text)
+}
+
+// Elements of an array, splitting on commas outside quotes and nested
+// arrays
+fn split_array(inner: &str) -> Vec<&str> {
+    let mut elements = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for_each_unquoted(inner, |index, c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                elements.push(inner[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+        true
+    });
+    elements.push(inner[start..].trim());
+    elements.retain(|element| !element.is_empty());
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, Parser, Subcommand};
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(subcommand)]
+        command: Command,
+        #[arg(long, global = true)]
+        config: Option<String>,
+        #[arg(long, global = true, default_value_t = 30)]
+        timeout_secs: u64,
+    }
+
+    #[derive(Subcommand)]
+    enum Command {
+        Batch {
+            #[arg(long, default_value = "composers.json")]
+            composers_file: String,
+            #[arg(long)]
+            deep: bool,
+            #[arg(long)]
+            gzip: bool,
+            #[arg(long)]
+            max_tables: Option<usize>,
+            #[arg(long, default_value_t = 2.0)]
+            deep_requests_per_second: f64,
+            #[arg(long = "section-composer")]
+            section_composers: Vec<String>,
+        },
+        Reprocess {
+            #[arg(long)]
+            raw_file: Option<String>,
+        },
+    }
+
+    const SAMPLE: &str = r#"
+        # Shared by every subcommand
+        timeout_secs = 60
+
+        [batch]
+        composers_file = "baroque.json"  # not a comment inside "quotes # here"
+        deep = true
+        gzip = false
+        max_tables = 3
+        deep_requests_per_second = 0.5
+        section_composer = ["Henry Purcell", 'John Blow']
+    "#;
+
+    fn parse(argv: &[&str], env: &[(&str, &str)]) -> Result<Cli> {
+        let argv = argv.iter().map(|arg| arg.to_string()).collect();
+        let config = Config::parse(SAMPLE)?;
+        let env = |name: &str| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        let argv = layered_args(&Cli::command(), argv, &config, env)?;
+        Ok(Cli::try_parse_from(argv)?)
+    }
+
+    #[test]
+    fn config_file_fills_in_flags_the_command_line_leaves_out() {
+        let cli = parse(&["prog", "batch"], &[]).unwrap();
+        let Command::Batch {
+            composers_file,
+            deep,
+            gzip,
+            max_tables,
+            deep_requests_per_second,
+            section_composers,
+        } = cli.command
+        else {
+            panic!("expected batch");
+        };
+        assert_eq!(cli.timeout_secs, 60);
+        assert_eq!(composers_file, "baroque.json");
+        assert!(deep);
+        assert!(!gzip);
+        assert_eq!(max_tables, Some(3));
+        assert_eq!(deep_requests_per_second, 0.5);
+        assert_eq!(section_composers, vec!["Henry Purcell", "John Blow"]);
+    }
+
+    #[test]
+    fn env_overrides_the_file_and_flags_override_both() {
+        let cli = parse(
+            &["prog", "--timeout-secs", "10", "batch", "--max-tables", "7"],
+            &[
+                ("GET_WIKIPEDIA_INFO_TIMEOUT_SECS", "20"),
+                ("GET_WIKIPEDIA_INFO_MAX_TABLES", "5"),
+                ("GET_WIKIPEDIA_INFO_COMPOSERS_FILE", "romantic.json"),
+                ("GET_WIKIPEDIA_INFO_GZIP", "true"),
+            ],
+        )
+        .unwrap();
+        let Command::Batch {
+            composers_file,
+            gzip,
+            max_tables,
+            ..
+        } = cli.command
+        else {
+            panic!("expected batch");
+        };
+        assert_eq!(cli.timeout_secs, 10);
+        assert_eq!(max_tables, Some(7));
+        assert_eq!(composers_file, "romantic.json");
+        assert!(gzip);
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        let config = Config::parse("[batch]\nmax_tabels = 3").unwrap();
+        let argv = vec!["prog".to_string(), "batch".to_string()];
+        let error = layered_args(&Cli::command(), argv, &config, |_| None).unwrap_err();
+        assert!(error.to_string().contains("max_tabels"));
+
+        for text in [
+            "timeout_sec = 60",
+            "deep = true",
+            "[bacth]\ndeep = true",
+            // Checked even though batch is what runs
+            "[reprocess]\nraw_fiel = \"raw-info-Bach.json\"",
+        ] {
+            let config = Config::parse(text).unwrap();
+            let argv = vec!["prog".to_string(), "batch".to_string()];
+            assert!(
+                layered_args(&Cli::command(), argv, &config, |_| None).is_err(),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn strings_are_unescaped_in_one_pass() {
+        let config = Config::parse("composers_file = \"C:\\\\new\\\\composers.json\"").unwrap();
+        assert_eq!(
+            config.global("composers_file"),
+            Some(&Value::String("C:\\new\\composers.json".to_string()))
+        );
+        let config = Config::parse(r#"name = "Dvo\u0159\u00e1k \"New World\"\t""#).unwrap();
+        assert_eq!(
+            config.global("name"),
+            Some(&Value::String("Dvořák \"New World\"\t".to_string()))
+        );
+    }
+
+    #[test]
+    fn arrays_may_span_lines() {
+        let config = Config::parse(
+            "[batch]\nsection_composer = [\n  \"Henry Purcell\",  # Restoration\n  \"John Blow\",\n]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.section("batch").unwrap()["section_composer"],
+            serde_json::json!(["Henry Purcell", "John Blow"])
+        );
+    }
+
+    #[test]
+    fn toml_outside_the_supported_subset_is_an_error() {
+        for (text, construct) in [
+            ("[[batch]]\ndeep = true", "arrays of tables"),
+            ("batch.deep = true", "dotted keys"),
+            ("limits = { max_tables = 3 }", "inline tables"),
+            ("notes = \"\"\"\nlong\n\"\"\"", "multi-line strings"),
+        ] {
+            let error = format!("{:#}", Config::parse(text).unwrap_err());
+            assert!(error.contains(construct), "{text}: {error}");
+        }
+    }
+}
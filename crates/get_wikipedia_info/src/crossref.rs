@@ -0,0 +1,209 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use crate::composers::{Composer, read_composers};
+use crate::jsonl::{create_writer, write_line};
+use crate::text::{fold_for_matching, wiki_title};
+use crate::works::{Composition, read_compositions};
+
+// How a composition's composer was found in composers.json
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ComposerMatch {
+    Url,
+    Name,
+}
+
+// The composers of a composers.json, by article title and by name. URLs
+// are compared by their /wiki/ title, decoded and folded, so
+// ".../wiki/Fr%C3%A9d%C3%A9ric_Chopin" from the composer list matches
+// ".../wiki/Frédéric_Chopin" built from the name, whatever the host.
+#[derive(Debug, Default)]
+pub struct ComposerIndex {
+    titles: HashSet<String>,
+    names: HashSet<String>,
+}
+
+impl ComposerIndex {
+    pub fn new(composers: &[Composer]) -> Self {
+        let mut index = Self::default();
+        for composer in composers {
+            index.titles.extend(article_title(&composer.url));
+            index.names.insert(fold_for_matching(&composer.full_name));
+        }
+        index
+    }
+
+    pub fn resolve(&self, composition: &Composition) -> Option<ComposerMatch> {
+        if article_title(&composition.composer_url)
+            .is_some_and(|title| self.titles.contains(&title))
+        {
+            Some(ComposerMatch::Url)
+        } else if self
+            .names
+            .contains(&fold_for_matching(&composition.composer_name))
+        {
+            Some(ComposerMatch::Name)
+        } else {
+            None
+        }
+    }
+}
+
+// "https://en.wikipedia.org/wiki/Arvo_P%C3%A4rt" -> "arvo part"
+fn article_title(url: &str) -> Option<String> {
+    wiki_title(url).map(|title| fold_for_matching(&title))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OrphanedComposition {
+    pub title: String,
+    pub composer_name: String,
+    pub composer_url: String,
+    pub source_url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CrossReferenceReport {
+    pub checked: usize,
+    pub matched_by_url: usize,
+    pub matched_by_name: usize,
+    pub orphaned: Vec<OrphanedComposition>,
+}
+
+// Set composer_known on each composition and count the outcomes
+pub fn tag_compositions(
+    compositions: &mut [Composition],
+    index: &ComposerIndex,
+) -> CrossReferenceReport {
+    let mut report = CrossReferenceReport::default();
+    for composition in compositions {
+        report.checked += 1;
+        let matched = index.resolve(composition);
+        composition.composer_known = Some(matched.is_some());
+        match matched {
+            Some(ComposerMatch::Url) => report.matched_by_url += 1,
+            Some(ComposerMatch::Name) => report.matched_by_name += 1,
+            None => report.orphaned.push(OrphanedComposition {
+                title: composition.title.clone(),
+                composer_name: composition.composer_name.clone(),
+                composer_url: composition.composer_url.clone(),
+                source_url: composition.source_url.clone(),
+            }),
+        }
+    }
+    report
+}
+
+// Check every composition in a compositions file against composers.json
+// and write the orphans (compositions whose composer isn't listed) to
+// `report_path`. With `tagged_output`, the compositions are also written
+// there with composer_known set.
+pub async fn cross_reference(
+    compositions_path: &str,
+    composers_path: &str,
+    report_path: &str,
+    tagged_output: Option<&str>,
+) -> Result<CrossReferenceReport> {
+    let index = ComposerIndex::new(&read_composers(composers_path).await?);
+
+    let mut compositions = Vec::new();
+    let mut reader = read_compositions(compositions_path).await?;
+    while let Some(composition) = reader.next().await {
+        compositions.push(composition?);
+    }
+
+    let report = tag_compositions(&mut compositions, &index);
+    tokio::fs::write(report_path, serde_json::to_string_pretty(&report)?).await?;
+
+    if let Some(tagged_output) = tagged_output {
+        let mut writer = create_writer(tagged_output, false).await?;
+        for composition in &compositions {
+            write_line(&mut writer, composition).await?;
+        }
+        writer.shutdown().await?;
+    }
+
+    info!(
+        "{} compositions checked: {} matched by URL, {} by name, {} orphaned; report written to {}",
+        report.checked,
+        report.matched_by_url,
+        report.matched_by_name,
+        report.orphaned.len(),
+        report_path
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::composers::QualityOfYearInfo;
+    use crate::works::canonicalize_raw_data;
+    use crate::works::tests::raw_row;
+
+    fn composition(composer_name: &str, composer_url: &str, title: &str) -> Composition {
+        let mut raw = raw_row(&["Title"], &[title]);
+        raw.composer_name = composer_name.to_string();
+        raw.composer_url = composer_url.to_string();
+        raw.source_url = "source_url".to_string();
+        canonicalize_raw_data(raw)
+    }
+
+    #[test]
+    fn tags_compositions_whose_composer_is_not_listed() {
+        let composers = vec![Composer {
+            url: "https://en.wikipedia.org/wiki/Fr%C3%A9d%C3%A9ric_Chopin".to_string(),
+            full_name: "Frédéric Chopin".to_string(),
+            sort_name: "Chopin, Frédéric".to_string(),
+            list_of_compositions_url:
+                "https://en.wikipedia.org/wiki/List_of_compositions_by_Fr%C3%A9d%C3%A9ric_Chopin"
+                    .to_string(),
+            birth_year: Some(1810),
+            death_year: Some(1849),
+            years_qualifier: QualityOfYearInfo::Exact,
+            summary: None,
+            birth_place: None,
+            death_place: None,
+            nationality: None,
+            image_url: None,
+        }];
+        let index = ComposerIndex::new(&composers);
+
+        let mut compositions = vec![
+            composition(
+                "Frédéric Chopin",
+                "http://localhost:8080/wiki/Frédéric_Chopin",
+                "Ballade No. 1",
+            ),
+            composition(
+                "Johann Christoph Bach",
+                "https://en.wikipedia.org/wiki/Johann_Christoph_Bach",
+                "Lamento",
+            ),
+        ];
+        let report = tag_compositions(&mut compositions, &index);
+
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.matched_by_url, 1);
+        assert_eq!(report.matched_by_name, 0);
+        assert_eq!(compositions[0].composer_known, Some(true));
+        assert_eq!(compositions[1].composer_known, Some(false));
+        assert_eq!(
+            report.orphaned,
+            vec![OrphanedComposition {
+                title: "Lamento".to_string(),
+                composer_name: "Johann Christoph Bach".to_string(),
+                composer_url: "https://en.wikipedia.org/wiki/Johann_Christoph_Bach".to_string(),
+                source_url: "source_url".to_string(),
+            }]
+        );
+
+        // A composer URL that matches nothing still resolves by name
+        let moved = composition("Frederic Chopin", "https://example.org/Chopin", "Mazurka");
+        assert_eq!(index.resolve(&moved), Some(ComposerMatch::Name));
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::works::{Composition, read_compositions};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChangedComposition {
+    pub composer_name: String,
+    pub title: String,
+    pub catalog_number: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompositionDiff {
+    pub added: Vec<Composition>,
+    pub removed: Vec<Composition>,
+    pub changed: Vec<ChangedComposition>,
+}
+
+impl CompositionDiff {
+    pub async fn save(&self, path: &str) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+// Compositions are matched on composer, title and catalogue number; the
+// composer keeps "Symphony No. 1" by different composers apart in batch
+// output. Within one file the first composition with a key wins.
+type DiffKey = (String, String, Option<String>);
+
+fn diff_key(composition: &Composition) -> DiffKey {
+    (
+        composition.composer_name.clone(),
+        composition.title.clone(),
+        composition.catalog_number.clone(),
+    )
+}
+
+async fn load(path: &str) -> Result<Vec<Composition>> {
+    let mut compositions = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut reader = read_compositions(path).await?;
+    while let Some(composition) = reader.next().await {
+        let composition = composition?;
+        if seen.insert(diff_key(&composition)) {
+            compositions.push(composition);
+        }
+    }
+    Ok(compositions)
+}
+
+// Every top-level field whose value differs, raw_data excepted since it
+// changes with any markup edit
+fn field_changes(old: &Composition, new: &Composition) -> Result<Vec<FieldChange>> {
+    let (Value::Object(mut old), Value::Object(mut new)) =
+        (serde_json::to_value(old)?, serde_json::to_value(new)?)
+    else {
+        return Ok(Vec::new());
+    };
+    old.remove("raw_data");
+    new.remove("raw_data");
+
+    let fields: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    Ok(fields
+        .into_iter()
+        .filter_map(|field| {
+            let old_value = old.get(field).cloned().unwrap_or(Value::Null);
+            let new_value = new.get(field).cloned().unwrap_or(Value::Null);
+            (old_value != new_value).then(|| FieldChange {
+                field: field.clone(),
+                old: old_value,
+                new: new_value,
+            })
+        })
+        .collect())
+}
+
+// Compare two compositions files (e.g. from runs a month apart): what was
+// added, what disappeared and which fields changed on the rest. Added and
+// changed follow the new file's order, removed the old file's.
+pub async fn diff_compositions(old_path: &str, new_path: &str) -> Result<CompositionDiff> {
+    let old = load(old_path).await?;
+    let new = load(new_path).await?;
+
+    let mut old_by_key: HashMap<DiffKey, Composition> =
+        old.iter().map(|c| (diff_key(c), c.clone())).collect();
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+
+    for composition in new {
+        match old_by_key.remove(&diff_key(&composition)) {
+            None => added.push(composition),
+            Some(previous) => {
+                let changes = field_changes(&previous, &composition)?;
+                if !changes.is_empty() {
+                    changed.push(ChangedComposition {
+                        composer_name: composition.composer_name,
+                        title: composition.title,
+                        catalog_number: composition.catalog_number,
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+
+    let removed = old
+        .into_iter()
+        .filter(|c| old_by_key.contains_key(&diff_key(c)))
+        .collect();
+
+    Ok(CompositionDiff {
+        added,
+        removed,
+        changed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(title: &str, catalog_number: Option<&str>, year: &str) -> String {
+        serde_json::json!({
+            "composer_name": "Franz Schubert",
+            "composer_url": "",
+            "source_url": "",
+            "title": title,
+            "work_url": null,
+            "year": year,
+            "key": null,
+            "opus": null,
+            "genre": null,
+            "catalog_number": catalog_number,
+            "instrumentation": null,
+            "duration": null,
+            "additional_info": {}
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn reports_added_removed_and_changed_compositions() {
+        let dir = std::env::temp_dir();
+        let old_path = dir.join(format!("diff-old-{}.json", std::process::id()));
+        let new_path = dir.join(format!("diff-new-{}.json", std::process::id()));
+        std::fs::write(
+            &old_path,
+            [
+                line("Erlkönig", Some("D 328"), "1815"),
+                line("Symphony No. 8", Some("D 759"), "1822"),
+                line("Die Forelle", Some("D 550"), "1817"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        std::fs::write(
+            &new_path,
+            [
+                line("Erlkönig", Some("D 328"), "1815"),
+                line("Symphony No. 8", Some("D 759"), "1822–1823"),
+                line("Winterreise", Some("D 911"), "1827"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let diff = diff_compositions(old_path.to_str().unwrap(), new_path.to_str().unwrap())
+            .await
+            .unwrap();
+        std::fs::remove_file(&old_path).unwrap();
+        std::fs::remove_file(&new_path).unwrap();
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].title, "Winterreise");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].title, "Die Forelle");
+        assert_eq!(
+            diff.changed,
+            vec![ChangedComposition {
+                composer_name: "Franz Schubert".to_string(),
+                title: "Symphony No. 8".to_string(),
+                catalog_number: Some("D 759".to_string()),
+                changes: vec![FieldChange {
+                    field: "year".to_string(),
+                    old: "1822".into(),
+                    new: "1822–1823".into(),
+                }],
+            }]
+        );
+    }
+}
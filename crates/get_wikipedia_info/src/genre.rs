@@ -0,0 +1,162 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::LazyLock;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Genre {
+    Opera,
+    Concerto,
+    Symphony,
+    Ballet,
+    Choral,
+    Song,
+    Sonata,
+    Chamber,
+    Keyboard,
+    Orchestral,
+    Film,
+    Other,
+}
+
+// Checked in order, so the more specific forms come first: "Piano concerto"
+// is a concerto, "Piano sonata" a sonata and "Symphonic poem" orchestral.
+// Each pattern matches from the start of a word, and the short words also
+// to its end, so "Christmas song" isn't a mass nor "Introduction" a trio.
+const GENRE_PATTERNS: &[(Genre, &str)] = &[
+    (
+        Genre::Opera,
+        r"opera|operett|singspiel|zarzuela|music drama",
+    ),
+    (Genre::Concerto, r"concert(o|i|ante)"),
+    (Genre::Orchestral, r"symphonic poem|tone poem"),
+    (Genre::Symphony, r"symphon"),
+    (Genre::Ballet, r"ballet"),
+    (
+        Genre::Choral,
+        r"choral|choir|chorus|mass(es)?\b|requiem|oratorio|cantata|motet|sacred|a cappella",
+    ),
+    (Genre::Song, r"(folk)?song|lied|mélodie|chanson"),
+    (Genre::Sonata, r"sonata"),
+    (
+        Genre::Chamber,
+        r"chamber|duos?\b|trios?\b|quartet|quintet|sextet|septet|octet|nonet",
+    ),
+    (
+        Genre::Keyboard,
+        r"piano|keyboard|organs?\b|harpsichord|clavier",
+    ),
+    (Genre::Orchestral, r"orchestra|overture|suites?\b|serenade"),
+    (Genre::Film, r"film|incidental|theatre|theater"),
+];
+
+static GENRE_REGEXES: LazyLock<Vec<(Genre, Regex)>> = LazyLock::new(|| {
+    GENRE_PATTERNS
+        .iter()
+        .map(|(genre, pattern)| (*genre, Regex::new(&format!(r"\b(?:{})", pattern)).unwrap()))
+        .collect()
+});
+
+impl Genre {
+    // Normalize free-text genre cells such as "Piano concerto" or
+    // "Works for string quartet" to one of a fixed set of genres.
+    pub fn from_text(text: &str) -> Genre {
+        let text_lower = text.to_lowercase();
+
+        GENRE_REGEXES
+            .iter()
+            .find(|(_, regex)| regex.is_match(&text_lower))
+            .map(|(genre, _)| *genre)
+            .unwrap_or(Genre::Other)
+    }
+
+    // Every genre in a cell listing several, e.g. "Sacred; Choral; A
+    // cappella" or "Opera, ballet", in order and without repeats
+    pub fn all_from_text(text: &str) -> Vec<Genre> {
+        let mut genres = Vec::new();
+        for part in split_genre_cell(text) {
+            let genre = Genre::from_text(part);
+            if !genres.contains(&genre) {
+                genres.push(genre);
+            }
+        }
+        genres
+    }
+
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Genre::Opera => "opera",
+            Genre::Concerto => "concerto",
+            Genre::Symphony => "symphony",
+            Genre::Ballet => "ballet",
+            Genre::Choral => "choral",
+            Genre::Song => "song",
+            Genre::Sonata => "sonata",
+            Genre::Chamber => "chamber",
+            Genre::Keyboard => "keyboard",
+            Genre::Orchestral => "orchestral",
+            Genre::Film => "film",
+            Genre::Other => "other",
+        }
+    }
+}
+
+// The parts of a multi-value genre cell, split on ";", ",", "/" and "·"
+pub fn split_genre_cell(text: &str) -> Vec<&str> {
+    text.split([';', ',', '/', '·'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+impl fmt::Display for Genre {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.slug())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_common_genre_cells() {
+        assert_eq!(Genre::from_text("Piano concerto"), Genre::Concerto);
+        assert_eq!(Genre::from_text("Piano sonata"), Genre::Sonata);
+        assert_eq!(Genre::from_text("Symphonic poem"), Genre::Orchestral);
+        assert_eq!(Genre::from_text("Symphony"), Genre::Symphony);
+        assert_eq!(Genre::from_text("String Quartet"), Genre::Chamber);
+        assert_eq!(Genre::from_text("Operetta"), Genre::Opera);
+        assert_eq!(Genre::from_text("Miscellaneous"), Genre::Other);
+    }
+
+    #[test]
+    fn genre_words_match_whole_words_only() {
+        assert_eq!(Genre::from_text("Christmas song"), Genre::Song);
+        assert_eq!(Genre::from_text("Masses"), Genre::Choral);
+        assert_eq!(Genre::from_text("Introduction and Allegro"), Genre::Other);
+        assert_eq!(Genre::from_text("Piano trios"), Genre::Chamber);
+        assert_eq!(Genre::from_text("Trios"), Genre::Chamber);
+        assert_eq!(Genre::from_text("Folksongs"), Genre::Song);
+        assert_eq!(Genre::from_text("Organised sound"), Genre::Other);
+    }
+
+    #[test]
+    fn splits_semicolon_and_comma_separated_genre_cells() {
+        assert_eq!(
+            split_genre_cell("Sacred; Choral; A cappella"),
+            vec!["Sacred", "Choral", "A cappella"]
+        );
+        assert_eq!(
+            Genre::all_from_text("Sacred; Choral; A cappella"),
+            vec![Genre::Choral]
+        );
+        assert_eq!(
+            Genre::all_from_text("Opera, ballet, incidental music"),
+            vec![Genre::Opera, Genre::Ballet, Genre::Film]
+        );
+        assert_eq!(Genre::all_from_text("Symphony"), vec![Genre::Symphony]);
+        assert!(Genre::all_from_text(" ; ").is_empty());
+    }
+}
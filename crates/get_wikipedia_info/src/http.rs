@@ -0,0 +1,368 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::{
+    sync::{Mutex, Semaphore, SemaphorePermit},
+    time::Instant,
+};
+use tracing::warn;
+
+// Wikimedia asks clients to identify themselves with a descriptive agent.
+pub const USER_AGENT: &str =
+    "classical-music-scraper/0.1 (https://github.com/aaelony/classical-music)";
+
+#[derive(Clone, Debug)]
+pub struct HttpOptions {
+    // Whole-request timeout, including reading the body
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    // Extra attempts after the first for timeouts, connection errors, 429s
+    // and 5xx responses
+    pub max_retries: u32,
+    // Delay before the first retry; doubled on each further attempt
+    pub retry_backoff: Duration,
+    // Idle keep-alive connections kept per host; None leaves reqwest's
+    // default (no limit)
+    pub pool_max_idle_per_host: Option<usize>,
+    // The client, built on first use by build_client and shared by every
+    // clone of these options, so all composers and enrichment fetches of a
+    // run reuse one connection pool
+    pub client: Arc<OnceLock<reqwest::Client>>,
+    // Cap on the requests of the whole run, shared like the client; None
+    // leaves them unlimited
+    pub request_budget: Option<Arc<RequestBudget>>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            pool_max_idle_per_host: None,
+            client: Arc::default(),
+            request_budget: None,
+        }
+    }
+}
+
+// A number of requests a run may make, counted across every clone of the
+// HttpOptions holding it. Each attempt counts, retries included.
+#[derive(Debug)]
+pub struct RequestBudget {
+    limit: u64,
+    used: AtomicU64,
+}
+
+impl RequestBudget {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    // Take one request from the budget; false once it is spent
+    pub fn try_spend(&self) -> bool {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                (used < self.limit).then_some(used + 1)
+            })
+            .is_ok()
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+// Returned instead of making a request once the run's RequestBudget is spent
+#[derive(Debug)]
+pub struct BudgetExhausted {
+    pub limit: u64,
+}
+
+impl std::fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request budget of {} exhausted", self.limit)
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
+// The client for these options: built the first time, then the same
+// (cheaply cloned) client and connection pool every time after
+pub fn build_client(options: &HttpOptions) -> Result<reqwest::Client> {
+    if let Some(client) = options.client.get() {
+        return Ok(client.clone());
+    }
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(options.timeout)
+        .connect_timeout(options.connect_timeout);
+    if let Some(max_idle) = options.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    let client = builder.build()?;
+    Ok(options.client.get_or_init(|| client).clone())
+}
+
+pub fn is_retryable(error: &reqwest::Error) -> bool {
+    error.is_timeout()
+        || error.is_connect()
+        || error
+            .status()
+            .is_some_and(|s| s.is_server_error() || s == reqwest::StatusCode::TOO_MANY_REQUESTS)
+}
+
+// GET `url`, retrying with exponential backoff on retryable failures. Other
+// responses (including 404) are returned as-is for the caller to inspect.
+// Fails with BudgetExhausted, without sending anything, once the request
+// budget is spent.
+pub async fn get_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    options: &HttpOptions,
+) -> Result<reqwest::Response> {
+    let mut attempt = 0;
+    loop {
+        if let Some(budget) = &options.request_budget
+            && !budget.try_spend()
+        {
+            return Err(BudgetExhausted {
+                limit: budget.limit(),
+            }
+            .into());
+        }
+        let result = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| match response.status() {
+                s if s.is_server_error() || s == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    response.error_for_status()
+                }
+                _ => Ok(response),
+            });
+
+        match result {
+            Err(e) if attempt < options.max_retries && is_retryable(&e) => {
+                let delay = options.retry_backoff * 2u32.pow(attempt);
+                attempt += 1;
+                warn!(
+                    "Request to {} failed ({}); retry {}/{} in {:?}",
+                    url, e, attempt, options.max_retries, delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            other => return Ok(other?),
+        }
+    }
+}
+
+// Spaces request starts evenly: each call to `acquire` waits for the next
+// free slot, `1 / requests_per_second` after the previous one.
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        let interval = if requests_per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / requests_per_second)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            interval,
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+// Request budget for one service, e.g. Wikipedia work articles fetched by
+// --deep or composer articles. Each service gets its own Throttle, so a
+// slow or strict service can't eat into another's budget.
+#[derive(Clone, Copy, Debug)]
+pub struct ServiceLimit {
+    // Requests in flight at once
+    pub concurrency: usize,
+    pub requests_per_second: f64,
+}
+
+impl Default for ServiceLimit {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            requests_per_second: 2.0,
+        }
+    }
+}
+
+// A ServiceLimit enforced: both the concurrency cap and the request rate
+pub struct Throttle {
+    semaphore: Semaphore,
+    limiter: RateLimiter,
+}
+
+impl Throttle {
+    pub fn new(limit: ServiceLimit) -> Self {
+        Self {
+            semaphore: Semaphore::new(limit.concurrency.max(1)),
+            limiter: RateLimiter::new(limit.requests_per_second),
+        }
+    }
+
+    // Wait for a free slot under both limits. Hold the permit for the whole
+    // request; dropping it frees the concurrency slot.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self
+            .semaphore
+            .acquire()
+            .await
+            .expect("throttle semaphore is never closed");
+        self.limiter.acquire().await;
+        permit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn throttle_serializes_calls_at_the_configured_rate() {
+        let throttle = std::sync::Arc::new(Throttle::new(ServiceLimit {
+            concurrency: 1,
+            requests_per_second: 20.0,
+        }));
+        let in_flight = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let start = Instant::now();
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..4 {
+            let throttle = throttle.clone();
+            let in_flight = in_flight.clone();
+            tasks.spawn(async move {
+                let _permit = throttle.acquire().await;
+                let started = Instant::now();
+                let overlapping = in_flight.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                in_flight.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                (started, overlapping)
+            });
+        }
+        let mut calls: Vec<(Instant, usize)> = tasks.join_all().await;
+        calls.sort();
+
+        assert!(calls.iter().all(|(_, overlapping)| *overlapping == 0));
+        // 20 per second: starts at least ~50ms apart
+        for pair in calls.windows(2) {
+            assert!(pair[1].0 - pair[0].0 >= Duration::from_millis(45));
+        }
+        assert!(calls[3].0 - start >= Duration::from_millis(140));
+    }
+
+    #[tokio::test]
+    async fn clones_of_the_options_share_one_client() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let options = HttpOptions::default();
+        let per_composer = options.clone();
+        assert!(per_composer.client.get().is_none());
+
+        let client = build_client(&options).unwrap();
+        assert!(per_composer.client.get().is_some());
+        let reused = build_client(&per_composer).unwrap();
+        for client in [client, reused] {
+            get_with_retry(&client, &server.uri(), &options)
+                .await
+                .unwrap();
+        }
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        // Fresh options get a client of their own
+        assert!(HttpOptions::default().client.get().is_none());
+    }
+
+    #[tokio::test]
+    async fn slow_responses_time_out_and_are_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let options = HttpOptions {
+            timeout: Duration::from_millis(100),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(10),
+            ..HttpOptions::default()
+        };
+        let client = build_client(&options).unwrap();
+
+        let error = get_with_retry(&client, &format!("{}/slow", server.uri()), &options)
+            .await
+            .unwrap_err();
+
+        assert!(error.downcast_ref::<reqwest::Error>().unwrap().is_timeout());
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn requests_stop_once_the_budget_is_spent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let options = HttpOptions {
+            request_budget: Some(Arc::new(RequestBudget::new(2))),
+            ..HttpOptions::default()
+        };
+        let per_composer = options.clone();
+        let client = build_client(&options).unwrap();
+
+        get_with_retry(&client, &server.uri(), &options)
+            .await
+            .unwrap();
+        get_with_retry(&client, &server.uri(), &per_composer)
+            .await
+            .unwrap();
+        let error = get_with_retry(&client, &server.uri(), &options)
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "request budget of 2 exhausted");
+        assert!(error.downcast_ref::<BudgetExhausted>().is_some());
+        assert_eq!(options.request_budget.as_ref().unwrap().used(), 2);
+        server.verify().await;
+    }
+}
@@ -0,0 +1,476 @@
+use anyhow::Result;
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+use crate::genre::Genre;
+use crate::http::{BudgetExhausted, HttpOptions, ServiceLimit, Throttle};
+use crate::works::{Composition, FieldCanonicalizer, YearConfidence, fetch_html, find_iswc};
+
+// Label/value rows of a work article's infobox, e.g. ("Key", "C minor"),
+// ("Opus", "67"), ("Composed", "1804–1808").
+pub fn parse_infobox(document: &Html) -> Vec<(String, String)> {
+    let row_selector = Selector::parse("table.infobox tr").unwrap();
+    let label_selector = Selector::parse("th").unwrap();
+    let data_selector = Selector::parse("td").unwrap();
+
+    document
+        .select(&row_selector)
+        .filter_map(|row| {
+            let label = row.select(&label_selector).next()?;
+            let data = row.select(&data_selector).next()?;
+            let label = label.text().collect::<String>().trim().to_string();
+            let data = data.text().collect::<String>().trim().to_string();
+            (!label.is_empty() && !data.is_empty()).then_some((label, data))
+        })
+        .collect()
+}
+
+// Lines of an infobox cell as displayed: <br> and block elements start a
+// new line, hidden spans (e.g. the machine-readable "(1819-09-13)") and
+// citation markers are left out.
+fn cell_lines(cell: scraper::ElementRef) -> Vec<String> {
+    let hidden = |element: scraper::ElementRef| {
+        let value = element.value();
+        value
+            .attr("style")
+            .is_some_and(|style| style.replace(' ', "").contains("display:none"))
+            || (value.name() == "sup"
+                && value.attr("class").is_some_and(|c| c.contains("reference")))
+    };
+
+    let mut lines = vec![String::new()];
+    for node in cell.descendants() {
+        let inside_hidden = node
+            .ancestors()
+            .filter_map(scraper::ElementRef::wrap)
+            .take_while(|ancestor| ancestor.id() != cell.id())
+            .any(hidden);
+        if inside_hidden {
+            continue;
+        }
+        if let Some(element) = scraper::ElementRef::wrap(node) {
+            if matches!(element.value().name(), "br" | "div" | "li" | "p") {
+                lines.push(String::new());
+            }
+        } else if let Some(text) = node.value().as_text() {
+            lines.last_mut().unwrap().push_str(text);
+        }
+    }
+    lines
+        .iter()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// The place in a "Born"/"Died" cell: whatever follows the line with the
+// date, e.g. "Leipzig, Kingdom of Saxony" from
+// "Clara Josephine Wieck / 13 September 1819 / Leipzig, Kingdom of Saxony".
+// Infoboxes that mark the place with a birthplace/deathplace class are read
+// from that directly.
+fn place_after_date(cell: scraper::ElementRef, place_class: &str) -> Option<String> {
+    let place_selector = Selector::parse(&format!(".{}", place_class)).unwrap();
+    if let Some(place) = cell.select(&place_selector).next() {
+        let place = cell_lines(place).join(", ");
+        return (!place.is_empty()).then_some(place);
+    }
+
+    let year_regex = Regex::new(r"\b\d{3,4}\b").unwrap();
+    let lines = cell_lines(cell);
+    let date_line = lines.iter().rposition(|line| year_regex.is_match(line))?;
+    let place = lines[date_line + 1..].join(", ");
+    (!place.is_empty()).then_some(place)
+}
+
+// Birth and death places from a composer article's infobox. A living
+// composer has no "Died" row, so death place is None.
+pub fn parse_birth_death_places(document: &Html) -> (Option<String>, Option<String>) {
+    let row_selector = Selector::parse("table.infobox tr").unwrap();
+    let label_selector = Selector::parse("th").unwrap();
+    let data_selector = Selector::parse("td").unwrap();
+
+    let mut birth_place = None;
+    let mut death_place = None;
+    for row in document.select(&row_selector) {
+        let (Some(label), Some(data)) = (
+            row.select(&label_selector).next(),
+            row.select(&data_selector).next(),
+        ) else {
+            continue;
+        };
+        match label.text().collect::<String>().trim() {
+            "Born" if birth_place.is_none() => birth_place = place_after_date(data, "birthplace"),
+            "Died" if death_place.is_none() => death_place = place_after_date(data, "deathplace"),
+            _ => {}
+        }
+    }
+    (birth_place, death_place)
+}
+
+// The infobox's main image, e.g. a composer's portrait, as an absolute
+// https URL. Wikipedia writes image sources protocol-relative
+// ("//upload.wikimedia.org/..."); None when the infobox has no image.
+// The image cell is looked at first: scraper yields matches in document
+// order whatever the selector list says, and an icon above the portrait
+// would otherwise win.
+pub fn parse_infobox_image(document: &Html) -> Option<String> {
+    let portrait_selector = Selector::parse("table.infobox .infobox-image img").unwrap();
+    let any_image_selector = Selector::parse("table.infobox img").unwrap();
+    let src = document
+        .select(&portrait_selector)
+        .next()
+        .or_else(|| document.select(&any_image_selector).next())?
+        .value()
+        .attr("src")?;
+    let src = src.trim();
+    if let Some(rest) = src.strip_prefix("//") {
+        Some(format!("https://{}", rest))
+    } else if let Some(rest) = src.strip_prefix("http://") {
+        Some(format!("https://{}", rest))
+    } else if src.starts_with("https://") {
+        Some(src.to_string())
+    } else {
+        None
+    }
+}
+
+// Fill the fields the list page left empty from infobox rows; fields that
+// already have a value are never overwritten.
+pub(crate) fn enrich_from_infobox(
+    composition: &mut Composition,
+    infobox: &[(String, String)],
+    canonicalizer: &FieldCanonicalizer,
+) {
+    for (label, value) in infobox {
+        if composition.iswc.is_none() {
+            composition.iswc = find_iswc(value);
+        }
+        match canonicalizer.categorize_header(label) {
+            Some("year") if composition.year.is_none() => {
+                composition.year = canonicalizer.extract_year_from_text(value);
+                if composition.year.is_some() {
+                    composition.year_confidence = Some(YearConfidence::High);
+                    composition.set_year_numeric(value);
+                }
+            }
+            Some("key") if composition.key.is_none() => {
+                composition.key = canonicalizer
+                    .extract_key_from_text(value)
+                    .or_else(|| Some(value.clone()));
+                composition.key_all.extend(composition.key.clone());
+            }
+            Some("opus") if composition.opus.is_none() => {
+                composition.opus = canonicalizer
+                    .extract_opus_from_text(value)
+                    .or_else(|| Some(value.clone()));
+                composition.opus_all.extend(composition.opus.clone());
+            }
+            Some("catalog_number") if composition.catalog_number.is_none() => {
+                composition.catalog_number = Some(value.clone());
+                composition.catalog_number_all.push(value.clone());
+            }
+            Some("genre") if composition.genre.is_none() => {
+                composition.genre = Some(value.clone());
+                composition.genres = Genre::all_from_text(value);
+            }
+            Some("instrumentation") if composition.instrumentation.is_none() => {
+                composition.instrumentation = Some(value.clone());
+            }
+            Some("duration") if composition.duration.is_none() => {
+                composition.duration = Some(value.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+// The composition, plus its work page URL when that page was fetched
+async fn enrich_composition(
+    client: &reqwest::Client,
+    http: &HttpOptions,
+    canonicalizer: &FieldCanonicalizer,
+    mut composition: Composition,
+) -> Result<(Composition, Option<String>)> {
+    let Some(work_url) = composition.work_url.clone() else {
+        return Ok((composition, None));
+    };
+
+    match fetch_html(client, &work_url, http).await {
+        Ok(Some(html)) => {
+            let infobox = parse_infobox(&Html::parse_document(&html));
+            enrich_from_infobox(&mut composition, &infobox, canonicalizer);
+            return Ok((composition, Some(work_url)));
+        }
+        Ok(None) => warn!("Work page {} not found", work_url),
+        Err(e) if e.downcast_ref::<BudgetExhausted>().is_some() => return Err(e),
+        Err(e) => warn!("Error fetching work page {}: {}", work_url, e),
+    }
+    Ok((composition, None))
+}
+
+#[derive(Clone, Debug)]
+pub struct DeepOptions {
+    // Maximum number of work pages fetched at once
+    pub concurrency: usize,
+    pub requests_per_second: f64,
+}
+
+impl Default for DeepOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            requests_per_second: 2.0,
+        }
+    }
+}
+
+// Fetch the work article of every composition that links to one and fill
+// gaps from its infobox. Order is preserved; fetch failures leave the
+// composition as it was, except a spent request budget, which fails the
+// whole enrichment so the composer isn't counted as scraped. Also returns
+// the work pages actually read, in composition order, for attribution.
+pub(crate) async fn enrich_compositions(
+    client: &reqwest::Client,
+    http: &HttpOptions,
+    canonicalizer: Arc<FieldCanonicalizer>,
+    compositions: Vec<Composition>,
+    options: &DeepOptions,
+) -> Result<(Vec<Composition>, Vec<String>)> {
+    let throttle = Arc::new(Throttle::new(ServiceLimit {
+        concurrency: options.concurrency,
+        requests_per_second: options.requests_per_second,
+    }));
+    let mut tasks = JoinSet::new();
+    let mut enriched: Vec<Option<Composition>> = Vec::with_capacity(compositions.len());
+    let mut fetched: Vec<Option<String>> = Vec::with_capacity(compositions.len());
+
+    info!(
+        "Fetching work pages for {} compositions",
+        compositions.iter().filter(|c| c.work_url.is_some()).count()
+    );
+
+    for (index, composition) in compositions.into_iter().enumerate() {
+        fetched.push(None);
+        if composition.work_url.is_none() {
+            enriched.push(Some(composition));
+            continue;
+        }
+        enriched.push(None);
+
+        let client = client.clone();
+        let http = http.clone();
+        let canonicalizer = canonicalizer.clone();
+        let throttle = throttle.clone();
+        tasks.spawn(async move {
+            let _permit = throttle.acquire().await;
+            let composition = enrich_composition(&client, &http, &canonicalizer, composition).await;
+            (index, composition)
+        });
+    }
+
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok((index, Ok((composition, work_url)))) => {
+                enriched[index] = Some(composition);
+                fetched[index] = work_url;
+            }
+            Ok((_, Err(e))) => {
+                tasks.abort_all();
+                return Err(e);
+            }
+            Err(e) => warn!("Work page task failed: {}", e),
+        }
+    }
+
+    let mut work_urls: Vec<String> = Vec::new();
+    for work_url in fetched.into_iter().flatten() {
+        if !work_urls.contains(&work_url) {
+            work_urls.push(work_url);
+        }
+    }
+    Ok((enriched.into_iter().flatten().collect(), work_urls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::works::canonicalize_raw_data;
+
+    #[test]
+    fn fills_missing_fields_from_a_work_infobox() {
+        // Trimmed from the infobox of "Symphony No. 5 (Beethoven)"
+        let html = r#"<html><body>
+            <table class="infobox vevent"><tbody>
+              <tr><th colspan="2" class="infobox-above">Symphony in C minor</th></tr>
+              <tr><th scope="row" class="infobox-label">Key</th><td class="infobox-data">C minor</td></tr>
+              <tr><th scope="row" class="infobox-label">Opus</th><td class="infobox-data">67</td></tr>
+              <tr><th scope="row" class="infobox-label">Composed</th><td class="infobox-data">1804–1808</td></tr>
+              <tr><th scope="row" class="infobox-label">Duration</th><td class="infobox-data">About 30–40 minutes</td></tr>
+              <tr><th scope="row" class="infobox-label">ISWC</th><td class="infobox-data">T-034.524.680-1</td></tr>
+            </tbody></table>
+            </body></html>"#;
+        let infobox = parse_infobox(&Html::parse_document(html));
+        assert_eq!(infobox.len(), 5);
+
+        let raw = serde_json::from_value(serde_json::json!({
+            "composer_name": "Ludwig van Beethoven",
+            "composer_url": "composer_url",
+            "source_url": "source_url",
+            "table_index": 0,
+            "row_index": 0,
+            "headers": ["Title", "Year"],
+            "cell_data": ["Symphony No. 5", "1808"],
+            "cell_links": [null, null],
+            "raw_html_snippet": ""
+        }))
+        .unwrap();
+        let mut composition = canonicalize_raw_data(raw);
+
+        enrich_from_infobox(&mut composition, &infobox, &FieldCanonicalizer::new());
+
+        assert_eq!(composition.year.as_deref(), Some("1808"));
+        assert_eq!(composition.key.as_deref(), Some("C minor"));
+        assert_eq!(composition.opus.as_deref(), Some("67"));
+        assert_eq!(composition.opus_all, vec!["67"]);
+        assert_eq!(composition.duration.as_deref(), Some("About 30–40 minutes"));
+        assert_eq!(composition.iswc.as_deref(), Some("T-034.524.680-1"));
+    }
+
+    #[tokio::test]
+    async fn reports_the_work_pages_it_read() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wiki/Symphony_No._5_(Beethoven)"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<table class="infobox"><tr><th>Key</th><td>C minor</td></tr></table>"#,
+            ))
+            .mount(&server)
+            .await;
+        let composition = |title: &str, page: Option<&str>| {
+            let raw = serde_json::from_value(serde_json::json!({
+                "composer_name": "Ludwig van Beethoven",
+                "composer_url": "composer_url",
+                "source_url": "source_url",
+                "table_index": 0,
+                "row_index": 0,
+                "headers": ["Title"],
+                "cell_data": [title],
+                "cell_links": [null],
+                "raw_html_snippet": ""
+            }))
+            .unwrap();
+            let mut composition = canonicalize_raw_data(raw);
+            composition.work_url = page.map(|page| format!("{}/wiki/{}", server.uri(), page));
+            composition
+        };
+        let compositions = vec![
+            composition("Symphony No. 5", Some("Symphony_No._5_(Beethoven)")),
+            composition("Bagatelle", None),
+            composition("Lost Cantata", Some("Lost_Cantata")),
+        ];
+        let http = HttpOptions {
+            max_retries: 0,
+            ..Default::default()
+        };
+        let client = crate::http::build_client(&http).unwrap();
+
+        let (enriched, work_urls) = enrich_compositions(
+            &client,
+            &http,
+            Arc::new(FieldCanonicalizer::new()),
+            compositions,
+            &DeepOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(enriched.len(), 3);
+        assert_eq!(enriched[0].key.as_deref(), Some("C minor"));
+        // The missing page was never read, so it isn't a source
+        assert_eq!(
+            work_urls,
+            vec![format!("{}/wiki/Symphony_No._5_(Beethoven)", server.uri())]
+        );
+    }
+
+    #[test]
+    fn reads_the_portrait_from_a_composer_infobox() {
+        // Trimmed from the infobox of "Johannes Brahms"; the flag icon in
+        // the "Born" row must not be taken for the portrait
+        let html = r#"<html><body>
+            <table class="infobox biography vcard"><tbody>
+              <tr><th colspan="2" class="infobox-above"><div class="fn">Johannes Brahms</div></th></tr>
+              <tr><td colspan="2" class="infobox-image"><span class="mw-default-size" typeof="mw:File/Frameless"><a href="/wiki/File:JohannesBrahms.jpg" class="mw-file-description"><img src="//upload.wikimedia.org/wikipedia/commons/thumb/1/15/JohannesBrahms.jpg/250px-JohannesBrahms.jpg" decoding="async" width="250" height="328" class="mw-file-element"></a></span><div class="infobox-caption">Brahms in 1889</div></td></tr>
+              <tr><th scope="row" class="infobox-label">Born</th><td class="infobox-data"><img src="//upload.wikimedia.org/flag.png"> 7 May 1833<br>Hamburg</td></tr>
+            </tbody></table>
+            </body></html>"#;
+        assert_eq!(
+            parse_infobox_image(&Html::parse_document(html)).as_deref(),
+            Some(
+                "https://upload.wikimedia.org/wikipedia/commons/thumb/1/15/JohannesBrahms.jpg/250px-JohannesBrahms.jpg"
+            )
+        );
+
+        let no_image = r#"<html><body>
+            <table class="infobox biography vcard"><tbody>
+              <tr><th scope="row" class="infobox-label">Born</th><td class="infobox-data">c. 1450</td></tr>
+            </tbody></table>
+            </body></html>"#;
+        assert_eq!(parse_infobox_image(&Html::parse_document(no_image)), None);
+
+        // An icon in the "above" row and a listen button both come before
+        // the portrait
+        let icons_first = r#"<html><body>
+            <table class="infobox biography vcard"><tbody>
+              <tr><th colspan="2" class="infobox-above"><img src="//upload.wikimedia.org/Featured_article_star.png"> Clara Schumann</th></tr>
+              <tr><td colspan="2"><a href="/wiki/File:Clara_Schumann_Romance.ogg"><img src="//upload.wikimedia.org/Loudspeaker.svg.png"></a> Listen</td></tr>
+              <tr><td colspan="2" class="infobox-image"><img src="//upload.wikimedia.org/Clara_Schumann_1878.jpg"></td></tr>
+            </tbody></table>
+            </body></html>"#;
+        assert_eq!(
+            parse_infobox_image(&Html::parse_document(icons_first)).as_deref(),
+            Some("https://upload.wikimedia.org/Clara_Schumann_1878.jpg")
+        );
+
+        // Without an image cell, the infobox's first image is the best guess
+        let no_image_cell = r#"<html><body>
+            <table class="infobox"><tbody>
+              <tr><td colspan="2"><img src="https://upload.wikimedia.org/Hildegard.jpg"></td></tr>
+            </tbody></table>
+            </body></html>"#;
+        assert_eq!(
+            parse_infobox_image(&Html::parse_document(no_image_cell)).as_deref(),
+            Some("https://upload.wikimedia.org/Hildegard.jpg")
+        );
+    }
+
+    #[test]
+    fn splits_places_from_dates_in_a_composer_infobox() {
+        // Trimmed from the infoboxes of "Clara Schumann" and "Arvo Pärt"
+        let clara = r#"<html><body>
+            <table class="infobox biography vcard"><tbody>
+              <tr><th scope="row" class="infobox-label">Born</th><td class="infobox-data">Clara Josephine Wieck<br><span style="display:none">(<span class="bday">1819-09-13</span>)</span>13 September 1819<br><a href="/wiki/Leipzig">Leipzig</a>, <a href="/wiki/Kingdom_of_Saxony">Kingdom of Saxony</a></td></tr>
+              <tr><th scope="row" class="infobox-label">Died</th><td class="infobox-data">20 May 1896<span style="display:none">(1896-05-21)</span> (aged 76)<br><a href="/wiki/Frankfurt">Frankfurt</a>, <a href="/wiki/German_Empire">German Empire</a><sup class="reference">[1]</sup></td></tr>
+              <tr><th scope="row" class="infobox-label">Occupations</th><td class="infobox-data">Pianist, composer</td></tr>
+            </tbody></table>
+            </body></html>"#;
+        let (born, died) = parse_birth_death_places(&Html::parse_document(clara));
+        assert_eq!(born.as_deref(), Some("Leipzig, Kingdom of Saxony"));
+        assert_eq!(died.as_deref(), Some("Frankfurt, German Empire"));
+
+        let part = r#"<html><body>
+            <table class="infobox biography vcard"><tbody>
+              <tr><th scope="row" class="infobox-label">Born</th><td class="infobox-data"><span style="display:none">(<span class="bday">1935-09-11</span>)</span>11 September 1935 (age 90)<br><div class="birthplace"><a href="/wiki/Paide">Paide</a>, Estonia</div></td></tr>
+            </tbody></table>
+            </body></html>"#;
+        let (born, died) = parse_birth_death_places(&Html::parse_document(part));
+        assert_eq!(born.as_deref(), Some("Paide, Estonia"));
+        assert_eq!(died, None);
+    }
+}
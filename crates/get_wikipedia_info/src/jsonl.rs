@@ -0,0 +1,158 @@
+use anyhow::Result;
+use async_compression::tokio::{bufread::GzipDecoder, write::GzipEncoder};
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, Lines},
+};
+use tracing::warn;
+
+pub type JsonlWriter = Box<dyn AsyncWrite + Unpin + Send>;
+pub type JsonlLines = Lines<Box<dyn AsyncBufRead + Unpin + Send>>;
+
+fn is_gzip(path: &str) -> bool {
+    path.ends_with(".gz")
+}
+
+// Open a JSONL file for writing, gzip-compressed when the name ends in ".gz".
+// Appending to a .gz file adds another gzip member, which open_lines reads
+// back as one stream. Call shutdown() when done so the gzip trailer is written.
+pub async fn create_writer(filename: &str, append: bool) -> Result<JsonlWriter> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(filename)
+        .await?;
+    let writer = BufWriter::new(file);
+
+    Ok(if is_gzip(filename) {
+        Box::new(GzipEncoder::new(writer))
+    } else {
+        Box::new(writer)
+    })
+}
+
+// The line and its newline go out in one write, so whatever the buffer
+// flushes always ends on a complete line
+pub async fn write_line<T: Serialize>(writer: &mut JsonlWriter, value: &T) -> Result<()> {
+    let mut json_line = serde_json::to_string(value)?;
+    json_line.push('\n');
+    writer.write_all(json_line.as_bytes()).await?;
+    Ok(())
+}
+
+// Serialize `value`, then read the line back as T and serialize that again:
+// a line that isn't valid JSON, or that doesn't read back as the same
+// value, is an error instead of output.
+pub fn validated_line<T: Serialize + DeserializeOwned>(value: &T) -> Result<String> {
+    let json_line = serde_json::to_string(value)?;
+    let parsed: serde_json::Value = serde_json::from_str(&json_line)?;
+    let read_back: T = serde_json::from_value(parsed.clone())?;
+    if serde_json::to_value(&read_back)? != parsed {
+        anyhow::bail!("record does not round-trip: {}", json_line);
+    }
+    Ok(json_line)
+}
+
+// write_line for strict mode: a record failing validated_line is logged and
+// skipped rather than written. Returns whether the record was written.
+pub async fn write_line_strict<T: Serialize + DeserializeOwned>(
+    writer: &mut JsonlWriter,
+    value: &T,
+) -> Result<bool> {
+    match validated_line(value) {
+        Ok(mut json_line) => {
+            json_line.push('\n');
+            writer.write_all(json_line.as_bytes()).await?;
+            Ok(true)
+        }
+        Err(e) => {
+            warn!("Skipping record that failed validation: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+// Lines of a .json or .json.gz file, chosen by extension
+pub async fn open_lines(path: &str) -> Result<JsonlLines> {
+    let reader = BufReader::new(File::open(path).await?);
+
+    let reader: Box<dyn AsyncBufRead + Unpin + Send> = if is_gzip(path) {
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        Box::new(BufReader::new(decoder))
+    } else {
+        Box::new(reader)
+    };
+    Ok(reader.lines())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written under one field name and read under another, so it never
+    // reads back
+    #[derive(Serialize, serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Mismatched {
+        #[serde(rename(serialize = "written", deserialize = "read"))]
+        value: u32,
+    }
+
+    #[tokio::test]
+    async fn strict_writes_skip_records_that_fail_validation() {
+        let path = std::env::temp_dir().join(format!("jsonl-strict-{}.json", std::process::id()));
+        let filename = path.to_str().unwrap();
+
+        let mut writer = create_writer(filename, false).await.unwrap();
+        assert!(
+            write_line_strict(&mut writer, &"before".to_string())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !write_line_strict(&mut writer, &Mismatched { value: 1 })
+                .await
+                .unwrap()
+        );
+        assert!(
+            write_line_strict(&mut writer, &"after".to_string())
+                .await
+                .unwrap()
+        );
+        writer.shutdown().await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "\"before\"\n\"after\"\n");
+    }
+
+    #[tokio::test]
+    async fn gzip_round_trip_across_appends() {
+        let path = std::env::temp_dir().join(format!("jsonl-{}.json.gz", std::process::id()));
+        let filename = path.to_str().unwrap();
+
+        for (append, values) in [(false, ["a", "b"]), (true, ["c", "d"])] {
+            let mut writer = create_writer(filename, append).await.unwrap();
+            for value in values {
+                write_line(&mut writer, &value).await.unwrap();
+            }
+            writer.shutdown().await.unwrap();
+        }
+
+        let raw = std::fs::read(&path).unwrap();
+        assert_eq!(&raw[..2], &[0x1f, 0x8b]);
+
+        let mut lines = open_lines(filename).await.unwrap();
+        let mut read = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            read.push(serde_json::from_str::<String>(&line).unwrap());
+        }
+        assert_eq!(read, vec!["a", "b", "c", "d"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+pub mod attribution;
+pub mod batch;
+#[cfg(feature = "tui")]
+pub mod browse;
+pub mod composers;
+pub mod config;
+pub mod crossref;
+pub mod diff;
+pub mod genre;
+pub mod http;
+pub mod infobox;
+pub mod jsonl;
+pub mod links;
+#[cfg(feature = "parquet")]
+pub mod parquet_output;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod summary;
+pub mod text;
+pub mod wikitext;
+pub mod works;
+pub mod works_index;
+
+pub use composers::{Composer, QualityOfYearInfo};
+pub use works::{
+    Composition, CompositionReader, Field, FieldCanonicalizer, FieldExtractor, RawCompositionData,
+    canonicalize_raw_data_with, read_compositions,
+};
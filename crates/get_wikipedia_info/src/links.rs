@@ -0,0 +1,223 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{info, warn};
+
+use crate::http::{HttpOptions, RateLimiter, build_client};
+use crate::works::read_compositions;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LinkCheck {
+    pub url: String,
+    pub final_url: Option<String>,
+    pub status: Option<u16>,
+    pub redirected: bool,
+    pub error: Option<String>,
+}
+
+impl LinkCheck {
+    pub fn is_broken(&self) -> bool {
+        self.status.is_none_or(|status| status >= 400)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkReport {
+    pub checked: usize,
+    pub ok: usize,
+    pub broken: Vec<LinkCheck>,
+    pub redirected: Vec<LinkCheck>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinkCheckOptions {
+    pub concurrency: usize,
+    pub requests_per_second: f64,
+    pub http: HttpOptions,
+}
+
+impl Default for LinkCheckOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            requests_per_second: 5.0,
+            http: HttpOptions::default(),
+        }
+    }
+}
+
+// HEAD the url (following redirects); fall back to GET for servers that
+// refuse HEAD.
+async fn check_url(client: &reqwest::Client, url: &str) -> LinkCheck {
+    let response = match client.head(url).send().await {
+        Ok(response) if response.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED => {
+            client.get(url).send().await
+        }
+        other => other,
+    };
+
+    match response {
+        Ok(response) => {
+            let final_url = response.url().to_string();
+            LinkCheck {
+                url: url.to_string(),
+                redirected: final_url != url,
+                final_url: Some(final_url),
+                status: Some(response.status().as_u16()),
+                error: None,
+            }
+        }
+        Err(e) => LinkCheck {
+            url: url.to_string(),
+            final_url: None,
+            status: None,
+            redirected: false,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+pub async fn check_urls(
+    client: &reqwest::Client,
+    limiter: Arc<RateLimiter>,
+    urls: Vec<String>,
+    concurrency: usize,
+) -> Vec<LinkCheck> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+
+    for url in urls {
+        let client = client.clone();
+        let limiter = limiter.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            limiter.acquire().await;
+            check_url(&client, &url).await
+        });
+    }
+
+    let mut checks = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok(check) => checks.push(check),
+            Err(e) => warn!("Link check task failed: {}", e),
+        }
+    }
+    checks.sort_by(|a, b| a.url.cmp(&b.url));
+    checks
+}
+
+pub fn build_report(checks: Vec<LinkCheck>) -> LinkReport {
+    let checked = checks.len();
+    let (broken, working): (Vec<_>, Vec<_>) = checks.into_iter().partition(|c| c.is_broken());
+    let ok = working.len();
+    let redirected = working.into_iter().filter(|c| c.redirected).collect();
+
+    LinkReport {
+        checked,
+        ok,
+        broken,
+        redirected,
+    }
+}
+
+// Check every distinct work_url in a compositions JSONL file and write the
+// broken/redirected ones to `report_path`.
+pub async fn check_links(
+    compositions_path: &str,
+    report_path: &str,
+    options: &LinkCheckOptions,
+) -> Result<LinkReport> {
+    let mut urls = BTreeSet::new();
+    let mut reader = read_compositions(compositions_path).await?;
+    while let Some(composition) = reader.next().await {
+        if let Some(url) = composition?.work_url {
+            urls.insert(url);
+        }
+    }
+
+    info!(
+        "Checking {} distinct work URLs from {}",
+        urls.len(),
+        compositions_path
+    );
+
+    let client = build_client(&options.http)?;
+    let limiter = Arc::new(RateLimiter::new(options.requests_per_second));
+    let checks = check_urls(
+        &client,
+        limiter,
+        urls.into_iter().collect(),
+        options.concurrency,
+    )
+    .await;
+
+    let report = build_report(checks);
+    tokio::fs::write(report_path, serde_json::to_string_pretty(&report)?).await?;
+
+    info!(
+        "{} of {} links OK, {} broken, {} redirected; report written to {}",
+        report.ok,
+        report.checked,
+        report.broken.len(),
+        report.redirected.len(),
+        report_path
+    );
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn reports_missing_and_redirected_links() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/wiki/Ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/wiki/Gone"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/wiki/Moved"))
+            .respond_with(
+                ResponseTemplate::new(301)
+                    .insert_header("Location", format!("{}/wiki/Ok", server.uri())),
+            )
+            .mount(&server)
+            .await;
+
+        let urls = ["Ok", "Gone", "Moved"]
+            .iter()
+            .map(|page| format!("{}/wiki/{}", server.uri(), page))
+            .collect();
+        let checks = check_urls(
+            &build_client(&HttpOptions::default()).unwrap(),
+            Arc::new(RateLimiter::new(100.0)),
+            urls,
+            2,
+        )
+        .await;
+        let report = build_report(checks);
+
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.ok, 2);
+        assert_eq!(report.broken.len(), 1);
+        assert!(report.broken[0].url.ends_with("/wiki/Gone"));
+        assert_eq!(report.broken[0].status, Some(404));
+        assert_eq!(report.redirected.len(), 1);
+        assert!(report.redirected[0].url.ends_with("/wiki/Moved"));
+        assert_eq!(report.redirected[0].status, Some(200));
+    }
+}
@@ -1,21 +1,578 @@
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
 
-mod composers;
-use composers::get_composers;
+use get_wikipedia_info::http::{HttpOptions, RequestBudget, ServiceLimit};
+use get_wikipedia_info::infobox::DeepOptions;
 
-mod works;
-use works::get_works;
+use get_wikipedia_info::batch::{BatchOptions, get_works_for_composers};
+use get_wikipedia_info::composers::{ComposerOptions, DEFAULT_COMPOSER_LIST_URL, get_composers};
+use get_wikipedia_info::config::{Config, config_path, layered_args};
+use get_wikipedia_info::crossref::cross_reference;
+use get_wikipedia_info::diff::diff_compositions;
+use get_wikipedia_info::links::{LinkCheckOptions, check_links};
+use get_wikipedia_info::works::{
+    self, ComposerHeadings, FlushPolicy, OutputFormat, ReprocessAllOptions, ReprocessOptions,
+    TableLimits, WorksOptions, get_works, reprocess_all, reprocess_to_file,
+};
+
+#[derive(Parser)]
+#[command(about = "Scrape classical composers and their works from Wikipedia")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Settings file (default config.toml, if present): top-level keys for global flags and a
+    /// [subcommand] table for each subcommand's, e.g. `timeout_secs = 60` or `[batch] deep = true`.
+    /// Precedence: defaults < file < GET_WIKIPEDIA_INFO_<FLAG> env vars < command line.
+    #[arg(long, global = true)]
+    config: Option<String>,
+    /// Log output format; levels are filtered with RUST_LOG (default "info")
+    #[arg(long, value_enum, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Timeout for each HTTP request; timed-out requests are retried
+    #[arg(long, global = true, default_value_t = 30)]
+    timeout_secs: u64,
+    /// Idle connections kept open per host in the shared HTTP client (default: no limit)
+    #[arg(long, global = true)]
+    pool_max_idle_per_host: Option<usize>,
+    /// Stop after this many HTTP requests in total (retries included). A batch run then writes
+    /// what it has and lists the composers it didn't reach in its report, for --retry-failed
+    #[arg(long, global = true)]
+    request_budget: Option<u64>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scrape the list of composers into composers.json (JSONL)
+    Composers {
+        /// Composer list or category page to start from; "next page" links are followed
+        #[arg(long, default_value = DEFAULT_COMPOSER_LIST_URL)]
+        composer_list_url: String,
+        /// Keep the existing composers.json and append only composers not already in it (by URL)
+        #[arg(long)]
+        append_composer: bool,
+        /// Fetch each composer's article and store its lead paragraph
+        #[arg(long)]
+        summaries: bool,
+        /// Longest summary kept with --summaries, in characters
+        #[arg(long, default_value_t = 500)]
+        summary_max_chars: usize,
+        /// Fetch each composer's article and store the birth and death places from its infobox
+        #[arg(long)]
+        places: bool,
+        /// Fetch each composer's article and store the URL of its infobox portrait
+        #[arg(long)]
+        images: bool,
+        /// Maximum composer articles fetched at once with --summaries/--places/--images
+        #[arg(long, default_value_t = 4)]
+        article_concurrency: usize,
+        #[arg(long, default_value_t = 2.0)]
+        article_requests_per_second: f64,
+    },
+    /// Scrape one composer's works into raw-info-<name>.json, compositions.json and
+    /// composer-summary-<name>.json
+    Works {
+        #[arg(long, default_value = "Igor Stravinsky")]
+        composer: String,
+        /// Scrape this page instead of guessing "List of compositions by <composer>"; rows are
+        /// still attributed to --composer
+        #[arg(long)]
+        works_url: Option<String>,
+        /// Scrape an already-downloaded works page instead of fetching one (same as
+        /// --works-url file://<path>)
+        #[arg(long, conflicts_with = "works_url")]
+        html_file: Option<String>,
+        /// Only estimate how many works the list page has (warning at 1000 or more), without
+        /// canonicalizing or writing anything
+        #[arg(long)]
+        preflight: bool,
+        #[command(flatten)]
+        works: WorksArgs,
+    },
+    /// Scrape works for every composer in composers.json and write run-report.json
+    Batch {
+        #[arg(long, default_value = "composers.json")]
+        composers_file: String,
+        /// Only scrape the first N composers
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Scrape N composers chosen at random from the list
+        #[arg(long)]
+        sample: Option<usize>,
+        /// Seed for --sample, to repeat the same selection
+        #[arg(long, requires = "sample")]
+        seed: Option<u64>,
+        /// Re-attempt only the composers that failed, or that a spent --request-budget left
+        /// unreached, in this earlier run report
+        #[arg(long)]
+        retry_failed: Option<String>,
+        /// Composers scraped at once, sharing one HTTP client and request budget
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        #[arg(long, default_value = "run-report.json")]
+        report: String,
+        #[command(flatten)]
+        works: WorksArgs,
+    },
+    /// Rebuild compositions from a raw-info-<name>.json file without re-scraping
+    Reprocess {
+        #[arg(long)]
+        raw_file: String,
+        #[arg(long, default_value = "compositions.json")]
+        output: String,
+        /// Skip the first N raw lines, e.g. those handled before a crash
+        #[arg(long, default_value_t = 0)]
+        skip: usize,
+        /// Append to the output file instead of overwriting it
+        #[arg(long)]
+        append: bool,
+        /// Read every raw line as this schema version (default: each line's schema_version, 0 if absent)
+        #[arg(long)]
+        format_version: Option<u32>,
+        /// Write field_provenance (the source header behind each canonical field) with each
+        /// composition
+        #[arg(long)]
+        debug: bool,
+    },
+    /// Rebuild compositions for every raw-info-<name>.json in a directory, each into
+    /// compositions-<name>.json beside it (or all into --output)
+    ReprocessAll {
+        dir: String,
+        /// Write all compositions to this one file instead
+        #[arg(long)]
+        output: Option<String>,
+        /// Raw files reprocessed at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        #[arg(long)]
+        format_version: Option<u32>,
+    },
+    /// Serve GET /works?composer=<name>, scraping on demand and streaming the compositions as
+    /// NDJSON; results are cached per composer while the server runs
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+        #[command(flatten)]
+        works: WorksArgs,
+    },
+    /// Browse a compositions file interactively; Enter opens the work_url
+    #[cfg(feature = "tui")]
+    Browse {
+        #[arg(long, default_value = "compositions.json")]
+        input: String,
+    },
+    /// Compare two compositions files and write what was added, removed or changed
+    Diff {
+        #[arg(long)]
+        old: String,
+        #[arg(long)]
+        new: String,
+        #[arg(long, default_value = "compositions-diff.json")]
+        output: String,
+    },
+    /// Check every composition's composer against composers.json and report the orphans
+    CheckComposers {
+        #[arg(long, default_value = "compositions.json")]
+        input: String,
+        #[arg(long, default_value = "composers.json")]
+        composers_file: String,
+        #[arg(long, default_value = "orphan-report.json")]
+        output: String,
+        /// Also write the compositions here with composer_known set
+        #[arg(long)]
+        tagged_output: Option<String>,
+    },
+    /// Check that every work_url in a compositions file still resolves
+    CheckLinks {
+        #[arg(long, default_value = "compositions.json")]
+        input: String,
+        #[arg(long, default_value = "link-report.json")]
+        output: String,
+        /// Maximum number of requests in flight
+        #[arg(long, default_value_t = 8)]
+        concurrency: usize,
+        #[arg(long, default_value_t = 5.0)]
+        requests_per_second: f64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Jsonl,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl From<FormatArg> for OutputFormat {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Jsonl => OutputFormat::Jsonl,
+            #[cfg(feature = "parquet")]
+            FormatArg::Parquet => OutputFormat::Parquet,
+        }
+    }
+}
+
+#[derive(Args)]
+struct WorksArgs {
+    /// Output format for canonical compositions
+    #[arg(long, value_enum, default_value_t = FormatArg::Jsonl)]
+    format: FormatArg,
+    /// Leave raw_data out of compositions.json (it is still in raw-info-<name>.json)
+    #[arg(long)]
+    slim: bool,
+    /// Write compositions-<genre>.json per normalized genre (e.g. compositions-symphony.json,
+    /// compositions-other.json for unknown genres) instead of compositions.json; JSONL only
+    #[arg(long)]
+    split_by_genre: bool,
+    /// JSON file with extra header -> canonical field mappings and headers for headerless tables
+    #[arg(long)]
+    field_map: Option<String>,
+    /// Fetch each work's own article and fill missing fields from its infobox
+    #[arg(long)]
+    deep: bool,
+    /// Maximum work pages fetched at once with --deep
+    #[arg(long, default_value_t = 4)]
+    deep_concurrency: usize,
+    #[arg(long, default_value_t = 2.0)]
+    deep_requests_per_second: f64,
+    /// Gzip the raw and compositions JSONL output (*.json.gz)
+    #[arg(long)]
+    gzip: bool,
+    /// Also write each table's raw rows, headers, caption and classes to
+    /// debug-tables-<name>/table-<index>.json
+    #[arg(long)]
+    debug_tables: bool,
+    /// Don't parse the list page's wikitext when its HTML has no usable tables
+    #[arg(long)]
+    no_wikitext_fallback: bool,
+    /// When the works list is split across pages such as "(A–M)" and "(N–Z)", also fetch the
+    /// parts linked from the list page and merge them, dropping works listed twice
+    #[arg(long)]
+    follow_continuations: bool,
+    /// Skip tables with more rows than this (navboxes, indexes)
+    #[arg(long)]
+    max_rows_per_table: Option<usize>,
+    /// Read at most this many tables per page
+    #[arg(long)]
+    max_tables: Option<usize>,
+    /// Skip tables with fewer columns than this (0 to keep every table)
+    #[arg(long, default_value_t = 2)]
+    min_columns: usize,
+    /// Flush compositions output to disk every N records (0 to only flush at the end)
+    #[arg(long, default_value_t = 100)]
+    flush_every: usize,
+    /// Also flush compositions output every N seconds (0 to disable)
+    #[arg(long, default_value_t = 5)]
+    flush_interval_secs: u64,
+    /// Also write this run's compositions as an indented JSON array to compositions-pretty.json,
+    /// for reading by hand. Held in memory until the run ends, so best for small scrapes.
+    #[arg(long)]
+    pretty: bool,
+    /// Existing compositions file to deduplicate against: compositions already in it (same
+    /// title, composer and catalog number or opus) are skipped. May be the output file itself.
+    #[arg(long)]
+    master: Option<String>,
+    /// Write field_provenance (the source header behind each canonical field) with each
+    /// composition, to audit how headers were mapped
+    #[arg(long)]
+    debug: bool,
+    /// Also write <composer>.txt listing the works one "Composer - Title (Year)" per line, in
+    /// catalogue order
+    #[arg(long)]
+    index: bool,
+    /// Only write compositions that link to their own work article
+    #[arg(long)]
+    only_with_url: bool,
+    /// Keep summary rows such as "Total: 104 symphonies" as compositions instead of skipping them
+    #[arg(long)]
+    keep_total_rows: bool,
+    /// Cut each row's raw_html_snippet in raw-info-<name>.json to this many bytes, ending
+    /// with "…"; 0 leaves snippets out (default: keep them whole)
+    #[arg(long)]
+    max_snippet_bytes: Option<usize>,
+    /// Check each record reads back from its JSON before writing it, skipping any that
+    /// don't, and report tables whose rows don't line up with their headers in
+    /// composer-summary-<name>.json (always on in debug builds)
+    #[arg(long)]
+    strict: bool,
+    /// The list page covers several composers: attribute each section's works to the composer
+    /// its heading names (a name with life years, or one given with --section-composer)
+    #[arg(long)]
+    composer_sections: bool,
+    /// A composer whose name heads a section of a combined list page (repeatable)
+    #[arg(long = "section-composer", requires = "composer_sections")]
+    section_composers: Vec<String>,
+}
+
+impl WorksArgs {
+    fn into_options(self, http: HttpOptions) -> WorksOptions {
+        WorksOptions {
+            format: self.format.into(),
+            http,
+            slim: self.slim,
+            split_by_genre: self.split_by_genre,
+            field_map: self.field_map,
+            deep: self.deep.then_some(DeepOptions {
+                concurrency: self.deep_concurrency,
+                requests_per_second: self.deep_requests_per_second,
+            }),
+            gzip: self.gzip,
+            debug_tables: self.debug_tables,
+            wikitext_fallback: !self.no_wikitext_fallback,
+            follow_continuations: self.follow_continuations,
+            table_limits: TableLimits {
+                max_rows_per_table: self.max_rows_per_table,
+                max_tables: self.max_tables,
+                min_columns: self.min_columns,
+            },
+            flush: FlushPolicy {
+                every_records: (self.flush_every > 0).then_some(self.flush_every),
+                interval: (self.flush_interval_secs > 0)
+                    .then(|| Duration::from_secs(self.flush_interval_secs)),
+            },
+            only_with_url: self.only_with_url,
+            keep_total_rows: self.keep_total_rows,
+            max_snippet_bytes: self.max_snippet_bytes,
+            pretty: self.pretty,
+            master: self.master.clone(),
+            debug: self.debug,
+            index: self.index,
+            strict: self.strict || cfg!(debug_assertions),
+            composer_sections: self.composer_sections.then_some(ComposerHeadings {
+                names: self.section_composers,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+fn init_logging(format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+// The command line layered over the config file and environment
+async fn parse_cli() -> anyhow::Result<Cli> {
+    let command = Cli::command();
+    let argv: Vec<String> = std::env::args().collect();
+    let config = match config_path(&command, &argv) {
+        Some(path) => Config::load(&path).await?,
+        None => Config::default(),
+    };
+    let argv = layered_args(&command, argv, &config, |name| std::env::var(name).ok())?;
+    Ok(Cli::parse_from(argv))
+}
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    let cli = match parse_cli().await {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(2);
+        }
+    };
+    init_logging(cli.log_format);
 
-    info!("Assuming we already retrieved list of composers.");
-    // To output jsonl with composers
-    // let _ = get_composers(); // outputs a composers.json file in jsonl format.
+    let http = HttpOptions {
+        timeout: Duration::from_secs(cli.timeout_secs),
+        pool_max_idle_per_host: cli.pool_max_idle_per_host,
+        request_budget: cli
+            .request_budget
+            .map(|limit| Arc::new(RequestBudget::new(limit))),
+        ..HttpOptions::default()
+    };
 
-    info!("Let's retrieve 1 composer");
-    // works
-    let composer_name = "Igor Stravinsky"; // "Wolfgang_Amadeus_Mozart"; // "Ludwig_van_Beethoven"; // "Johann_Sebastian_Bach"; // "Giuseppe_Verdi";
-    let _ = get_works(&composer_name).await;
+    match cli.command {
+        Command::Composers {
+            composer_list_url,
+            append_composer,
+            summaries,
+            summary_max_chars,
+            places,
+            images,
+            article_concurrency,
+            article_requests_per_second,
+        } => {
+            let options = ComposerOptions {
+                append: append_composer,
+                summary_max_chars: summaries.then_some(summary_max_chars),
+                places,
+                images,
+                article_limit: ServiceLimit {
+                    concurrency: article_concurrency,
+                    requests_per_second: article_requests_per_second,
+                },
+            };
+            // outputs a composers.json file in jsonl format.
+            get_composers(&composer_list_url, &http, &options).await;
+        }
+        Command::Works {
+            composer,
+            works_url,
+            html_file,
+            preflight,
+            works,
+        } => {
+            let options = WorksOptions {
+                works_url: html_file
+                    .map(|path| format!("file://{}", path))
+                    .or(works_url),
+                ..works.into_options(http)
+            };
+            if preflight {
+                if let Err(e) = works::preflight(&composer, &options).await {
+                    error!("Error estimating works for {}: {}", composer, e);
+                }
+            } else {
+                info!("Let's retrieve 1 composer");
+                if let Err(e) = get_works(&composer, &options).await {
+                    error!("Error getting works for {}: {}", composer, e);
+                }
+            }
+        }
+        Command::Batch {
+            composers_file,
+            limit,
+            sample,
+            seed,
+            retry_failed,
+            concurrency,
+            report,
+            works,
+        } => {
+            let options = BatchOptions {
+                composers_file,
+                limit,
+                sample,
+                seed,
+                retry_failed,
+                concurrency,
+                report_file: report,
+                works: works.into_options(http),
+            };
+            if let Err(e) = get_works_for_composers(&options).await {
+                error!("Error running batch: {}", e);
+            }
+        }
+        Command::Reprocess {
+            raw_file,
+            output,
+            skip,
+            append,
+            format_version,
+            debug,
+        } => {
+            let options = ReprocessOptions {
+                output,
+                skip,
+                append,
+                format_version,
+                debug,
+            };
+            if let Err(e) = reprocess_to_file(&raw_file, &options).await {
+                error!("Error reprocessing {}: {}", raw_file, e);
+            }
+        }
+        Command::ReprocessAll {
+            dir,
+            output,
+            concurrency,
+            format_version,
+        } => {
+            let options = ReprocessAllOptions {
+                combined_output: output,
+                concurrency,
+                format_version,
+            };
+            match reprocess_all(&dir, &options).await {
+                Ok(totals) => {
+                    info!(
+                        "Reprocessed {} raw files into {} compositions; {} failed",
+                        totals.files,
+                        totals.compositions,
+                        totals.failed.len()
+                    );
+                    for (raw_file, e) in &totals.failed {
+                        error!("{}: {}", raw_file, e);
+                    }
+                }
+                Err(e) => error!("Error reprocessing {}: {}", dir, e),
+            }
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { addr, works } => {
+            let result = match tokio::net::TcpListener::bind(&addr).await {
+                Ok(listener) => {
+                    get_wikipedia_info::server::serve(listener, works.into_options(http)).await
+                }
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                error!("Error serving on {}: {}", addr, e);
+            }
+        }
+        #[cfg(feature = "tui")]
+        Command::Browse { input } => {
+            if let Err(e) = get_wikipedia_info::browse::browse(&input).await {
+                error!("Error browsing {}: {}", input, e);
+            }
+        }
+        Command::Diff { old, new, output } => match diff_compositions(&old, &new).await {
+            Ok(diff) => {
+                info!(
+                    "{} added, {} removed, {} changed",
+                    diff.added.len(),
+                    diff.removed.len(),
+                    diff.changed.len()
+                );
+                if let Err(e) = diff.save(&output).await {
+                    error!("Error writing {}: {}", output, e);
+                }
+            }
+            Err(e) => error!("Error comparing {} and {}: {}", old, new, e),
+        },
+        Command::CheckComposers {
+            input,
+            composers_file,
+            output,
+            tagged_output,
+        } => {
+            if let Err(e) =
+                cross_reference(&input, &composers_file, &output, tagged_output.as_deref()).await
+            {
+                error!("Error checking composers in {}: {}", input, e);
+            }
+        }
+        Command::CheckLinks {
+            input,
+            output,
+            concurrency,
+            requests_per_second,
+        } => {
+            let options = LinkCheckOptions {
+                concurrency,
+                requests_per_second,
+                http,
+            };
+            if let Err(e) = check_links(&input, &output, &options).await {
+                error!("Error checking links in {}: {}", input, e);
+            }
+        }
+    }
 }
@@ -0,0 +1,262 @@
+use anyhow::Result;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float32Array, Int32Array, RecordBatch, StringArray, UInt32Array,
+    builder::{ListBuilder, StringBuilder},
+};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use std::{fs::File, sync::Arc};
+
+use crate::works::Composition;
+
+// Rows per Parquet row group / record batch
+const BATCH_SIZE: usize = 1024;
+
+// Flat columns mirror Composition; the Vec fields become list<utf8> columns
+// and additional_info is stored as a JSON object string. raw_data is left
+// out: it is available in the raw-info-*.json files.
+pub fn compositions_schema() -> Arc<Schema> {
+    let text = |name: &str, nullable: bool| Field::new(name, DataType::Utf8, nullable);
+    let list = |name: &str| {
+        Field::new(
+            name,
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        )
+    };
+
+    Arc::new(Schema::new(vec![
+        text("composer_name", false),
+        text("composer_url", false),
+        text("source_url", false),
+        text("title", false),
+        list("alt_titles"),
+        text("work_url", true),
+        text("year", true),
+        text("year_confidence", true),
+        text("composition_year", true),
+        text("publication_year", true),
+        Field::new("year_numeric", DataType::Int32, true),
+        text("year_qualifier", true),
+        Field::new("year_approximate", DataType::Boolean, false),
+        text("key", true),
+        text("opus", true),
+        text("genre", true),
+        text("catalog_number", true),
+        list("key_all"),
+        list("opus_all"),
+        list("catalog_number_all"),
+        text("catalog_sort_key", true),
+        text("instrumentation", true),
+        text("duration", true),
+        Field::new("duration_seconds", DataType::UInt32, true),
+        text("iswc", true),
+        Field::new("is_arrangement", DataType::Boolean, false),
+        text("arrangement_of", true),
+        text("status", false),
+        Field::new("confidence", DataType::Float32, false),
+        text("additional_info", false),
+    ]))
+}
+
+fn text_column<'a>(
+    compositions: &'a [Composition],
+    value: impl Fn(&'a Composition) -> Option<&'a str>,
+) -> ArrayRef {
+    Arc::new(compositions.iter().map(value).collect::<StringArray>())
+}
+
+fn list_column<'a>(
+    compositions: &'a [Composition],
+    values: impl Fn(&'a Composition) -> &'a [String],
+) -> ArrayRef {
+    let mut builder = ListBuilder::new(StringBuilder::new());
+    for composition in compositions {
+        for value in values(composition) {
+            builder.values().append_value(value);
+        }
+        builder.append(true);
+    }
+    Arc::new(builder.finish())
+}
+
+fn record_batch(compositions: &[Composition]) -> Result<RecordBatch> {
+    let additional_info = compositions
+        .iter()
+        .map(|c| serde_json::to_string(&c.additional_info))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns = vec![
+        text_column(compositions, |c| Some(c.composer_name.as_str())),
+        text_column(compositions, |c| Some(c.composer_url.as_str())),
+        text_column(compositions, |c| Some(c.source_url.as_str())),
+        text_column(compositions, |c| Some(c.title.as_str())),
+        list_column(compositions, |c| &c.alt_titles),
+        text_column(compositions, |c| c.work_url.as_deref()),
+        text_column(compositions, |c| c.year.as_deref()),
+        text_column(compositions, |c| c.year_confidence.map(|y| y.as_str())),
+        text_column(compositions, |c| c.composition_year.as_deref()),
+        text_column(compositions, |c| c.publication_year.as_deref()),
+        Arc::new(
+            compositions
+                .iter()
+                .map(|c| c.year_numeric)
+                .collect::<Int32Array>(),
+        ),
+        text_column(compositions, |c| c.year_qualifier.map(|q| q.as_str())),
+        Arc::new(
+            compositions
+                .iter()
+                .map(|c| Some(c.year_approximate))
+                .collect::<BooleanArray>(),
+        ),
+        text_column(compositions, |c| c.key.as_deref()),
+        text_column(compositions, |c| c.opus.as_deref()),
+        text_column(compositions, |c| c.genre.as_deref()),
+        text_column(compositions, |c| c.catalog_number.as_deref()),
+        list_column(compositions, |c| &c.key_all),
+        list_column(compositions, |c| &c.opus_all),
+        list_column(compositions, |c| &c.catalog_number_all),
+        text_column(compositions, |c| c.catalog_sort_key.as_deref()),
+        text_column(compositions, |c| c.instrumentation.as_deref()),
+        text_column(compositions, |c| c.duration.as_deref()),
+        Arc::new(
+            compositions
+                .iter()
+                .map(|c| c.duration_seconds)
+                .collect::<UInt32Array>(),
+        ),
+        text_column(compositions, |c| c.iswc.as_deref()),
+        Arc::new(
+            compositions
+                .iter()
+                .map(|c| Some(c.is_arrangement))
+                .collect::<BooleanArray>(),
+        ),
+        text_column(compositions, |c| c.arrangement_of.as_deref()),
+        text_column(compositions, |c| Some(c.status.as_str())),
+        Arc::new(
+            compositions
+                .iter()
+                .map(|c| Some(c.confidence))
+                .collect::<Float32Array>(),
+        ),
+        Arc::new(StringArray::from(additional_info)) as ArrayRef,
+    ];
+
+    Ok(RecordBatch::try_new(compositions_schema(), columns)?)
+}
+
+// Write compositions to a Parquet file in batches of BATCH_SIZE rows. Unlike
+// the JSONL writer this replaces any existing file.
+pub fn write_compositions_parquet(compositions: &[Composition], filename: &str) -> Result<()> {
+    let file = File::create(filename)?;
+    let mut writer = ArrowWriter::try_new(file, compositions_schema(), None)?;
+
+    for chunk in compositions.chunks(BATCH_SIZE) {
+        writer.write(&record_batch(chunk)?)?;
+    }
+
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genre::Genre;
+    use crate::works::{WorkStatus, YearConfidence, YearQualifier};
+    use arrow_array::{Array, ListArray};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::collections::HashMap;
+
+    fn composition(title: &str, opus_all: &[&str]) -> Composition {
+        Composition {
+            composer_name: "Frédéric Chopin".to_string(),
+            composer_url: "https://en.wikipedia.org/wiki/Fr%C3%A9d%C3%A9ric_Chopin".to_string(),
+            source_url:
+                "https://en.wikipedia.org/wiki/List_of_compositions_by_Fr%C3%A9d%C3%A9ric_Chopin"
+                    .to_string(),
+            title: title.to_string(),
+            alt_titles: Vec::new(),
+            work_url: None,
+            year: Some("1830".to_string()),
+            year_confidence: Some(YearConfidence::High),
+            composition_year: None,
+            publication_year: None,
+            year_numeric: Some(1830),
+            year_qualifier: Some(YearQualifier::Exact),
+            year_approximate: false,
+            key: None,
+            opus: opus_all.first().map(|o| o.to_string()),
+            genre: Some("Nocturne".to_string()),
+            genres: vec![Genre::Keyboard],
+            catalog_number: None,
+            key_all: Vec::new(),
+            opus_all: opus_all.iter().map(|o| o.to_string()).collect(),
+            opus_detail: None,
+            catalog_number_all: Vec::new(),
+            catalog_sort_key: None,
+            instrumentation: None,
+            duration: None,
+            duration_seconds: None,
+            iswc: None,
+            is_arrangement: false,
+            arrangement_of: None,
+            status: WorkStatus::Complete,
+            confidence: 1.0,
+            composer_known: None,
+            field_provenance: HashMap::new(),
+            additional_info: HashMap::from([("Notes".to_string(), "posth.".to_string())]),
+            raw_data: None,
+        }
+    }
+
+    #[test]
+    fn writes_and_reads_back_a_batch() {
+        let compositions = vec![
+            composition("Nocturne in E minor", &["72"]),
+            composition("Nocturnes", &["9", "15"]),
+        ];
+        let path =
+            std::env::temp_dir().join(format!("compositions-{}.parquet", std::process::id()));
+        let filename = path.to_str().unwrap();
+
+        write_compositions_parquet(&compositions, filename).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().fields(), compositions_schema().fields());
+
+        let titles = batch
+            .column_by_name("title")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(titles.value(1), "Nocturnes");
+
+        let opus_all = batch
+            .column_by_name("opus_all")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        assert_eq!(opus_all.value(1).len(), 2);
+
+        let additional_info = batch
+            .column_by_name("additional_info")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(additional_info.value(0), r#"{"Notes":"posth."}"#);
+    }
+}
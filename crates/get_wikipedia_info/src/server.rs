@@ -0,0 +1,200 @@
+// Scrape on demand over HTTP: GET /works?composer=Johannes%20Brahms runs
+// get_works for that composer and streams the canonical compositions back
+// as NDJSON while they are produced. Finished scrapes are cached per
+// composer for the life of the server, so asking again costs nothing; a
+// failed scrape is not cached. Requests for a composer whose scrape is
+// still running wait for it and are then served from the cache instead of
+// scraping again. Files get_works writes (raw-info-*.json and
+// so on) still go to the working directory.
+
+use anyhow::Result;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, StreamBody, combinators::BoxBody};
+use hyper::body::{Frame, Incoming};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::works::{Composition, CompositionSink, SinkFuture, WorksOptions, get_works_with};
+
+type Body = BoxBody<Bytes, Infallible>;
+
+struct ServerState {
+    works: WorksOptions,
+    // NDJSON lines of every finished scrape, by composer name
+    cache: Mutex<HashMap<String, Arc<Vec<Bytes>>>>,
+    // Held while a composer is scraped, by composer name; an entry lives
+    // only as long as some request holds it
+    in_flight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl ServerState {
+    fn scrape_lock(&self, composer: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.in_flight
+            .lock()
+            .unwrap()
+            .entry(composer.to_string())
+            .or_default()
+            .clone()
+    }
+
+    // Drop the composer's lock from the map once no other request holds it
+    fn release_scrape_lock(&self, composer: &str, lock: Arc<tokio::sync::Mutex<()>>) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if Arc::strong_count(&lock) == 2 {
+            in_flight.remove(composer);
+        }
+    }
+}
+
+// Serialize compositions to NDJSON lines, sending each to the response as
+// it comes and keeping a copy for the cache
+struct NdjsonSink {
+    sender: mpsc::Sender<Bytes>,
+    lines: Arc<Mutex<Vec<Bytes>>>,
+}
+
+impl CompositionSink for NdjsonSink {
+    fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+        Box::pin(async move {
+            let mut line = serde_json::to_vec(&composition)?;
+            line.push(b'\n');
+            let line = Bytes::from(line);
+            self.lines.lock().unwrap().push(line.clone());
+            self.sender
+                .send(line)
+                .await
+                .map_err(|_| anyhow::anyhow!("Client went away"))
+        })
+    }
+
+    fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+}
+
+fn text_response(status: StatusCode, text: &str) -> Response<Body> {
+    let mut response = Response::new(Full::new(Bytes::from(text.to_string())).boxed());
+    *response.status_mut() = status;
+    response
+}
+
+fn ndjson_response(receiver: mpsc::Receiver<Bytes>) -> Response<Body> {
+    let stream = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let line = receiver.recv().await?;
+        Some((Ok(Frame::data(line)), receiver))
+    });
+    let mut response = Response::new(StreamBody::new(stream).boxed());
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/x-ndjson"),
+    );
+    response
+}
+
+// The composer query parameter, percent-decoded
+fn composer_param(request: &Request<Incoming>) -> Option<String> {
+    let query = request.uri().query()?;
+    let url = reqwest::Url::parse(&format!("http://localhost/?{}", query)).ok()?;
+    url.query_pairs()
+        .find(|(name, _)| name == "composer")
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+async fn handle(state: Arc<ServerState>, request: Request<Incoming>) -> Response<Body> {
+    if request.method() != Method::GET || request.uri().path() != "/works" {
+        return text_response(StatusCode::NOT_FOUND, "not found\n");
+    }
+    let Some(composer) = composer_param(&request) else {
+        return text_response(StatusCode::BAD_REQUEST, "missing ?composer=\n");
+    };
+
+    let (sender, receiver) = mpsc::channel::<Bytes>(100);
+    tokio::spawn(async move {
+        let lock = state.scrape_lock(&composer);
+        {
+            let _scraping = lock.lock().await;
+            let cached = state.cache.lock().unwrap().get(&composer).cloned();
+            match cached {
+                Some(lines) => send_cached(&composer, &lines, sender).await,
+                None => scrape(&state, &composer, sender).await,
+            }
+        }
+        state.release_scrape_lock(&composer, lock);
+    });
+    ndjson_response(receiver)
+}
+
+async fn send_cached(composer: &str, lines: &[Bytes], sender: mpsc::Sender<Bytes>) {
+    info!(
+        "Serving {} cached compositions for {}",
+        lines.len(),
+        composer
+    );
+    for line in lines {
+        if sender.send(line.clone()).await.is_err() {
+            break;
+        }
+    }
+}
+
+// The response has started by the time a scrape can fail, so failures end
+// the stream with an {"error": ...} line rather than a status
+async fn scrape(state: &ServerState, composer: &str, sender: mpsc::Sender<Bytes>) {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = NdjsonSink {
+        sender: sender.clone(),
+        lines: lines.clone(),
+    };
+    match get_works_with(composer, &state.works, Box::new(sink)).await {
+        Ok(count) => {
+            info!("Scraped {} compositions for {}; caching", count, composer);
+            let lines = std::mem::take(&mut *lines.lock().unwrap());
+            state
+                .cache
+                .lock()
+                .unwrap()
+                .insert(composer.to_string(), Arc::new(lines));
+        }
+        Err(e) => {
+            warn!("Error getting works for {}: {}", composer, e);
+            let mut line = serde_json::to_vec(&serde_json::json!({ "error": e.to_string() }))
+                .unwrap_or_default();
+            line.push(b'\n');
+            let _ = sender.send(Bytes::from(line)).await;
+        }
+    }
+}
+
+// Answer requests on `listener` until the task is dropped
+pub async fn serve(listener: TcpListener, works: WorksOptions) -> Result<()> {
+    info!("Serving /works on {}", listener.local_addr()?);
+    let state = Arc::new(ServerState {
+        works,
+        cache: Mutex::new(HashMap::new()),
+        in_flight: Mutex::new(HashMap::new()),
+    });
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = hyper::service::service_fn(move |request| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle(state, request).await) }
+            });
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Connection error: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,122 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::genre::Genre;
+use crate::works::{Composition, MisalignedTable};
+
+// Dataset overview for one composer, written to
+// composer-summary-<name>.json next to the other outputs.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ComposerSummary {
+    pub composer_name: String,
+    pub total_works: usize,
+    // Keyed by normalized genre (see Genre::slug); works without a genre
+    // count as "other"
+    pub works_per_genre: BTreeMap<String, usize>,
+    pub earliest_year: Option<i32>,
+    pub latest_year: Option<i32>,
+    pub keys: BTreeSet<String>,
+    // The works-list article the compositions came from, if one was found
+    #[serde(default)]
+    pub list_page: Option<String>,
+    // Tables whose rows don't line up with their headers; only checked
+    // under --strict (see find_misaligned_tables)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub misaligned_tables: Vec<MisalignedTable>,
+}
+
+impl ComposerSummary {
+    pub fn from_compositions(composer_name: &str, compositions: &[Composition]) -> Self {
+        let year_regex = Regex::new(r"\b\d{4}\b").unwrap();
+
+        let mut works_per_genre = BTreeMap::new();
+        for composition in compositions {
+            let genre = composition
+                .genre
+                .as_deref()
+                .map(Genre::from_text)
+                .unwrap_or(Genre::Other);
+            *works_per_genre.entry(genre.slug().to_string()).or_insert(0) += 1;
+        }
+
+        let years: Vec<i32> = compositions
+            .iter()
+            .filter_map(|c| c.year.as_deref())
+            .filter_map(|year| year_regex.find(year)?.as_str().parse().ok())
+            .collect();
+
+        Self {
+            composer_name: composer_name.to_string(),
+            total_works: compositions.len(),
+            works_per_genre,
+            earliest_year: years.iter().min().copied(),
+            latest_year: years.iter().max().copied(),
+            keys: compositions
+                .iter()
+                .flat_map(|c| c.key_all.iter().cloned())
+                .collect(),
+            list_page: None,
+            misaligned_tables: Vec::new(),
+        }
+    }
+
+    pub async fn save(&self, path: &str) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composition(year: Option<&str>, genre: Option<&str>, keys: &[&str]) -> Composition {
+        serde_json::from_value(serde_json::json!({
+            "composer_name": "Frédéric Chopin",
+            "composer_url": "",
+            "source_url": "",
+            "title": "",
+            "work_url": null,
+            "year": year,
+            "key": keys.first(),
+            "key_all": keys,
+            "opus": null,
+            "genre": genre,
+            "catalog_number": null,
+            "instrumentation": null,
+            "duration": null,
+            "additional_info": {}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn summarizes_genres_years_and_keys() {
+        let compositions = vec![
+            composition(Some("1830"), Some("Piano concerto"), &["E minor"]),
+            composition(Some("c. 1844"), Some("Piano sonata"), &["B minor"]),
+            composition(Some("1829–1830"), Some("Concerto"), &["F minor", "E minor"]),
+            composition(None, None, &[]),
+        ];
+
+        let summary = ComposerSummary::from_compositions("Frédéric Chopin", &compositions);
+
+        assert_eq!(summary.total_works, 4);
+        assert_eq!(
+            summary.works_per_genre,
+            BTreeMap::from([
+                ("concerto".to_string(), 2),
+                ("other".to_string(), 1),
+                ("sonata".to_string(), 1),
+            ])
+        );
+        assert_eq!(summary.earliest_year, Some(1829));
+        assert_eq!(summary.latest_year, Some(1844));
+        assert_eq!(
+            summary.keys.into_iter().collect::<Vec<_>>(),
+            vec!["B minor", "E minor", "F minor"]
+        );
+    }
+}
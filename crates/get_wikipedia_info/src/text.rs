@@ -0,0 +1,203 @@
+// Wikipedia mixes hyphens with en dashes (1685–1750), em dashes
+// (Op. 10—12), minus signs and the Unicode hyphen variants. Parsers run
+// their regexes over normalize_dashes(text) so they only need to match "-".
+const DASHES: &[char] = &[
+    '\u{2010}', // hyphen
+    '\u{2011}', // non-breaking hyphen
+    '\u{2012}', // figure dash
+    '\u{2013}', // en dash
+    '\u{2014}', // em dash
+    '\u{2015}', // horizontal bar
+    '\u{2212}', // minus sign
+];
+
+pub fn normalize_dashes(text: &str) -> String {
+    text.replace(DASHES, "-")
+}
+
+// Lowercase, strip accents and punctuation and collapse whitespace so that
+// "Première", "premiere" and "Premiere:" compare equal.
+pub fn fold_for_matching(text: &str) -> String {
+    let folded: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Decode %XX escapes as UTF-8; malformed escapes are left as they are
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// The article title in a /wiki/ URL, decoded and with spaces:
+// ".../wiki/Symphony_No._5_(Beethoven)#History" gives
+// "Symphony No. 5 (Beethoven)"
+pub fn wiki_title(url: &str) -> Option<String> {
+    let (_, title) = url.split_once("/wiki/")?;
+    let title = title.split(['#', '?']).next().unwrap_or_default();
+    let title = percent_decode(title).replace('_', " ");
+    (!title.trim().is_empty()).then_some(title)
+}
+
+// A name as a /wiki/ title: spaces become underscores and anything that
+// could end or split the path ("/", "?", "#", "&", "%"...) is
+// percent-encoded, so "Weill/Brecht" gives "Weill%2FBrecht". Letters,
+// accented ones included, are left for the HTTP client to encode.
+pub fn wiki_slug(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        match c {
+            ' ' => slug.push('_'),
+            c if c.is_alphanumeric() || "_-.,()'!:*~".contains(c) => slug.push(c),
+            c => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    slug.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+    slug
+}
+
+// A name as part of an output file name, e.g. raw-info-<name>.json: spaces
+// become underscores and characters that are unsafe or special in paths
+// become "_". Leading dots are dropped so a name can't give a hidden file
+// or "..".
+pub fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '&' | '%' | '#' | ' ' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    stem.trim_start_matches('.').to_string()
+}
+
+// Levenshtein distance, counted in chars
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// edit_distance, but swapping two adjacent chars is one edit rather than
+// two ("Ttile" is one from "title"); optimal string alignment distance
+pub fn transposition_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows: Vec<Vec<usize>> = vec![(0..=b.len()).collect()];
+
+    for i in 1..=a.len() {
+        let mut current = vec![i];
+        for j in 1..=b.len() {
+            let mut distance = (rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]))
+                .min(rows[i - 1][j] + 1)
+                .min(current[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            current.push(distance);
+        }
+        rows.push(current);
+    }
+
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transpositions_count_as_one_edit() {
+        assert_eq!(edit_distance("ttile", "title"), 2);
+        assert_eq!(transposition_distance("ttile", "title"), 1);
+        assert_eq!(transposition_distance("lenght", "length"), 1);
+        assert_eq!(transposition_distance("venue", "genre"), 2);
+        assert_eq!(transposition_distance("", "key"), 3);
+        assert_eq!(transposition_distance("opus", "opus"), 0);
+    }
+
+    #[test]
+    fn names_with_slashes_give_safe_file_names_and_urls() {
+        let name = "Kurt Weill/Bertolt Brecht";
+        assert_eq!(file_stem(name), "Kurt_Weill_Bertolt_Brecht");
+        assert_eq!(wiki_slug(name), "Kurt_Weill%2FBertolt_Brecht");
+
+        let url = format!("https://en.wikipedia.org/wiki/{}", wiki_slug(name));
+        let parsed = reqwest::Url::parse(&url).unwrap();
+        assert_eq!(parsed.path(), "/wiki/Kurt_Weill%2FBertolt_Brecht");
+        assert_eq!(wiki_title(&url).as_deref(), Some(name));
+
+        assert_eq!(file_stem("Simon & Garfunkel"), "Simon___Garfunkel");
+        assert_eq!(wiki_slug("Simon & Garfunkel"), "Simon_%26_Garfunkel");
+        assert_eq!(wiki_slug("No. 5?"), "No._5%3F");
+        assert_eq!(file_stem("../etc"), "_etc");
+
+        // Ordinary names are unchanged from the plain space replacement
+        for name in [
+            "Hans Leo Hassler",
+            "Frédéric Chopin",
+            "Antonín Dvořák",
+            "John Adams (composer)",
+            "武満徹",
+            "Ōtaka Hisatada",
+        ] {
+            assert_eq!(file_stem(name), name.replace(" ", "_"));
+            assert_eq!(wiki_slug(name), name.replace(" ", "_"));
+        }
+    }
+
+    #[test]
+    fn every_dash_variant_becomes_a_hyphen() {
+        for dash in DASHES {
+            assert_eq!(normalize_dashes(&format!("1685{dash}1750")), "1685-1750");
+        }
+        assert_eq!(normalize_dashes("Op. 10-12"), "Op. 10-12");
+    }
+}
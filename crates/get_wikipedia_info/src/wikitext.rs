@@ -0,0 +1,421 @@
+use regex::Regex;
+
+use crate::text::wiki_slug;
+
+use crate::works::{
+    RAW_SCHEMA_VERSION, RawCompositionData, is_likely_composition_url,
+    is_likely_non_composition_url,
+};
+
+// A `{| ... |}` table from a page's wikitext, cells already reduced to
+// plain text plus the first wiki link in each
+#[derive(Debug, Default, PartialEq)]
+pub struct WikiTable {
+    pub classes: Vec<String>,
+    pub id: Option<String>,
+    pub caption: Option<String>,
+    pub section_heading: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<WikiRow>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct WikiRow {
+    pub cells: Vec<String>,
+    // Link targets as page titles, e.g. "Symphony No. 5 (Beethoven)"
+    pub links: Vec<Option<String>>,
+    // The row's wikitext, kept for debugging like raw_html_snippet
+    pub source: String,
+}
+
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let regex = Regex::new(&format!(r#"(?i)\b{}\s*=\s*"([^"]*)""#, name)).unwrap();
+    regex
+        .captures(attributes)
+        .map(|caps| caps[1].trim().to_string())
+}
+
+// Split on `separator` outside [[...]] and {{...}}, so link and template
+// pipes don't start a new cell
+fn split_outside_markup<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+    let bytes = text.as_bytes();
+
+    while i < bytes.len() {
+        let rest = &text[i..];
+        if rest.starts_with("[[") || rest.starts_with("{{") {
+            depth += 1;
+            i += 2;
+        } else if rest.starts_with("]]") || rest.starts_with("}}") {
+            depth = depth.saturating_sub(1);
+            i += 2;
+        } else if depth == 0 && rest.starts_with(separator) {
+            parts.push(&text[start..i]);
+            i += separator.len();
+            start = i;
+        } else {
+            // Step over the whole character so `i` stays on a char boundary
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+// `style="..." | content` keeps only the content
+fn strip_cell_attributes(cell: &str) -> &str {
+    let parts = split_outside_markup(cell, "|");
+    match parts.as_slice() {
+        [attributes, content] if attributes.contains('=') || attributes.trim().is_empty() => {
+            content
+        }
+        _ => cell,
+    }
+}
+
+// Reduce a cell's wikitext to its displayed text and first link target.
+// {{nowrap|x}} and {{sort|key|x}} keep their last argument; other
+// templates, references, comments and bold/italic quotes are dropped.
+fn clean_cell(cell: &str) -> (String, Option<String>) {
+    let comment_regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    let ref_regex = Regex::new(r"(?s)<ref[^>/]*/>|<ref[^>]*>.*?</ref>").unwrap();
+    let template_regex = Regex::new(r"\{\{([^{}]*)\}\}").unwrap();
+    let link_regex = Regex::new(r"\[\[([^\[\]|]*)(?:\|([^\[\]]*))?\]\]").unwrap();
+    let tag_regex = Regex::new(r"<[^>]+>").unwrap();
+
+    let mut text = comment_regex.replace_all(cell, "").to_string();
+    text = ref_regex.replace_all(&text, "").to_string();
+
+    // Innermost templates first, so nested ones unwind
+    while template_regex.is_match(&text) {
+        text = template_regex
+            .replace_all(&text, |caps: &regex::Captures| {
+                let parts: Vec<&str> = caps[1].split('|').collect();
+                match parts[0].trim().to_lowercase().as_str() {
+                    "nowrap" | "sort" | "nts" if parts.len() > 1 => {
+                        parts[parts.len() - 1].trim().to_string()
+                    }
+                    _ => String::new(),
+                }
+            })
+            .to_string();
+    }
+
+    let mut first_link = None;
+    text = link_regex
+        .replace_all(&text, |caps: &regex::Captures| {
+            let target = caps[1].trim();
+            if target.starts_with("File:") || target.starts_with("Category:") {
+                return String::new();
+            }
+            if first_link.is_none() && !target.starts_with('#') {
+                first_link = Some(target.to_string());
+            }
+            caps.get(2).map_or(target, |m| m.as_str()).to_string()
+        })
+        .to_string();
+
+    text = tag_regex
+        .replace_all(&text, "")
+        .replace("'''", "")
+        .replace("''", "");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (text, first_link)
+}
+
+// Parse every top-level `{| ... |}` table in a page's wikitext. Nested
+// tables are skipped; rowspan/colspan are not expanded.
+pub fn parse_tables(wikitext: &str) -> Vec<WikiTable> {
+    let heading_regex = Regex::new(r"^(={2,4})\s*(.*?)\s*={2,4}\s*$").unwrap();
+
+    let mut tables = Vec::new();
+    let mut section_heading = None;
+    let mut current: Option<WikiTable> = None;
+    let mut depth = 0usize;
+    // Cells of the row being read, each marked as a header cell or not
+    let mut row: Vec<(bool, String)> = Vec::new();
+    let mut row_source = String::new();
+
+    fn finish_row(table: &mut WikiTable, row: &mut Vec<(bool, String)>, source: &mut String) {
+        if row.is_empty() {
+            source.clear();
+            return;
+        }
+        let all_headers = row.iter().all(|(is_header, _)| *is_header);
+        if all_headers && table.headers.is_empty() && table.rows.is_empty() {
+            table.headers = row.iter().map(|(_, cell)| clean_cell(cell).0).collect();
+        } else if !all_headers {
+            let (cells, links) = row.iter().map(|(_, cell)| clean_cell(cell)).unzip();
+            table.rows.push(WikiRow {
+                cells,
+                links,
+                source: source.trim().to_string(),
+            });
+        }
+        row.clear();
+        source.clear();
+    }
+
+    for line in wikitext.lines() {
+        let trimmed = line.trim();
+
+        if current.is_none() {
+            if let Some(caps) = heading_regex.captures(trimmed) {
+                section_heading = Some(caps[2].to_string());
+            } else if let Some(attributes) = trimmed.strip_prefix("{|") {
+                current = Some(WikiTable {
+                    classes: attribute(attributes, "class")
+                        .map(|c| c.split_whitespace().map(str::to_string).collect())
+                        .unwrap_or_default(),
+                    id: attribute(attributes, "id"),
+                    section_heading: section_heading.clone(),
+                    ..Default::default()
+                });
+                depth = 1;
+            }
+            continue;
+        }
+        let table = current.as_mut().unwrap();
+
+        if trimmed.starts_with("{|") {
+            depth += 1;
+            continue;
+        }
+        if trimmed.starts_with("|}") {
+            depth -= 1;
+            if depth == 0 {
+                finish_row(table, &mut row, &mut row_source);
+                tables.push(current.take().unwrap());
+            }
+            continue;
+        }
+        if depth > 1 {
+            continue;
+        }
+
+        if let Some(caption) = trimmed.strip_prefix("|+") {
+            let (caption, _) = clean_cell(strip_cell_attributes(caption));
+            table.caption = Some(caption).filter(|c| !c.is_empty());
+        } else if trimmed.starts_with("|-") {
+            finish_row(table, &mut row, &mut row_source);
+        } else if let Some(cells) = trimmed.strip_prefix('!') {
+            // "! a !! b" or "! a || b"
+            for cell in split_outside_markup(cells, "!!")
+                .into_iter()
+                .flat_map(|cell| split_outside_markup(cell, "||"))
+            {
+                row.push((true, strip_cell_attributes(cell).to_string()));
+            }
+            row_source.push_str(line);
+            row_source.push('\n');
+        } else if let Some(cells) = trimmed.strip_prefix('|') {
+            for cell in split_outside_markup(cells, "||") {
+                row.push((false, strip_cell_attributes(cell).to_string()));
+            }
+            row_source.push_str(line);
+            row_source.push('\n');
+        } else if let Some((_, cell)) = row.last_mut() {
+            // Continuation of a multi-line cell
+            cell.push(' ');
+            cell.push_str(trimmed);
+            row_source.push_str(line);
+            row_source.push('\n');
+        }
+    }
+
+    tables
+}
+
+// A "#Section" on the target stays a fragment rather than being escaped
+fn wiki_url(base_url: &str, target: &str) -> String {
+    match target.split_once('#') {
+        Some((page, section)) => {
+            format!(
+                "{}/wiki/{}#{}",
+                base_url,
+                wiki_slug(page),
+                wiki_slug(section)
+            )
+        }
+        None => format!("{}/wiki/{}", base_url, wiki_slug(target)),
+    }
+}
+
+// RawCompositionData for every row of every table in `wikitext`, shaped
+// like the HTML extractor's output. raw_html_snippet holds the row's
+// wikitext instead of HTML.
+pub(crate) fn extract_raw_wikitext_data(
+    wikitext: &str,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+    base_url: &str,
+) -> Vec<RawCompositionData> {
+    let mut raw_data_list = Vec::new();
+
+    for (table_index, table) in parse_tables(wikitext).into_iter().enumerate() {
+        let column_count = table.rows.iter().map(|row| row.cells.len()).max();
+        let headers = if table.headers.is_empty() {
+            (0..column_count.unwrap_or(0))
+                .map(|i| format!("column_{}", i))
+                .collect()
+        } else {
+            table.headers.clone()
+        };
+
+        for (row_index, row) in table.rows.into_iter().enumerate() {
+            let cell_links: Vec<Option<String>> = row
+                .links
+                .iter()
+                .map(|link| link.as_deref().map(|target| wiki_url(base_url, target)))
+                .collect();
+            let source_url = cell_links
+                .iter()
+                .flatten()
+                .find(|url| is_likely_composition_url(url))
+                .or_else(|| {
+                    cell_links
+                        .iter()
+                        .flatten()
+                        .find(|url| !is_likely_non_composition_url(url))
+                })
+                .cloned()
+                .unwrap_or_else(|| page_url.to_string());
+
+            raw_data_list.push(RawCompositionData {
+                schema_version: RAW_SCHEMA_VERSION,
+                composer_name: composer_name.to_string(),
+                composer_url: composer_url.to_string(),
+                source_url,
+                table_index,
+                row_index,
+                headers: headers.clone(),
+                cell_data: row.cells,
+                cell_links,
+                raw_html_snippet: row.source,
+                section_heading: table.section_heading.clone(),
+                table_caption: table.caption.clone(),
+                table_classes: table.classes.clone(),
+                table_id: table.id.clone(),
+                was_collapsed: false,
+            });
+        }
+    }
+
+    raw_data_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed from the wikitext of "List of compositions by Johannes Brahms"
+    const BRAHMS_SAMPLE: &str = r#"
+== Orchestral works ==
+{| class="wikitable sortable" id="orchestral"
+|+ Symphonies
+! Opus !! Title !! Key !! Year
+|-
+| {{sort|068|68}} || [[Symphony No. 1 (Brahms)|Symphony No. 1]] || [[C minor]] || 1876<ref>Swafford, p. 412</ref>
+|-
+| 73
+| [[Symphony No. 2 (Brahms)|Symphony No. 2]]
+| D major
+| style="text-align:center" | 1877
+|-
+| 90 || ''Symphony No. 3'' {{nowrap|(in F)}} || F major || 1883
+|}
+"#;
+
+    #[test]
+    fn parses_a_wikitext_table_into_raw_rows() {
+        let raw_data = extract_raw_wikitext_data(
+            BRAHMS_SAMPLE,
+            "Johannes Brahms",
+            "composer_url",
+            "page_url",
+            "https://en.wikipedia.org",
+        );
+
+        assert_eq!(raw_data.len(), 3);
+        let first = &raw_data[0];
+        assert_eq!(first.headers, vec!["Opus", "Title", "Key", "Year"]);
+        assert_eq!(
+            first.cell_data,
+            vec!["68", "Symphony No. 1", "C minor", "1876"]
+        );
+        assert_eq!(
+            first.cell_links[1].as_deref(),
+            Some("https://en.wikipedia.org/wiki/Symphony_No._1_(Brahms)")
+        );
+        assert_eq!(
+            first.source_url,
+            "https://en.wikipedia.org/wiki/Symphony_No._1_(Brahms)"
+        );
+        assert_eq!(first.table_caption.as_deref(), Some("Symphonies"));
+        assert_eq!(first.table_classes, vec!["wikitable", "sortable"]);
+        assert_eq!(first.table_id.as_deref(), Some("orchestral"));
+        assert_eq!(first.section_heading.as_deref(), Some("Orchestral works"));
+
+        // One cell per line, with an attribute on the last
+        assert_eq!(
+            raw_data[1].cell_data,
+            vec!["73", "Symphony No. 2", "D major", "1877"]
+        );
+        assert_eq!(
+            raw_data[2].cell_data,
+            vec!["90", "Symphony No. 3 (in F)", "F major", "1883"]
+        );
+        assert_eq!(raw_data[2].source_url, "page_url");
+    }
+
+    #[test]
+    fn link_targets_are_escaped_in_urls() {
+        let base = "https://en.wikipedia.org";
+        assert_eq!(
+            wiki_url(base, "Pelléas & Mélisande"),
+            "https://en.wikipedia.org/wiki/Pelléas_%26_Mélisande"
+        );
+        assert_eq!(
+            wiki_url(base, "Was ist Leben?/Lied"),
+            "https://en.wikipedia.org/wiki/Was_ist_Leben%3F%2FLied"
+        );
+        assert_eq!(
+            wiki_url(base, "Symphony No. 1 (Brahms)#Premiere"),
+            "https://en.wikipedia.org/wiki/Symphony_No._1_(Brahms)#Premiere"
+        );
+    }
+
+    #[test]
+    fn splits_cells_with_accented_and_cjk_text() {
+        let wikitext = "{| class=\"wikitable\"\n\
+                        ! Title !! Year\n\
+                        |-\n\
+                        | [[Erlkönig (Schubert)|Erlkönig]] – ''Ballade'' || 1815\n\
+                        |-\n\
+                        | [[ノヴェンバー・ステップス]] || 1967\n\
+                        |}";
+        let raw_data = extract_raw_wikitext_data(
+            wikitext,
+            "Franz Schubert",
+            "composer_url",
+            "page_url",
+            "https://en.wikipedia.org",
+        );
+
+        let cells: Vec<&[String]> = raw_data
+            .iter()
+            .map(|raw| raw.cell_data.as_slice())
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                &["Erlkönig – Ballade".to_string(), "1815".to_string()][..],
+                &["ノヴェンバー・ステップス".to_string(), "1967".to_string()][..],
+            ]
+        );
+    }
+}
@@ -1,17 +1,38 @@
 use anyhow::Result;
 use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::{CaseSensitivity, Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tokio::{
-    fs::OpenOptions,
-    io::{AsyncWriteExt, BufWriter},
-    sync::mpsc,
-};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio::{io::AsyncWriteExt, sync::mpsc, task::JoinHandle};
 use tracing::{error, info, warn};
 
+use crate::attribution::{ATTRIBUTION_FILENAME, record_sources};
+use crate::composers::find_next_page_url;
+use crate::genre::{Genre, split_genre_cell};
+use crate::http::{BudgetExhausted, HttpOptions, build_client, get_with_retry};
+use crate::infobox::{DeepOptions, enrich_compositions};
+use crate::jsonl::{JsonlLines, create_writer, open_lines, write_line, write_line_strict};
+use crate::summary::ComposerSummary;
+use crate::text::{
+    edit_distance, file_stem, fold_for_matching, normalize_dashes, transposition_distance,
+    wiki_slug, wiki_title,
+};
+use crate::wikitext::extract_raw_wikitext_data;
+use crate::works_index::{index_filename, write_works_index};
+
+// Version of the raw-info-*.json line format. Lines without a
+// schema_version are version 0; migrate_raw_line upgrades them.
+pub const RAW_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RawCompositionData {
+    #[serde(default)]
+    pub schema_version: u32,
     pub composer_name: String,
     pub composer_url: String,
     pub source_url: String,
@@ -21,6 +42,258 @@ pub struct RawCompositionData {
     pub cell_data: Vec<String>,
     pub cell_links: Vec<Option<String>>,
     pub raw_html_snippet: String,
+    // Nearest enclosing h2-h4 heading on the page, if any
+    #[serde(default)]
+    pub section_heading: Option<String>,
+    // The table's <caption>, e.g. "Works for solo piano"
+    #[serde(default)]
+    pub table_caption: Option<String>,
+    // The table's class and id attributes, for working out which kinds of
+    // table yield good rows (e.g. "wikitable sortable")
+    #[serde(default)]
+    pub table_classes: Vec<String>,
+    #[serde(default)]
+    pub table_id: Option<String>,
+    // The table sat in a collapsible container (mw-collapsible and the
+    // like), so was folded away on the page; see is_collapsed
+    #[serde(default)]
+    pub was_collapsed: bool,
+}
+
+// How far a composition's year can be trusted: High when it came from a
+// year-labelled column, Low when it was picked out of free text elsewhere
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum YearConfidence {
+    High,
+    Low,
+}
+
+impl YearConfidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            YearConfidence::High => "high",
+            YearConfidence::Low => "low",
+        }
+    }
+}
+
+// How year_numeric relates to the year text: the year itself, a circa
+// date, or the first year of a span such as "1804–06"
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum YearQualifier {
+    Exact,
+    Approximate,
+    RangeStart,
+}
+
+impl YearQualifier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            YearQualifier::Exact => "exact",
+            YearQualifier::Approximate => "approximate",
+            YearQualifier::RangeStart => "range_start",
+        }
+    }
+}
+
+// The sortable year in a year text and how to qualify it: "1804" is exact,
+// "c. 1804", "probably 1601" or "1804?" approximate (also when a range is
+// circa), "1804–06" a range start. Prose is read by its first plausible
+// year.
+pub fn parse_year_numeric(text: &str) -> Option<(i32, YearQualifier)> {
+    let text = normalize_dashes(text);
+    let year_regex = Regex::new(r"\b(1[0-9]\d{2}|20\d{2})\b(\s*\?)?").unwrap();
+    let circa_regex = Regex::new(
+        r"(?i)(?:\bc\.|\bca\.|\b(?:circa|around|about|approx\.?|approximately|probably|likely)(?:\s+in)?)\s*$",
+    )
+    .unwrap();
+    let range_regex = Regex::new(r"^\s*-\s*\d{2,4}\b").unwrap();
+
+    let caps = year_regex.captures(&text)?;
+    let whole = caps.get(0).unwrap();
+    let year = caps[1].parse().ok()?;
+    let qualifier = if caps.get(2).is_some() || circa_regex.is_match(&text[..whole.start()]) {
+        YearQualifier::Approximate
+    } else if range_regex.is_match(&text[whole.end()..]) {
+        YearQualifier::RangeStart
+    } else {
+        YearQualifier::Exact
+    };
+    Some((year, qualifier))
+}
+
+// A performance time in seconds. Clock times ("25:30", "1:02:15") and
+// unit forms ("25 min", "1 h 20 mins", "1,234 seconds", "25'30\"") are
+// read once citation markers ("25 min[3]") and thousands separators are
+// removed; adjacent units add up and a range such as "20–25 minutes" is
+// read by its start. A bare number has no unit to go by and gives None.
+pub fn parse_duration_seconds(text: &str) -> Option<u32> {
+    let citation_regex = Regex::new(r"\[(?:\d+|[a-z]|note \d+|citation needed)\]").unwrap();
+    let thousands_regex = Regex::new(r"(\d),(\d{3})\b").unwrap();
+    let clock_regex = Regex::new(r"\b(?:(\d+):)?(\d{1,3}):([0-5]\d)\b").unwrap();
+    let unit_regex = Regex::new(
+        r#"(?i)(\d+(?:\.\d+)?)(?:\s*-\s*\d+(?:\.\d+)?)?\s*(hours?|hrs?|h|minutes?|mins?|m|seconds?|secs?|s|'|′|’|"|″|”)"#,
+    )
+    .unwrap();
+
+    let text = citation_regex.replace_all(text, "");
+    let text = thousands_regex.replace_all(&text, "$1$2");
+    let text = normalize_dashes(&text);
+
+    if let Some(caps) = clock_regex.captures(&text) {
+        let hours: u32 = caps.get(1).map_or(Some(0), |h| h.as_str().parse().ok())?;
+        let minutes: u32 = caps[2].parse().ok()?;
+        let seconds: u32 = caps[3].parse().ok()?;
+        return Some(hours * 3600 + minutes * 60 + seconds);
+    }
+
+    let mut total = 0.0;
+    let mut previous_end = None;
+    for caps in unit_regex.captures_iter(&text) {
+        let whole = caps.get(0).unwrap();
+        // "5 movements" is not five minutes
+        if text[whole.end()..]
+            .chars()
+            .next()
+            .is_some_and(char::is_alphabetic)
+        {
+            continue;
+        }
+        // Only units written together count: "1 h 20 min", not "25 min
+        // (orchestra), 30 min (piano)"
+        if let Some(end) = previous_end
+            && !text[end..whole.start()].trim().is_empty()
+        {
+            break;
+        }
+        let value: f64 = caps[1].parse().ok()?;
+        let unit = caps[2].to_lowercase();
+        total += value
+            * match unit.as_str() {
+                u if u.starts_with('h') => 3600.0,
+                u if u.starts_with('m') || ["'", "′", "’"].contains(&u) => 60.0,
+                _ => 1.0,
+            };
+        previous_end = Some(whole.end());
+    }
+    previous_end.map(|_| total.round() as u32)
+}
+
+// The first ISWC (International Standard Musical Work Code) in a text, in
+// its standard form "T-034.524.680-1". The final digit is a check digit
+// over "T" and the nine digits before it, so a mistyped code is refused.
+pub fn find_iswc(text: &str) -> Option<String> {
+    let iswc_regex = Regex::new(r"\bT-(\d{3})\.(\d{3})\.(\d{3})-(\d)\b").unwrap();
+    iswc_regex.captures_iter(text).find_map(|caps| {
+        let digits: Vec<u32> = format!("{}{}{}", &caps[1], &caps[2], &caps[3])
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .collect();
+        let sum: u32 = 1 + digits
+            .iter()
+            .enumerate()
+            .map(|(i, digit)| (i as u32 + 1) * digit)
+            .sum::<u32>();
+        let check: u32 = caps[4].parse().ok()?;
+        ((10 - sum % 10) % 10 == check).then(|| caps[0].to_string())
+    })
+}
+
+// Whether the work survives whole, going by markers on its row. Rows
+// canonicalized without any marker are Complete; Unknown is what older
+// output files without the field read back as.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkStatus {
+    Complete,
+    Incomplete,
+    Lost,
+    Fragment,
+    #[default]
+    Unknown,
+}
+
+impl WorkStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkStatus::Complete => "complete",
+            WorkStatus::Incomplete => "incomplete",
+            WorkStatus::Lost => "lost",
+            WorkStatus::Fragment => "fragment",
+            WorkStatus::Unknown => "unknown",
+        }
+    }
+}
+
+// An opus designation with the parts catalogues add to the bare number:
+// "Op. 59, Nos. 1–3", "Op. 10a/b", "Op. 10–12", "Op. posth." or
+// "Op. 72 (posth.)". The flat form in Composition::opus is number, suffixes
+// and range ("10a/b", "10-12"), or "posth." when there is no number.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Opus {
+    pub number: Option<String>,
+    // Letter sub-divisions: "10a/b" gives ["a", "b"]
+    #[serde(default)]
+    pub suffixes: Vec<String>,
+    // Last opus of a range: "10-12" gives "12"
+    pub range_end: Option<String>,
+    // Numbers within the opus: "Nos. 1-3" gives "1-3", "No. 2" gives "2"
+    pub numbers: Option<String>,
+    #[serde(default)]
+    pub posthumous: bool,
+}
+
+impl Opus {
+    pub fn parse(text: &str) -> Option<Self> {
+        let opus_regex = Regex::new(
+            r"(?x)
+            \b(?:Op\.|Opus|op\.)\s*
+            (?:
+                (?P<posth_only>posth\b\.?)
+              | (?P<number>\d+)
+                (?P<suffixes>\s*[a-z]\b(?:\s*/\s*[a-z]\b)*)?
+                (?:\s*-\s*(?P<range_end>\d+[a-z]?)\b)?
+            )
+            (?P<posth>\s*,?\s*\(?posth\b\.?\)?)?
+            (?:\s*,?\s*Nos?\.\s*(?P<numbers>\d+(?:\s*(?:-|,|&|and)\s*\d+)*))?",
+        )
+        .unwrap();
+        let text = normalize_dashes(text);
+        let caps = opus_regex.captures(&text)?;
+
+        Some(Opus {
+            number: caps.name("number").map(|m| m.as_str().to_string()),
+            suffixes: caps
+                .name("suffixes")
+                .map(|m| {
+                    m.as_str()
+                        .split('/')
+                        .map(|suffix| suffix.trim().to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            range_end: caps.name("range_end").map(|m| m.as_str().to_string()),
+            numbers: caps
+                .name("numbers")
+                .map(|m| m.as_str().split_whitespace().collect::<Vec<_>>().join(" ")),
+            posthumous: caps.name("posth_only").is_some() || caps.name("posth").is_some(),
+        })
+    }
+}
+
+impl std::fmt::Display for Opus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(number) = &self.number else {
+            return write!(f, "posth.");
+        };
+        write!(f, "{}{}", number, self.suffixes.join("/"))?;
+        if let Some(range_end) = &self.range_end {
+            write!(f, "-{}", range_end)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -29,64 +302,692 @@ pub struct Composition {
     pub composer_url: String,
     pub source_url: String,
     pub title: String,
+    // Alternate-language titles given in parentheses after the main title,
+    // e.g. "The Rite of Spring (Le Sacre du printemps)"
+    #[serde(default)]
+    pub alt_titles: Vec<String>,
     pub work_url: Option<String>,
     pub year: Option<String>,
+    #[serde(default)]
+    pub year_confidence: Option<YearConfidence>,
+    // Set when a date cell gives both, e.g. "1804 (comp.) / 1806 (pub.)";
+    // year then holds the composition year
+    #[serde(default)]
+    pub composition_year: Option<String>,
+    #[serde(default)]
+    pub publication_year: Option<String>,
+    // The year as a number for sorting and filtering, read from the year
+    // text by parse_year_numeric
+    #[serde(default)]
+    pub year_numeric: Option<i32>,
+    #[serde(default)]
+    pub year_qualifier: Option<YearQualifier>,
+    // Whether the year text marked the year as uncertain ("1785?",
+    // "ca. 1720", "probably 1601"); year and year_numeric keep the number
+    #[serde(default)]
+    pub year_approximate: bool,
     pub key: Option<String>,
     pub opus: Option<String>,
     pub genre: Option<String>,
+    // Every genre a multi-value cell such as "Sacred; Choral" lists,
+    // normalized; genre above keeps the first part's text
+    #[serde(default)]
+    pub genres: Vec<Genre>,
     pub catalog_number: Option<String>,
+    // Every distinct value when several columns map to the same field;
+    // the scalar fields above hold the first of these.
+    #[serde(default)]
+    pub key_all: Vec<String>,
+    #[serde(default)]
+    pub opus_all: Vec<String>,
+    // The parts of the first opus that the flat string leaves out
+    #[serde(default)]
+    pub opus_detail: Option<Opus>,
+    #[serde(default)]
+    pub catalog_number_all: Vec<String>,
+    // catalog_number in a form that sorts as text by system, then number:
+    // see catalog_sort_key
+    #[serde(default)]
+    pub catalog_sort_key: Option<String>,
     pub instrumentation: Option<String>,
     pub duration: Option<String>,
+    // The duration in seconds, read from its text by parse_duration_seconds
+    #[serde(default)]
+    pub duration_seconds: Option<u32>,
+    // ISWC from any cell of the row, or from the work's infobox with
+    // --deep (see find_iswc)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iswc: Option<String>,
+    // Arrangement or transcription of another work; arrangement_of names
+    // the source when the page says ("Arr. of Bach BWV 565" gives
+    // "Bach BWV 565")
+    #[serde(default)]
+    pub is_arrangement: bool,
+    #[serde(default)]
+    pub arrangement_of: Option<String>,
+    #[serde(default)]
+    pub status: WorkStatus,
+    // Whether composer_name/composer_url resolve to a composer in
+    // composers.json; None until checked (see crossref::cross_reference)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composer_known: Option<bool>,
+    // How much of the row's title, year, key/opus and genre came from
+    // columns labeled for them, from 0.0 to 1.0 (see confidence_score)
+    #[serde(default)]
+    pub confidence: f32,
+    // Which source header each canonical field was read from, e.g.
+    // "title" -> "Work"; only written with --debug (see WorksOptions)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub field_provenance: HashMap<String, String>,
     pub additional_info: HashMap<String, String>,
-    pub raw_data: RawCompositionData, // Preserve original raw data
+    // Preserve original raw data; left out of slim output (see WorksOptions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_data: Option<RawCompositionData>,
+}
+
+// Common additional_info concepts and the header wordings they appear under
+const FIELD_SYNONYMS: &[(&str, &[&str])] = &[
+    (
+        "premiere",
+        &["premiere", "first performance", "first performed"],
+    ),
+    ("dedication", &["dedicat"]),
+    ("libretto", &["libretto", "librettist", "text by", "words"]),
+    ("notes", &["notes", "remarks", "comments"]),
+];
+
+impl Composition {
+    // Fill year_numeric, year_qualifier and year_approximate from the text
+    // the year came from
+    pub(crate) fn set_year_numeric(&mut self, year_text: &str) {
+        let parsed = parse_year_numeric(year_text);
+        self.year_numeric = parsed.map(|(year, _)| year);
+        self.year_qualifier = parsed.map(|(_, qualifier)| qualifier);
+        self.year_approximate = self.year_qualifier == Some(YearQualifier::Approximate);
+    }
+
+    // Whether the row linked to an article for the work itself
+    pub fn has_work_url(&self) -> bool {
+        self.work_url.as_deref().is_some_and(|url| !url.is_empty())
+    }
+
+    // Look a concept such as "premiere" up in additional_info whatever header
+    // the source page used for it: exact (accent-folded) match first, then the
+    // canonicalizer's patterns, known synonyms and finally near-misspellings.
+    pub fn get_field_fuzzy(&self, concept: &str) -> Option<&str> {
+        let concept = fold_for_matching(concept);
+        let canonicalizer = FieldCanonicalizer::new();
+        let synonyms = FIELD_SYNONYMS
+            .iter()
+            .find(|(name, _)| *name == concept)
+            .map(|(_, synonyms)| *synonyms)
+            .unwrap_or(&[]);
+
+        self.additional_info
+            .iter()
+            .filter_map(|(header, value)| {
+                let folded = fold_for_matching(header);
+                let rank = if folded == concept {
+                    0
+                } else if canonicalizer.categorize_header(header) == Some(concept.as_str()) {
+                    1
+                } else if synonyms.iter().any(|synonym| folded.contains(synonym)) {
+                    2
+                } else if edit_distance(&folded, &concept) <= 2 {
+                    3
+                } else {
+                    return None;
+                };
+                Some((rank, header, value.as_str()))
+            })
+            .min()
+            .map(|(_, _, value)| value)
+    }
+}
+
+#[derive(Debug)]
+pub enum WorksError {
+    // The works page does not exist (HTTP 404)
+    NotFound { url: String },
+    // The works page exists but no table, work list, wikitext or category
+    // fallback yielded a row: the page moved, was renamed to a stub, or is
+    // rendered by script. html_bytes is the size of the page fetched.
+    NoContentExtracted { url: String, html_bytes: usize },
+}
+
+impl std::fmt::Display for WorksError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorksError::NotFound { url } => write!(f, "works page not found: {}", url),
+            WorksError::NoContentExtracted { url, html_bytes } => write!(
+                f,
+                "no works extracted from {} ({} bytes of HTML, no usable tables or lists)",
+                url, html_bytes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WorksError {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    // compositions.json, one JSON object per line, appended across runs
+    #[default]
+    Jsonl,
+    // compositions.parquet, rewritten on each run
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+pub const WIKIPEDIA_BASE_URL: &str = "https://en.wikipedia.org";
+
+#[derive(Clone, Debug)]
+pub struct WorksOptions {
+    // Site the works pages are fetched from; overridable for tests/mirrors
+    pub base_url: String,
+    // Scrape this page instead of looking for "List of compositions by X";
+    // rows are still attributed to the composer name passed to get_works.
+    // A file:// URL reads an already-downloaded page, with no wikitext or
+    // category fallback.
+    pub works_url: Option<String>,
+    pub format: OutputFormat,
+    pub http: HttpOptions,
+    // Drop raw_data from compositions.json; it stays in raw-info-*.json.
+    pub slim: bool,
+    // Write compositions-<genre>.json per normalized genre (see Genre::slug)
+    // instead of a single compositions.json; unknown genres go to
+    // compositions-other.json.
+    pub split_by_genre: bool,
+    // JSON file of extra header mappings, see FieldMap
+    pub field_map: Option<String>,
+    // Fetch each work's own article and fill gaps from its infobox
+    pub deep: Option<DeepOptions>,
+    // Write raw-info-<name>.json.gz and compositions*.json.gz instead
+    pub gzip: bool,
+    // Also write each table's raw rows to debug-tables-<name>/table-<index>.json
+    pub debug_tables: bool,
+    // When the list page's HTML yields no rows, parse its wikitext
+    // (?action=raw) before falling back to the category page
+    pub wikitext_fallback: bool,
+    // When the list is split across pages ("... (A–M)", "... (N–Z)"), fetch
+    // the other parts linked from the list page and merge their rows (see
+    // continuation_pages)
+    pub follow_continuations: bool,
+    pub table_limits: TableLimits,
+    pub flush: FlushPolicy,
+    // Drop compositions without a work_url before writing
+    pub only_with_url: bool,
+    // Keep summary rows such as "Total: 104 symphonies" as compositions
+    // instead of skipping them (see is_total_row); they stay in raw-info
+    // either way
+    pub keep_total_rows: bool,
+    // Cut each row's raw_html_snippet to this many bytes (see
+    // truncate_snippet); Some(0) drops snippets, None keeps them whole
+    pub max_snippet_bytes: Option<usize>,
+    // Check every raw and composition record round-trips through JSON
+    // before writing it, skipping (and logging) any that don't, and flag
+    // misaligned tables (see find_misaligned_tables). On by default in
+    // debug builds.
+    pub strict: bool,
+    // The list page covers several composers, one section each; attribute
+    // each section's rows to its composer (see extract_raw_data_by_composer)
+    pub composer_sections: Option<ComposerHeadings>,
+    // Lifespans by composer name (e.g. from composers.json), used to drop
+    // rows that only repeat the composer's dates
+    pub composer_years: HashMap<String, LifeYears>,
+    // Also write the run's compositions as one indented JSON array to
+    // compositions-pretty.json (see PrettyJsonSink)
+    pub pretty: bool,
+    // An existing compositions file; compositions already in it are not
+    // written again (see MasterIndex)
+    pub master: Option<String>,
+    // Keep field_provenance in the compositions output
+    pub debug: bool,
+    // Also write a "Composer - Title (Year)" line per work to <name>.txt,
+    // in catalogue order (see works_index)
+    pub index: bool,
+}
+
+// A composer's birth and death years
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LifeYears {
+    pub birth: Option<i32>,
+    pub death: Option<i32>,
+}
+
+// How often the compositions writers push buffered lines to disk, so a crash
+// in a long run loses at most a few records. Lines are written whole, so the
+// file always ends on a complete line. None disables that trigger; the
+// writer always flushes when it finishes.
+#[derive(Clone, Copy, Debug)]
+pub struct FlushPolicy {
+    pub every_records: Option<usize>,
+    pub interval: Option<Duration>,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self {
+            every_records: Some(100),
+            interval: Some(Duration::from_secs(5)),
+        }
+    }
+}
+
+impl Default for WorksOptions {
+    fn default() -> Self {
+        Self {
+            base_url: WIKIPEDIA_BASE_URL.to_string(),
+            works_url: None,
+            format: OutputFormat::default(),
+            http: HttpOptions::default(),
+            slim: false,
+            split_by_genre: false,
+            field_map: None,
+            deep: None,
+            gzip: false,
+            debug_tables: false,
+            wikitext_fallback: true,
+            follow_continuations: false,
+            table_limits: TableLimits::default(),
+            flush: FlushPolicy::default(),
+            only_with_url: false,
+            keep_total_rows: false,
+            max_snippet_bytes: None,
+            strict: cfg!(debug_assertions),
+            composer_sections: None,
+            composer_years: HashMap::new(),
+            pretty: false,
+            master: None,
+            debug: false,
+            index: false,
+        }
+    }
 }
 
 async fn raw_data_writer_task(
     mut receiver: mpsc::Receiver<RawCompositionData>,
     filename: &str,
+    strict: bool,
 ) -> Result<()> {
-    let file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true) // Start fresh for each composer
-        .open(filename)
-        .await?;
-
-    let mut writer = BufWriter::new(file);
+    // Start fresh for each composer
+    let mut writer = create_writer(filename, false).await?;
 
     while let Some(raw_data) = receiver.recv().await {
-        let json_line = serde_json::to_string(&raw_data)?;
-        writer.write_all(json_line.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
+        if strict {
+            write_line_strict(&mut writer, &raw_data).await?;
+        } else {
+            write_line(&mut writer, &raw_data).await?;
+        }
     }
 
-    writer.flush().await?;
+    writer.shutdown().await?;
     Ok(())
 }
 
 async fn composition_writer_task(
     mut receiver: mpsc::Receiver<Composition>,
     filename: &str,
+    flush: FlushPolicy,
+    strict: bool,
 ) -> Result<()> {
-    let file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .append(true)
-        .open(filename)
-        .await?;
-
-    let mut writer = BufWriter::new(file);
+    let mut writer = create_writer(filename, true).await?;
+    let mut interval = flush.interval.map(tokio::time::interval);
+    let mut unflushed = 0;
 
-    while let Some(composition) = receiver.recv().await {
-        let json_line = serde_json::to_string(&composition)?;
-        writer.write_all(json_line.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
+    loop {
+        let tick = async {
+            match &mut interval {
+                Some(interval) => interval.tick().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            composition = receiver.recv() => {
+                let Some(composition) = composition else { break };
+                if strict {
+                    if !write_line_strict(&mut writer, &composition).await? {
+                        continue;
+                    }
+                } else {
+                    write_line(&mut writer, &composition).await?;
+                }
+                unflushed += 1;
+                if flush.every_records.is_some_and(|n| unflushed >= n) {
+                    writer.flush().await?;
+                    unflushed = 0;
+                }
+            }
+            _ = tick => {
+                if unflushed > 0 {
+                    writer.flush().await?;
+                    unflushed = 0;
+                }
+            }
+        }
     }
 
-    writer.flush().await?;
+    writer.shutdown().await?;
     Ok(())
 }
 
+// Where canonical compositions go. get_works writes to a file sink by
+// default (see output_sink); other destinations such as stdout, a database or
+// an in-memory buffer implement this and go through get_works_with.
+// Methods return boxed futures so sinks can be used as Box<dyn CompositionSink>.
+pub type SinkFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+pub trait CompositionSink: Send {
+    fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()>;
+
+    // Flush and close everything; resolves to the outputs written (file
+    // names, for the log)
+    fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>>;
+}
+
+// The default sink: compositions.json, or compositions-<genre>.json for
+// each normalized genre when splitting by genre. One writer task is spawned
+// per file on first use.
+pub struct JsonlFileSink {
+    split_by_genre: bool,
+    gzip: bool,
+    flush: FlushPolicy,
+    strict: bool,
+    writers: HashMap<String, (mpsc::Sender<Composition>, JoinHandle<Result<()>>)>,
+}
+
+impl JsonlFileSink {
+    pub fn new(options: &WorksOptions) -> Self {
+        Self {
+            split_by_genre: options.split_by_genre,
+            gzip: options.gzip,
+            flush: options.flush,
+            strict: options.strict,
+            writers: HashMap::new(),
+        }
+    }
+
+    fn filename_for(&self, composition: &Composition) -> String {
+        let filename = if self.split_by_genre {
+            let genre = composition
+                .genre
+                .as_deref()
+                .map(Genre::from_text)
+                .unwrap_or(Genre::Other);
+            format!("compositions-{}.json", genre.slug())
+        } else {
+            "compositions.json".to_string()
+        };
+        if self.gzip {
+            filename + ".gz"
+        } else {
+            filename
+        }
+    }
+}
+
+impl CompositionSink for JsonlFileSink {
+    fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+        Box::pin(async move {
+            let filename = self.filename_for(&composition);
+            let (flush, strict) = (self.flush, self.strict);
+            let (sender, _) = self.writers.entry(filename.clone()).or_insert_with(|| {
+                let (tx, rx) = mpsc::channel::<Composition>(100);
+                let handle = tokio::spawn(async move {
+                    composition_writer_task(rx, &filename, flush, strict).await
+                });
+                (tx, handle)
+            });
+
+            sender
+                .send(composition)
+                .await
+                .map_err(|_| anyhow::anyhow!("Error trying to send Composition through Channel"))
+        })
+    }
+
+    fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+        Box::pin(async move {
+            let mut filenames: Vec<String> = self.writers.keys().cloned().collect();
+            filenames.sort();
+            for (_, (sender, handle)) in self.writers {
+                drop(sender);
+                handle.await??;
+            }
+            Ok(filenames)
+        })
+    }
+}
+
+// Buffers every composition and writes compositions.parquet in one go on
+// finish
+#[cfg(feature = "parquet")]
+#[derive(Default)]
+pub struct ParquetFileSink {
+    rows: Vec<Composition>,
+}
+
+#[cfg(feature = "parquet")]
+const PARQUET_FILENAME: &str = "compositions.parquet";
+
+#[cfg(feature = "parquet")]
+impl CompositionSink for ParquetFileSink {
+    fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+        self.rows.push(composition);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+        Box::pin(async move {
+            let rows = self.rows;
+            tokio::task::spawn_blocking(move || {
+                crate::parquet_output::write_compositions_parquet(&rows, PARQUET_FILENAME)
+            })
+            .await??;
+            Ok(vec![PARQUET_FILENAME.to_string()])
+        })
+    }
+}
+
+pub const PRETTY_FILENAME: &str = "compositions-pretty.json";
+
+// Passes compositions on to `inner` and also keeps them, to write as one
+// indented JSON array when finished, for reading small scrapes by hand. The
+// array has to be complete to be valid JSON, so everything is held in
+// memory until the end and the file is rewritten on each run; the streamed
+// output from `inner` stays the record to rely on for large runs.
+pub struct PrettyJsonSink {
+    inner: Box<dyn CompositionSink>,
+    filename: String,
+    rows: Vec<Composition>,
+}
+
+impl PrettyJsonSink {
+    pub fn new(inner: Box<dyn CompositionSink>, filename: &str) -> Self {
+        Self {
+            inner,
+            filename: filename.to_string(),
+            rows: Vec::new(),
+        }
+    }
+}
+
+impl CompositionSink for PrettyJsonSink {
+    fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+        self.rows.push(composition.clone());
+        self.inner.write(composition)
+    }
+
+    fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+        Box::pin(async move {
+            let mut outputs = self.inner.finish().await?;
+            tokio::fs::write(&self.filename, serde_json::to_string_pretty(&self.rows)?).await?;
+            outputs.push(self.filename);
+            Ok(outputs)
+        })
+    }
+}
+
+// The file sink for options.format, plus the pretty copy if asked for
+pub fn file_sink(options: &WorksOptions) -> Box<dyn CompositionSink> {
+    let sink: Box<dyn CompositionSink> = match options.format {
+        OutputFormat::Jsonl => Box::new(JsonlFileSink::new(options)),
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => Box::<ParquetFileSink>::default(),
+    };
+    if options.pretty {
+        Box::new(PrettyJsonSink::new(sink, PRETTY_FILENAME))
+    } else {
+        sink
+    }
+}
+
+// Identity of each composition in a master dataset: folded title and
+// composer plus catalog number (or opus, when there is none), so
+// "Symphony No. 1" by the same composer with a different catalog number
+// still counts as new.
+#[derive(Debug, Default)]
+pub struct MasterIndex {
+    keys: HashSet<(String, String, String)>,
+}
+
+impl MasterIndex {
+    pub async fn load(path: &str) -> Result<Self> {
+        let mut index = Self::default();
+        let mut reader = read_compositions(path).await?;
+        while let Some(composition) = reader.next().await {
+            index.insert(&composition?);
+        }
+        info!("Loaded {} compositions from master {}", index.len(), path);
+        Ok(index)
+    }
+
+    fn key(composition: &Composition) -> (String, String, String) {
+        let catalog = composition
+            .catalog_number
+            .as_deref()
+            .or(composition.opus.as_deref())
+            .unwrap_or_default();
+        (
+            fold_for_matching(&composition.title),
+            fold_for_matching(&composition.composer_name),
+            fold_for_matching(catalog),
+        )
+    }
+
+    // False if the composition was already present
+    pub fn insert(&mut self, composition: &Composition) -> bool {
+        self.keys.insert(Self::key(composition))
+    }
+
+    pub fn contains(&self, composition: &Composition) -> bool {
+        self.keys.contains(&Self::key(composition))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+// Passes on only compositions not yet in the master index. New ones are
+// added to the index as they go by, so a row scraped twice in one run is
+// written once. The new and skipped counts are logged on finish.
+pub struct DedupSink {
+    inner: Box<dyn CompositionSink>,
+    index: MasterIndex,
+    new: usize,
+    skipped: usize,
+}
+
+impl DedupSink {
+    pub fn new(inner: Box<dyn CompositionSink>, index: MasterIndex) -> Self {
+        Self {
+            inner,
+            index,
+            new: 0,
+            skipped: 0,
+        }
+    }
+}
+
+impl CompositionSink for DedupSink {
+    fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+        if !self.index.insert(&composition) {
+            self.skipped += 1;
+            return Box::pin(async { Ok(()) });
+        }
+        self.new += 1;
+        self.inner.write(composition)
+    }
+
+    fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+        Box::pin(async move {
+            info!(
+                "{} new compositions, {} skipped as already in the master dataset",
+                self.new, self.skipped
+            );
+            self.inner.finish().await
+        })
+    }
+}
+
+// file_sink, deduplicated against options.master when one is given. The
+// master is read in full before anything is written, so it may be the same
+// compositions.json the run appends to. Splitting by genre is only done for
+// JSONL, so asking for it with Parquet is an error rather than one file.
+pub async fn output_sink(options: &WorksOptions) -> Result<Box<dyn CompositionSink>> {
+    #[cfg(feature = "parquet")]
+    if options.split_by_genre && matches!(options.format, OutputFormat::Parquet) {
+        anyhow::bail!("--split-by-genre only applies to JSONL output, not --format parquet");
+    }
+    let sink = file_sink(options);
+    match &options.master {
+        Some(master) => Ok(Box::new(DedupSink::new(
+            sink,
+            MasterIndex::load(master).await?,
+        ))),
+        None => Ok(sink),
+    }
+}
+
+// One long-lived output for a whole run. Every composer's worker holds a
+// clone; a single task owns the sink (and so every open file), and the
+// sink is finished, closing files and writing any Parquet output, only once
+// the last clone has been dropped. Concurrent composers therefore never
+// interleave partial lines or reopen compositions.json.
+#[derive(Clone)]
+pub struct SharedSink {
+    sender: mpsc::Sender<Composition>,
+}
+
+impl SharedSink {
+    // The handle resolves to the outputs written once every clone is dropped
+    pub fn spawn(mut sink: Box<dyn CompositionSink>) -> (Self, JoinHandle<Result<Vec<String>>>) {
+        let (sender, mut receiver) = mpsc::channel::<Composition>(100);
+
+        let handle = tokio::spawn(async move {
+            while let Some(composition) = receiver.recv().await {
+                sink.write(composition).await?;
+            }
+            sink.finish().await
+        });
+
+        (Self { sender }, handle)
+    }
+
+    pub async fn send(&self, composition: Composition) -> Result<()> {
+        self.sender
+            .send(composition)
+            .await
+            .map_err(|_| anyhow::anyhow!("Error trying to send Composition through Channel"))
+    }
+}
+
 fn determine_source_url(
     cells: &[scraper::ElementRef],
     a_selector: &Selector,
@@ -100,20 +1001,19 @@ fn determine_source_url(
     // 3. Fallback to the page we're scraping from
 
     // Check first cell for links (highest priority)
-    if let Some(first_cell) = cells.first() {
-        if let Some(link) = first_cell.select(a_selector).next() {
-            if let Some(href) = link.value().attr("href") {
-                let full_url = if href.starts_with("/wiki/") {
-                    format!("{}{}", base_url, href)
-                } else {
-                    href.to_string()
-                };
+    if let Some(first_cell) = cells.first()
+        && let Some(link) = first_cell.select(a_selector).next()
+        && let Some(href) = link.value().attr("href")
+    {
+        let full_url = if href.starts_with("/wiki/") {
+            format!("{}{}", base_url, href)
+        } else {
+            href.to_string()
+        };
 
-                // Verify this looks like a composition/work page
-                if is_likely_composition_url(&full_url) {
-                    return full_url;
-                }
-            }
+        // Verify this looks like a composition/work page
+        if is_likely_composition_url(&full_url) {
+            return full_url;
         }
     }
 
@@ -156,9 +1056,7 @@ fn determine_source_url(
     page_url.to_string()
 }
 
-fn is_likely_composition_url(url: &str) -> bool {
-    let url_lower = url.to_lowercase();
-
+pub(crate) fn is_likely_composition_url(url: &str) -> bool {
     // Positive indicators for composition URLs
     let composition_indicators = [
         "symphony",
@@ -202,16 +1100,72 @@ fn is_likely_composition_url(url: &str) -> bool {
         "woo",
     ];
 
-    composition_indicators
-        .iter()
-        .any(|indicator| url_lower.contains(indicator))
+    // Whole path tokens only, allowing plurals: plain substring matching
+    // found "aria" in Bavaria and "mass" in Massachusetts. A venue named
+    // after a genre ("Royal Opera House") is still a venue.
+    let tokens = url_path_tokens(url);
+    !is_venue(&tokens)
+        && tokens.iter().any(|token| {
+            composition_indicators
+                .iter()
+                .any(|indicator| token_matches(token, indicator))
+        })
+}
+
+// A token equal to the indicator or its plural
+fn token_matches(token: &str, indicator: &str) -> bool {
+    token == indicator
+        || token.strip_suffix('s') == Some(indicator)
+        || token.strip_suffix("es") == Some(indicator)
+}
+
+// Whether the tokens contain `phrase` as consecutive tokens, the last of
+// which may be plural
+fn has_phrase(tokens: &[String], phrase: &[&str]) -> bool {
+    tokens.windows(phrase.len()).any(|window| {
+        window
+            .iter()
+            .zip(phrase)
+            .enumerate()
+            .all(|(i, (token, word))| {
+                token == word || (i == phrase.len() - 1 && token_matches(token, word))
+            })
+    })
+}
+
+// Halls and theatres: their pages link from premiere columns, and several
+// are named after the genre played in them
+fn is_venue(tokens: &[String]) -> bool {
+    let venues: [&[&str]; 6] = [
+        &["opera", "house"],
+        &["concert", "hall"],
+        &["music", "hall"],
+        &["theatre"],
+        &["theater"],
+        &["auditorium"],
+    ];
+    venues.iter().any(|venue| has_phrase(tokens, venue))
 }
 
-fn is_likely_non_composition_url(url: &str) -> bool {
-    let url_lower = url.to_lowercase();
+// Lowercased tokens of a URL's path, split on / _ - , spaces and
+// parentheses: ".../wiki/Symphony_No._5_(Beethoven)" gives wiki, symphony,
+// no., 5, beethoven.
+fn url_path_tokens(url: &str) -> Vec<String> {
+    let path = match reqwest::Url::parse(url) {
+        Ok(parsed) => parsed.path().to_lowercase(),
+        Err(_) => url.to_lowercase(),
+    };
+
+    path.replace("%20", " ")
+        .split(['/', '_', '-', ',', '(', ')', ' '])
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
-    // Negative indicators (things that are definitely not compositions)
-    let non_composition_indicators = [
+pub(crate) fn is_likely_non_composition_url(url: &str) -> bool {
+    // Namespaced pages (things that are definitely not compositions)
+    let namespaces = [
         "category:",
         "file:",
         "template:",
@@ -221,27 +1175,65 @@ fn is_likely_non_composition_url(url: &str) -> bool {
         "portal:",
         "help:",
         "special:",
-        "list_of",
-        "discography",
-        "biography",
-        "chronology",
-        "timeline",
-        "genre",
-        "style",
-        "period",
-        "era",
-        "instrument",
-        "orchestra",
-        "ensemble",
-        "conservatory",
-        "music_school",
-        "university",
-        "college",
+    ];
+    // Negative indicators, matched as whole path tokens like the positive
+    // ones, so "era" no longer rejects every opera
+    let non_composition_indicators: [&[&str]; 16] = [
+        &["list", "of"],
+        &["discography"],
+        &["biography"],
+        &["chronology"],
+        &["timeline"],
+        &["genre"],
+        &["style"],
+        &["period"],
+        &["era"],
+        &["instrument"],
+        &["orchestra"],
+        &["ensemble"],
+        &["conservatory"],
+        &["music", "school"],
+        &["university"],
+        &["college"],
     ];
 
-    non_composition_indicators
-        .iter()
-        .any(|indicator| url_lower.contains(indicator))
+    let tokens = url_path_tokens(url);
+    tokens.iter().any(|token| {
+        namespaces
+            .iter()
+            .any(|namespace| token.starts_with(namespace))
+    }) || is_venue(&tokens)
+        || non_composition_indicators
+            .iter()
+            .any(|indicator| has_phrase(&tokens, indicator))
+}
+
+// Sortable tables hide sort keys inside cells, e.g.
+// <span style="display:none">001</span> or <span class="sortkey">, which
+// cell.text() would glue onto the visible text ("001Symphony No. 1").
+fn is_hidden(element: scraper::ElementRef) -> bool {
+    let value = element.value();
+    value.has_class("sortkey", CaseSensitivity::AsciiCaseInsensitive)
+        || value.attr("style").is_some_and(|style| {
+            style
+                .replace(' ', "")
+                .to_lowercase()
+                .contains("display:none")
+        })
+}
+
+fn visible_text(element: scraper::ElementRef) -> String {
+    element
+        .descendants()
+        .filter(|node| {
+            !node
+                .ancestors()
+                .take_while(|ancestor| ancestor.id() != element.id())
+                .filter_map(scraper::ElementRef::wrap)
+                .any(is_hidden)
+        })
+        .filter_map(|node| node.value().as_text().map(|text| text.to_string()))
+        .collect()
 }
 
 fn extract_raw_table_data(
@@ -250,16 +1242,31 @@ fn extract_raw_table_data(
     composer_url: &str,
     page_url: &str,
     table_index: usize,
+    section_heading: Option<&str>,
+    was_collapsed: bool,
 ) -> Vec<RawCompositionData> {
     let th_selector = Selector::parse("th").unwrap();
     let tr_selector = Selector::parse("tr").unwrap();
     let td_selector = Selector::parse("td").unwrap();
     let a_selector = Selector::parse("a[href^=\"/wiki\"]").unwrap();
+    let caption_selector = Selector::parse("caption").unwrap();
     let base_url = "https://en.wikipedia.org";
 
     let mut headers = Vec::new();
     let mut raw_data_list = Vec::new();
 
+    let table_classes: Vec<String> = table
+        .value()
+        .attr("class")
+        .map(|classes| classes.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    let table_id = table.value().id().map(str::to_string);
+    let table_caption = table
+        .select(&caption_selector)
+        .next()
+        .map(|caption| caption.text().collect::<String>().trim().to_string())
+        .filter(|caption| !caption.is_empty());
+
     // Find headers
     for row in table.select(&tr_selector) {
         let header_cells: Vec<_> = row.select(&th_selector).collect();
@@ -294,7 +1301,7 @@ fn extract_raw_table_data(
 
         let cell_data: Vec<String> = cells
             .iter()
-            .map(|cell| cell.text().collect::<String>().trim().to_string())
+            .map(|cell| visible_text(*cell).trim().to_string())
             .collect();
 
         let cell_links: Vec<Option<String>> = cells
@@ -327,6 +1334,7 @@ fn extract_raw_table_data(
         );
 
         let raw_data = RawCompositionData {
+            schema_version: RAW_SCHEMA_VERSION,
             composer_name: composer_name.to_string(),
             composer_url: composer_url.to_string(),
             source_url: source_url.to_string(),
@@ -336,6 +1344,11 @@ fn extract_raw_table_data(
             cell_data,
             cell_links,
             raw_html_snippet,
+            section_heading: section_heading.map(|h| h.to_string()),
+            table_caption: table_caption.clone(),
+            table_classes: table_classes.clone(),
+            table_id: table_id.clone(),
+            was_collapsed,
         };
 
         raw_data_list.push(raw_data);
@@ -345,26 +1358,736 @@ fn extract_raw_table_data(
     raw_data_list
 }
 
-// ------
-struct FieldCanonicalizer {
-    title_patterns: Vec<Regex>,
-    year_patterns: Vec<Regex>,
-    key_patterns: Vec<Regex>,
-    opus_patterns: Vec<Regex>,
-    genre_patterns: Vec<Regex>,
-    catalog_patterns: Vec<Regex>,
-    instrumentation_patterns: Vec<Regex>,
-    duration_patterns: Vec<Regex>,
+fn is_work_heading(heading: &str) -> bool {
+    let work_heading_regex = Regex::new(
+        r"(?i)works|compositions|opera|symphon|concert|sonata|chamber|piano|organ|keyboard|orchestra|vocal|choral|sacred|songs|ballet|cantata|mass|lieder",
+    )
+    .unwrap();
+    work_heading_regex.is_match(heading)
 }
 
-impl FieldCanonicalizer {
-    fn new() -> Self {
-        Self {
-            title_patterns: vec![Regex::new(r"(?i)title|work|composition|piece|name").unwrap()],
-            year_patterns: vec![Regex::new(r"(?i)year|date|composed|written|created").unwrap()],
-            key_patterns: vec![Regex::new(r"(?i)key|tonality").unwrap()],
-            opus_patterns: vec![
-                Regex::new(r"(?i)opus|op\.?|work number|woo|bwv|k\.?|hob\.?").unwrap(),
+// Lists nested in other lists, tables or page furniture (navboxes, table of
+// contents, references) are never work lists in their own right.
+fn is_nested_or_navigation_list(list: scraper::ElementRef) -> bool {
+    list.ancestors()
+        .filter_map(scraper::ElementRef::wrap)
+        .any(|ancestor| {
+            matches!(
+                ancestor.value().name(),
+                "table" | "nav" | "ul" | "ol" | "li"
+            ) || ancestor.value().classes().any(|class| {
+                matches!(
+                    class,
+                    "navbox" | "toc" | "reflist" | "refbegin" | "hatnote" | "sidebar"
+                )
+            })
+        })
+}
+
+// Turn a bulleted works list into raw rows shaped like a small table with
+// Title / Year / Description columns, so canonicalization treats them the
+// same way as table rows.
+fn extract_raw_list_data(
+    list: scraper::ElementRef,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+    table_index: usize,
+    section_heading: Option<&str>,
+) -> Vec<RawCompositionData> {
+    let a_selector = Selector::parse("a[href^=\"/wiki\"]").unwrap();
+    let trailing_parens_regex = Regex::new(r"\(([^()]*)\)\s*$").unwrap();
+    let base_url = "https://en.wikipedia.org";
+
+    let headers = vec![
+        "Title".to_string(),
+        "Year".to_string(),
+        "Description".to_string(),
+    ];
+    let mut raw_data_list = Vec::new();
+
+    let items = list
+        .children()
+        .filter_map(scraper::ElementRef::wrap)
+        .filter(|child| child.value().name() == "li");
+
+    for (row_index, item) in items.enumerate() {
+        let text = item.text().collect::<String>().trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        let link = item.select(&a_selector).next();
+        let link_url = link
+            .and_then(|a| a.value().attr("href"))
+            .map(|href| format!("{}{}", base_url, href));
+
+        let year = trailing_parens_regex
+            .captures(&text)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str().trim().to_string())
+            .unwrap_or_default();
+
+        let title = link
+            .map(|a| a.text().collect::<String>().trim().to_string())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| {
+                trailing_parens_regex
+                    .replace(&text, "")
+                    .trim()
+                    .trim_end_matches(',')
+                    .to_string()
+            });
+
+        raw_data_list.push(RawCompositionData {
+            schema_version: RAW_SCHEMA_VERSION,
+            composer_name: composer_name.to_string(),
+            composer_url: composer_url.to_string(),
+            source_url: link_url.clone().unwrap_or_else(|| page_url.to_string()),
+            table_index,
+            row_index,
+            headers: headers.clone(),
+            cell_data: vec![title, year, text],
+            cell_links: vec![link_url, None, None],
+            raw_html_snippet: item.html(),
+            section_heading: section_heading.map(|h| h.to_string()),
+            table_caption: None,
+            table_classes: Vec::new(),
+            table_id: None,
+            was_collapsed: false,
+        });
+    }
+
+    raw_data_list
+}
+
+// Bounds on how much of a page is extracted. Tables with more rows than
+// max_rows_per_table are skipped (typically navboxes or indexes); at most
+// max_tables tables are read. None means no limit. Tables narrower than
+// min_columns are skipped too; the default of 2 drops single-column tables,
+// which are rarely work lists.
+#[derive(Clone, Copy, Debug)]
+pub struct TableLimits {
+    pub max_rows_per_table: Option<usize>,
+    pub max_tables: Option<usize>,
+    pub min_columns: usize,
+}
+
+impl Default for TableLimits {
+    fn default() -> Self {
+        Self {
+            max_rows_per_table: None,
+            max_tables: None,
+            min_columns: 2,
+        }
+    }
+}
+
+// Tables in a collapsible container are present in the HTML but folded
+// away on the page. That is how navboxes are built, but some list pages
+// also fold long work tables.
+fn is_collapsed(table: scraper::ElementRef) -> bool {
+    std::iter::once(table)
+        .chain(table.ancestors().filter_map(scraper::ElementRef::wrap))
+        .any(|element| {
+            element.value().classes().any(|class| {
+                matches!(
+                    class,
+                    "mw-collapsible" | "collapsible" | "mw-collapsed" | "autocollapse"
+                )
+            })
+        })
+}
+
+// A collapsed table is kept when its first header row names at least two
+// work fields (e.g. Title and Year); a navbox's title and group headings
+// name none.
+fn has_work_headers(table: scraper::ElementRef, canonicalizer: &FieldCanonicalizer) -> bool {
+    let tr_selector = Selector::parse("tr").unwrap();
+    let th_selector = Selector::parse("th").unwrap();
+    let Some(header_row) = table
+        .select(&tr_selector)
+        .find(|row| row.select(&th_selector).next().is_some())
+    else {
+        return false;
+    };
+    let fields: HashSet<&str> = header_row
+        .select(&th_selector)
+        .filter_map(|cell| canonicalizer.categorize_header(cell.text().collect::<String>().trim()))
+        .collect();
+    fields.len() >= 2
+}
+
+// Widest row of a table, counting header and data cells
+fn table_column_count(table: scraper::ElementRef) -> usize {
+    let tr_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    table
+        .select(&tr_selector)
+        .map(|row| row.select(&cell_selector).count())
+        .max()
+        .unwrap_or(0)
+}
+
+// Extract raw rows from every table on the page plus any bulleted lists that
+// sit under work-related section headings. List rows are numbered after the
+// tables (their table_index continues past the last table) and are dropped
+// when the same title already appeared in a table.
+pub fn extract_raw_data(
+    document: &Html,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+) -> Vec<RawCompositionData> {
+    extract_raw_data_with_limits(
+        document,
+        composer_name,
+        composer_url,
+        page_url,
+        &TableLimits::default(),
+    )
+}
+
+pub fn extract_raw_data_with_limits(
+    document: &Html,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+    limits: &TableLimits,
+) -> Vec<RawCompositionData> {
+    extract_raw_data_and_headings(document, composer_name, composer_url, page_url, limits).0
+}
+
+// Recognises the section headings that split a combined list page (a family
+// of composers, "List of Baroque composers' works") into one section per
+// composer: a heading naming one of `names`, or a name followed by life
+// years such as "Johann Christoph Bach (1642–1703)".
+#[derive(Clone, Debug, Default)]
+pub struct ComposerHeadings {
+    pub names: Vec<String>,
+}
+
+impl ComposerHeadings {
+    // The composer a heading introduces, if it introduces one
+    pub fn composer_in(&self, heading: &str) -> Option<String> {
+        let folded = fold_for_matching(heading);
+        if let Some(name) = self
+            .names
+            .iter()
+            .find(|name| folded.starts_with(&fold_for_matching(name)))
+        {
+            return Some(name.clone());
+        }
+
+        let life_years_regex = Regex::new(
+            r"^(\p{Lu}[^()]*?)\s*\((?:c\.\s*|b\.\s*)?\d{3,4}\s*[–-]\s*(?:c\.\s*)?\d{3,4}\)$",
+        )
+        .unwrap();
+        life_years_regex
+            .captures(heading.trim())
+            .map(|caps| caps[1].trim().to_string())
+    }
+}
+
+// As extract_raw_data_with_limits for a page listing several composers'
+// works. Rows under a composer heading (at any level above them) get that
+// composer's name and article URL; rows outside any composer's section
+// keep `composer_name`.
+pub fn extract_raw_data_by_composer(
+    document: &Html,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+    base_url: &str,
+    limits: &TableLimits,
+    composer_headings: &ComposerHeadings,
+) -> Vec<RawCompositionData> {
+    let (mut all_raw_data, heading_paths) =
+        extract_raw_data_and_headings(document, composer_name, composer_url, page_url, limits);
+
+    let section_composers: Vec<Option<String>> = heading_paths
+        .iter()
+        .map(|path| {
+            path.iter()
+                .rev()
+                .find_map(|heading| composer_headings.composer_in(heading))
+        })
+        .collect();
+
+    for raw_data in &mut all_raw_data {
+        if let Some(Some(section_composer)) = section_composers.get(raw_data.table_index) {
+            raw_data.composer_name = section_composer.clone();
+            raw_data.composer_url = format!("{}/wiki/{}", base_url, wiki_slug(section_composer));
+        }
+    }
+    all_raw_data
+}
+
+// The rows of every table and work list on the page, plus the h2-h4
+// headings enclosing each table_index (outermost first)
+fn extract_raw_data_and_headings(
+    document: &Html,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+    limits: &TableLimits,
+) -> (Vec<RawCompositionData>, Vec<Vec<String>>) {
+    let content_selector = Selector::parse("h2, h3, h4, table, ul").unwrap();
+    let tr_selector = Selector::parse("tr").unwrap();
+    let canonicalizer = FieldCanonicalizer::new();
+
+    let mut headings: [Option<String>; 3] = Default::default();
+    let mut tables = Vec::new();
+    let mut work_lists = Vec::new();
+
+    let mut table_headings = Vec::new();
+    let mut list_headings = Vec::new();
+
+    for element in document.select(&content_selector) {
+        let section_heading = headings.iter().flatten().last().cloned();
+        let heading_path = || headings.iter().flatten().cloned().collect::<Vec<String>>();
+
+        match element.value().name() {
+            "h2" | "h3" | "h4" => {
+                let level = match element.value().name() {
+                    "h2" => 0,
+                    "h3" => 1,
+                    _ => 2,
+                };
+                let text = element.text().collect::<String>().replace("[edit]", "");
+                headings[level] = Some(text.trim().to_string());
+                for deeper in headings.iter_mut().skip(level + 1) {
+                    *deeper = None;
+                }
+            }
+            "table" => {
+                let row_count = element.select(&tr_selector).count();
+                let column_count = table_column_count(element);
+                let collapsed = is_collapsed(element);
+                if collapsed && !has_work_headers(element, &canonicalizer) {
+                    info!(
+                        "Skipping collapsed table without work headers (likely a navbox) on {}",
+                        page_url
+                    );
+                } else if column_count < limits.min_columns {
+                    info!(
+                        "Skipping table with {} columns (under {}) on {}",
+                        column_count, limits.min_columns, page_url
+                    );
+                } else if let Some(max_rows) = limits.max_rows_per_table
+                    && row_count > max_rows
+                {
+                    info!(
+                        "Skipping table with {} rows (over {}) on {}",
+                        row_count, max_rows, page_url
+                    );
+                } else if limits.max_tables.is_some_and(|max| tables.len() >= max) {
+                    info!(
+                        "Skipping table beyond the first {} on {}",
+                        tables.len(),
+                        page_url
+                    );
+                } else {
+                    tables.push((element, section_heading, collapsed));
+                    table_headings.push(heading_path());
+                }
+            }
+            _ => {
+                if headings.iter().flatten().any(|h| is_work_heading(h))
+                    && !is_nested_or_navigation_list(element)
+                {
+                    work_lists.push((element, section_heading));
+                    list_headings.push(heading_path());
+                }
+            }
+        }
+    }
+
+    let table_count = tables.len();
+    let mut all_raw_data: Vec<RawCompositionData> = tables
+        .into_iter()
+        .enumerate()
+        .flat_map(|(table_index, (table, section_heading, collapsed))| {
+            extract_raw_table_data(
+                table,
+                composer_name,
+                composer_url,
+                page_url,
+                table_index,
+                section_heading.as_deref(),
+                collapsed,
+            )
+        })
+        .collect();
+
+    let table_cells: HashSet<String> = all_raw_data
+        .iter()
+        .flat_map(|raw_data| raw_data.cell_data.iter().cloned())
+        .collect();
+
+    for (offset, (list, section_heading)) in work_lists.into_iter().enumerate() {
+        let list_rows = extract_raw_list_data(
+            list,
+            composer_name,
+            composer_url,
+            page_url,
+            table_count + offset,
+            section_heading.as_deref(),
+        );
+        all_raw_data.extend(
+            list_rows
+                .into_iter()
+                .filter(|raw_data| !table_cells.contains(&raw_data.cell_data[0])),
+        );
+    }
+
+    table_headings.extend(list_headings);
+    (all_raw_data, table_headings)
+}
+
+// One table's rows and what was captured about the table itself, for
+// working out which table indices produce garbage
+#[derive(Serialize)]
+struct TableDump<'a> {
+    table_index: usize,
+    headers: &'a [String],
+    caption: Option<&'a str>,
+    classes: &'a [String],
+    id: Option<&'a str>,
+    section_heading: Option<&'a str>,
+    rows: Vec<&'a RawCompositionData>,
+}
+
+// Write table-<index>.json into `dir` for every table (or work list) that
+// yielded rows. Returns the files written, in table order.
+async fn write_debug_tables(
+    dir: &Path,
+    all_raw_data: &[RawCompositionData],
+) -> Result<Vec<PathBuf>> {
+    let mut dumps: BTreeMap<usize, TableDump> = BTreeMap::new();
+    for raw_data in all_raw_data {
+        dumps
+            .entry(raw_data.table_index)
+            .or_insert_with(|| TableDump {
+                table_index: raw_data.table_index,
+                headers: &raw_data.headers,
+                caption: raw_data.table_caption.as_deref(),
+                classes: &raw_data.table_classes,
+                id: raw_data.table_id.as_deref(),
+                section_heading: raw_data.section_heading.as_deref(),
+                rows: Vec::new(),
+            })
+            .rows
+            .push(raw_data);
+    }
+
+    tokio::fs::create_dir_all(dir).await?;
+    let mut paths = Vec::new();
+    for (table_index, dump) in dumps {
+        let path = dir.join(format!("table-{}.json", table_index));
+        tokio::fs::write(&path, serde_json::to_string_pretty(&dump)?).await?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+// Rows whose cell count differs from their header count by more than this
+// are taken to be misaligned; one cell either way is common (a trailing
+// notes column left empty, a rowspan carried over) and harmless.
+pub const MISALIGNMENT_THRESHOLD: usize = 1;
+
+// A table with rows whose cells probably don't line up with its headers,
+// e.g. from a rowspan or colspan the extraction got wrong
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MisalignedTable {
+    pub source_url: String,
+    pub table_index: usize,
+    pub headers: usize,
+    pub misaligned_rows: usize,
+    // The first such row, with its cell count
+    pub first_row_index: usize,
+    pub first_row_cells: usize,
+}
+
+// Check every row with headers against MISALIGNMENT_THRESHOLD, warning
+// once per table that has misaligned rows. Run under --strict. Rows are
+// grouped by table_index alone: a row's source_url is its own work link,
+// so tables are reported against `page_url`, the list page they came from.
+pub fn find_misaligned_tables(
+    all_raw_data: &[RawCompositionData],
+    page_url: &str,
+) -> Vec<MisalignedTable> {
+    let mut tables: Vec<MisalignedTable> = Vec::new();
+    for raw_data in all_raw_data {
+        if raw_data.headers.is_empty()
+            || raw_data.headers.len().abs_diff(raw_data.cell_data.len()) <= MISALIGNMENT_THRESHOLD
+        {
+            continue;
+        }
+        match tables
+            .iter_mut()
+            .find(|table| table.table_index == raw_data.table_index)
+        {
+            Some(table) => table.misaligned_rows += 1,
+            None => tables.push(MisalignedTable {
+                source_url: page_url.to_string(),
+                table_index: raw_data.table_index,
+                headers: raw_data.headers.len(),
+                misaligned_rows: 1,
+                first_row_index: raw_data.row_index,
+                first_row_cells: raw_data.cell_data.len(),
+            }),
+        }
+    }
+    for table in &tables {
+        warn!(
+            "Table {} of {} looks misaligned: {} rows differ from its {} headers by more than {} cells (first: row {} with {} cells)",
+            table.table_index,
+            table.source_url,
+            table.misaligned_rows,
+            table.headers,
+            MISALIGNMENT_THRESHOLD,
+            table.first_row_index,
+            table.first_row_cells
+        );
+    }
+    tables
+}
+
+// ------
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    Title,
+    Year,
+    Key,
+    Opus,
+    Genre,
+    CatalogNumber,
+    Instrumentation,
+    Duration,
+}
+
+impl Field {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Field::Title => "title",
+            Field::Year => "year",
+            Field::Key => "key",
+            Field::Opus => "opus",
+            Field::Genre => "genre",
+            Field::CatalogNumber => "catalog_number",
+            Field::Instrumentation => "instrumentation",
+            Field::Duration => "duration",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "title" => Some(Field::Title),
+            "year" => Some(Field::Year),
+            "key" => Some(Field::Key),
+            "opus" => Some(Field::Opus),
+            "genre" => Some(Field::Genre),
+            "catalog_number" => Some(Field::CatalogNumber),
+            "instrumentation" => Some(Field::Instrumentation),
+            "duration" => Some(Field::Duration),
+            _ => None,
+        }
+    }
+}
+
+// How table cells become Composition fields: which field a column header
+// names, and the value to take from a cell of that column. `extract`
+// returning None means "nothing recognisable"; for key, opus and year the
+// cell text is then kept as-is, for the rest the cell is skipped.
+// FieldCanonicalizer is the default; pass another implementation to
+// canonicalize_raw_data_with for datasets with their own conventions.
+pub trait FieldExtractor {
+    fn categorize(&self, header: &str) -> Option<Field>;
+    fn extract(&self, field: Field, cell: &str) -> Option<String>;
+
+    // When several columns map to one field they are read in ascending
+    // rank, ties in column order; the first usable value wins.
+    fn column_rank(&self, _field: Field, _header: &str) -> usize {
+        0
+    }
+}
+
+const CANONICAL_FIELDS: [&str; 8] = [
+    "title",
+    "year",
+    "key",
+    "opus",
+    "genre",
+    "catalog_number",
+    "instrumentation",
+    "duration",
+];
+
+// Extra header mappings for pages with idiosyncratic column names, loaded
+// from JSON and checked before the built-in patterns:
+//
+//     {
+//       "mappings": [
+//         {"pattern": "Uraufführung", "field": "year"},
+//         {"pattern": "^(BWV|Anh\\.)$", "field": "catalog_number", "regex": true}
+//       ],
+//       "ignore": ["Notes", "Ref."],
+//       "priority": {"year": ["composed", "premiere", "published"]},
+//       "table_headers": [
+//         {"url": "List_of_compositions_by_Erik_Satie", "table_index": 2,
+//          "headers": ["Title", "Year", "Notes"]}
+//       ],
+//       "fuzzy_threshold": 1
+//     }
+//
+// Plain patterns match as case-insensitive substrings of the header; headers
+// listed under "ignore" (case-insensitive, exact) are never mapped and end up
+// in additional_info. "priority" orders columns that map to the same field:
+// headers containing an earlier substring are read first, unlisted ones last.
+// It replaces the built-in order for that field (year: composed, written).
+// "table_headers" names the columns of tables that have no <th> row (see
+// apply_table_headers). "fuzzy_threshold" sets the edit distance allowed
+// when matching misspelled headers (see FUZZY_KEYWORDS); 0 turns it off.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct FieldMap {
+    #[serde(default)]
+    pub mappings: Vec<FieldMapEntry>,
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    #[serde(default)]
+    pub priority: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub table_headers: Vec<TableHeaders>,
+    #[serde(default)]
+    pub fuzzy_threshold: Option<usize>,
+}
+
+// Headers for a headerless table. `url` matches the list page's URL, or its
+// end (e.g. just the page title); `table_index` picks one table on it.
+// Either left out matches any page or table.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TableHeaders {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub table_index: Option<usize>,
+    pub headers: Vec<String>,
+}
+
+impl TableHeaders {
+    fn matches(&self, page_url: &str, table_index: usize) -> bool {
+        self.url
+            .as_deref()
+            .is_none_or(|url| page_url == url || page_url.ends_with(url))
+            && self.table_index.is_none_or(|index| index == table_index)
+    }
+}
+
+// Rows of headerless tables only have the generated column_0, column_1, ...
+fn has_generated_headers(raw_data: &RawCompositionData) -> bool {
+    let generated_regex = Regex::new(r"^column_\d+$").unwrap();
+    !raw_data.headers.is_empty() && raw_data.headers.iter().all(|h| generated_regex.is_match(h))
+}
+
+// Replace the generated headers of headerless tables on `page_url` with the
+// first matching user-supplied list, so their columns canonicalize. Columns
+// beyond the supplied headers keep their column_N names. Returns the number
+// of rows changed.
+pub fn apply_table_headers(
+    raw_data: &mut [RawCompositionData],
+    page_url: &str,
+    table_headers: &[TableHeaders],
+) -> usize {
+    let mut changed = 0;
+    for raw in raw_data.iter_mut().filter(|raw| has_generated_headers(raw)) {
+        let Some(supplied) = table_headers
+            .iter()
+            .find(|supplied| supplied.matches(page_url, raw.table_index))
+        else {
+            continue;
+        };
+        for (header, name) in raw.headers.iter_mut().zip(&supplied.headers) {
+            *header = name.clone();
+        }
+        changed += 1;
+    }
+    changed
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FieldMapEntry {
+    pub pattern: String,
+    pub field: String,
+    #[serde(default)]
+    pub regex: bool,
+}
+
+impl FieldMap {
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+// Words headers are compared against when no pattern matches, so that
+// "Ttile", "Genra" or "Yr." still map. Kept to words unlike any other
+// common header: "piece" would take "Place", "work" would take "Word",
+// "composed" would take "Composer".
+const FUZZY_KEYWORDS: &[(&str, &str)] = &[
+    ("title", "title"),
+    ("composition", "title"),
+    ("year", "year"),
+    ("yr", "year"),
+    ("tonality", "key"),
+    ("opus", "opus"),
+    ("genre", "genre"),
+    ("category", "genre"),
+    ("catalogue", "catalog_number"),
+    ("catalog", "catalog_number"),
+    ("instrumentation", "instrumentation"),
+    ("scoring", "instrumentation"),
+    ("ensemble", "instrumentation"),
+    ("duration", "duration"),
+    ("length", "duration"),
+    ("minutes", "duration"),
+];
+
+pub const DEFAULT_FUZZY_THRESHOLD: usize = 2;
+
+pub struct FieldCanonicalizer {
+    custom_patterns: Vec<(Regex, &'static str)>,
+    // Edit distance allowed between a header word and a FUZZY_KEYWORDS
+    // entry; 0 turns fuzzy matching off
+    fuzzy_threshold: usize,
+    ignored_headers: Vec<String>,
+    // Preferred header substrings (lowercase) per field, see FieldMap
+    priorities: HashMap<Field, Vec<String>>,
+    title_patterns: Vec<Regex>,
+    year_patterns: Vec<Regex>,
+    key_patterns: Vec<Regex>,
+    opus_patterns: Vec<Regex>,
+    genre_patterns: Vec<Regex>,
+    catalog_patterns: Vec<Regex>,
+    instrumentation_patterns: Vec<Regex>,
+    duration_patterns: Vec<Regex>,
+}
+
+impl FieldCanonicalizer {
+    pub fn new() -> Self {
+        Self {
+            custom_patterns: Vec::new(),
+            fuzzy_threshold: DEFAULT_FUZZY_THRESHOLD,
+            ignored_headers: Vec::new(),
+            priorities: HashMap::from([(
+                Field::Year,
+                vec!["composed".to_string(), "written".to_string()],
+            )]),
+            title_patterns: vec![Regex::new(r"(?i)title|work|composition|piece|name").unwrap()],
+            year_patterns: vec![Regex::new(r"(?i)year|date|composed|written|created").unwrap()],
+            key_patterns: vec![Regex::new(r"(?i)key|tonality").unwrap()],
+            opus_patterns: vec![
+                Regex::new(r"(?i)opus|op\.?|work number|woo|bwv|k\.?|hob\.?").unwrap(),
             ],
             genre_patterns: vec![Regex::new(r"(?i)genre|type|form|category").unwrap()],
             catalog_patterns: vec![
@@ -377,9 +2100,77 @@ impl FieldCanonicalizer {
         }
     }
 
-    fn categorize_header(&self, header: &str) -> Option<&'static str> {
+    fn with_field_map(mut self, field_map: &FieldMap) -> Result<Self> {
+        for entry in &field_map.mappings {
+            let field = CANONICAL_FIELDS
+                .iter()
+                .find(|field| **field == entry.field)
+                .ok_or_else(|| anyhow::anyhow!("Unknown canonical field: {}", entry.field))?;
+            let pattern = if entry.regex {
+                entry.pattern.clone()
+            } else {
+                regex::escape(&entry.pattern)
+            };
+            self.custom_patterns
+                .push((Regex::new(&format!("(?i){}", pattern))?, field));
+        }
+        self.ignored_headers
+            .extend(field_map.ignore.iter().map(|h| h.trim().to_lowercase()));
+        for (name, preferred) in &field_map.priority {
+            let field = Field::from_name(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown canonical field: {}", name))?;
+            self.priorities
+                .insert(field, preferred.iter().map(|p| p.to_lowercase()).collect());
+        }
+        if let Some(threshold) = field_map.fuzzy_threshold {
+            self.fuzzy_threshold = threshold;
+        }
+        Ok(self)
+    }
+
+    pub fn with_fuzzy_threshold(mut self, threshold: usize) -> Self {
+        self.fuzzy_threshold = threshold;
+        self
+    }
+
+    // The closest FUZZY_KEYWORDS entry to any word of the header, within
+    // fuzzy_threshold edits (a swap of two letters counting as one).
+    // Keywords allow at most one edit per three letters, so "Yr." has to
+    // match "yr" exactly and "Venue" is too far from "genre".
+    fn fuzzy_category(&self, header_lower: &str) -> Option<&'static str> {
+        if self.fuzzy_threshold == 0 {
+            return None;
+        }
+        fold_for_matching(header_lower)
+            .split_whitespace()
+            .flat_map(|word| {
+                FUZZY_KEYWORDS.iter().filter_map(move |(keyword, field)| {
+                    let allowed = self.fuzzy_threshold.min(keyword.len() / 3);
+                    let distance = transposition_distance(word, keyword);
+                    (distance <= allowed).then_some((distance, *field))
+                })
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, field)| field)
+    }
+
+    pub(crate) fn categorize_header(&self, header: &str) -> Option<&'static str> {
         let header_lower = header.to_lowercase();
 
+        if self
+            .ignored_headers
+            .contains(&header_lower.trim().to_string())
+        {
+            return None;
+        }
+        if let Some((_, field)) = self
+            .custom_patterns
+            .iter()
+            .find(|(p, _)| p.is_match(&header_lower))
+        {
+            return Some(field);
+        }
+
         if self
             .title_patterns
             .iter()
@@ -417,130 +2208,602 @@ impl FieldCanonicalizer {
         {
             Some("duration")
         } else {
-            None
+            self.fuzzy_category(&header_lower)
         }
     }
 
-    fn extract_year_from_text(&self, text: &str) -> Option<String> {
+    pub(crate) fn extract_year_from_text(&self, text: &str) -> Option<String> {
         let year_regex = Regex::new(r"\b(1[5-9]\d{2}|20[0-2]\d)\b").unwrap();
         year_regex.find(text).map(|m| m.as_str().to_string())
     }
 
-    fn extract_key_from_text(&self, text: &str) -> Option<String> {
+    // Matches "C minor", "B♭ major" and hyphenated "E-flat major"
+    // The tonic must be a capital A-G so that "a minor revision" is not a
+    // key; accidentals may be words ("E-flat", "F sharp"), symbols ("B♭",
+    // "C♯") or the ASCII shorthand written straight after the tonic ("Bb",
+    // "F#").
+    pub(crate) fn extract_key_from_text(&self, text: &str) -> Option<String> {
         let key_regex =
-            Regex::new(r"\b([A-G](?:\s*(?:flat|sharp|♭|♯))?\s*(?:major|minor|Major|Minor))\b")
+            Regex::new(r"\b[A-G](?:[\s-]*(?i:flat|sharp)\b|\s*[♭♯]|[b#])?[\s-]*(?i:major|minor)\b")
                 .unwrap();
-        key_regex.find(text).map(|m| m.as_str().to_string())
+        key_regex
+            .find(&normalize_dashes(text))
+            .map(|m| m.as_str().to_string())
     }
 
-    fn extract_opus_from_text(&self, text: &str) -> Option<String> {
-        let opus_regex = Regex::new(r"\b(?:Op\.|Opus|op\.)\s*(\d+(?:\s*[a-z])?)\b").unwrap();
-        opus_regex
-            .captures(text)
-            .and_then(|caps| caps.get(1))
-            .map(|m| m.as_str().to_string())
+    // Ranges such as "Op. 10–12" are kept whole, with the dash normalized;
+    // see Opus for the full structure
+    pub(crate) fn extract_opus_from_text(&self, text: &str) -> Option<String> {
+        Opus::parse(text).map(|opus| opus.to_string())
     }
 }
 
-fn canonicalize_raw_data(raw_data: RawCompositionData) -> Composition {
-    let canonicalizer = FieldCanonicalizer::new();
-
-    let mut composition = Composition {
-        composer_name: raw_data.composer_name.clone(),
-        composer_url: raw_data.composer_url.clone(),
-        source_url: raw_data.source_url.clone(),
-        title: String::new(),
-        work_url: None,
-        year: None,
-        key: None,
-        opus: None,
-        genre: None,
-        catalog_number: None,
-        instrumentation: None,
-        duration: None,
-        additional_info: HashMap::new(),
-        raw_data: raw_data.clone(),
-    };
+impl Default for FieldCanonicalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    // Map headers to canonical fields
-    let mut field_mappings: HashMap<&str, Vec<usize>> = HashMap::new();
+impl FieldExtractor for FieldCanonicalizer {
+    fn categorize(&self, header: &str) -> Option<Field> {
+        self.categorize_header(header).and_then(Field::from_name)
+    }
 
-    for (idx, header) in raw_data.headers.iter().enumerate() {
-        if let Some(field) = canonicalizer.categorize_header(header) {
-            field_mappings
-                .entry(field)
-                .or_insert_with(Vec::new)
-                .push(idx);
+    fn extract(&self, field: Field, cell: &str) -> Option<String> {
+        match field {
+            Field::Year => self.extract_year_from_text(cell),
+            Field::Key => self.extract_key_from_text(cell),
+            Field::Opus => self.extract_opus_from_text(cell),
+            _ => Some(cell.to_string()).filter(|cell| !cell.is_empty()),
         }
     }
 
-    // Extract data based on mappings
-    for (field, indices) in field_mappings {
-        for &idx in &indices {
-            if idx < raw_data.cell_data.len() {
-                let cell_data = &raw_data.cell_data[idx];
-                let cell_link = raw_data.cell_links.get(idx).and_then(|l| l.as_ref());
+    fn column_rank(&self, field: Field, header: &str) -> usize {
+        let Some(preferred) = self.priorities.get(&field) else {
+            return 0;
+        };
+        let header = header.to_lowercase();
+        preferred
+            .iter()
+            .position(|p| header.contains(p.as_str()))
+            .unwrap_or(preferred.len())
+    }
+}
 
-                match field {
-                    "title" => {
-                        if composition.title.is_empty() && !cell_data.is_empty() {
-                            composition.title = cell_data.clone();
+// Numbering in a work article's title, for rows that don't give it:
+// ".../wiki/BWV_1007" has catalogue number "BWV 1007",
+// ".../wiki/Symphony_No._5_(Beethoven)" number "5"
+#[derive(Debug, Default, PartialEq)]
+pub struct WorkUrlNumbers {
+    pub catalog_number: Option<String>,
+    pub opus: Option<String>,
+    pub number: Option<String>,
+}
+
+pub fn numbers_from_work_url(url: &str) -> WorkUrlNumbers {
+    let Some(title) = wiki_title(url) else {
+        return WorkUrlNumbers::default();
+    };
+    let catalog_regex =
+        Regex::new(r"\b(BWV|BuxWV|HWV|TWV|RV|KV|K\.|WoO|Hob\.)\s*((?:[IVXL]+[a-z]?:)?\d+[a-z]?)\b")
+            .unwrap();
+    let number_regex = Regex::new(r"\bNos?\.\s*(\d+[a-z]?)\b").unwrap();
+
+    WorkUrlNumbers {
+        catalog_number: catalog_regex
+            .captures(&title)
+            .map(|caps| format!("{} {}", &caps[1], &caps[2])),
+        opus: Opus::parse(&title).map(|opus| opus.to_string()),
+        number: number_regex
+            .captures(&title)
+            .map(|caps| caps[1].to_string()),
+    }
+}
+
+// A catalogue number as text that sorts the way the catalogue does: the
+// system without dots or spaces, then any Roman-numeral group (Hoboken's
+// "XVI:") and the number zero-padded, then letter suffixes. "BWV 1",
+// "BWV 10" and "BWV 1007" give "BWV 000001", "BWV 000010" and
+// "BWV 001007"; "K. 331" gives "K 000331" and "Hob. XVI:52" gives
+// "Hob 016:000052". A number with no system ("1007" from a column headed
+// "BWV") is just the padded number.
+pub fn catalog_sort_key(catalog_number: &str) -> Option<String> {
+    let catalog_regex =
+        Regex::new(r"^\s*([^\d\s][^\d]*?)?\s*(?:([IVXL]+):\s*)?(\d+)\s*([a-z]*)\b").unwrap();
+    let caps = catalog_regex.captures(catalog_number)?;
+    let number: u32 = caps[3].parse().ok()?;
+    let system: String = caps
+        .get(1)
+        .map(|system| {
+            system
+                .as_str()
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect()
+        })
+        .unwrap_or_default();
+    let group = caps
+        .get(2)
+        .and_then(|group| roman_to_number(group.as_str()))
+        .map(|group| format!("{:03}:", group))
+        .unwrap_or_default();
+    let key = format!("{}{:06}{}", group, number, &caps[4]);
+    Some(if system.is_empty() {
+        key
+    } else {
+        format!("{} {}", system, key)
+    })
+}
+
+// "XVI" -> 16; None for anything but I, V, X and L
+fn roman_to_number(roman: &str) -> Option<u32> {
+    let values: Vec<i32> = roman
+        .chars()
+        .map(|c| match c {
+            'I' => Some(1),
+            'V' => Some(5),
+            'X' => Some(10),
+            'L' => Some(50),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let mut total = 0;
+    for (i, value) in values.iter().enumerate() {
+        if values.get(i + 1).is_some_and(|next| next > value) {
+            total -= value;
+        } else {
+            total += value;
+        }
+    }
+    u32::try_from(total).ok().filter(|total| *total > 0)
+}
+
+static ARRANGEMENT_MARKER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:arr\.|arranged|arrangement|transcription|transcribed)(?:\s+(?:of|from)\s+([^()\[\]]+))?",
+    )
+    .unwrap()
+});
+static ARRANGEMENT_AFTER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\()\s*[Aa]fter\s+([A-Z][^()]*)").unwrap());
+
+// Spot arrangements and transcriptions: "Arr. of Bach BWV 565",
+// "Transcription of Beethoven's Symphony No. 9", "Chaconne (after Bach)".
+// Returns the source work when one is named. "after" only counts at the
+// start of the text or of a parenthetical, so "Music after Dark" isn't one.
+// Outside the title only "arrangement of/from ..." counts: a note such as
+// "orchestral arrangement by Ravel" describes a later version of the work.
+fn detect_arrangement(text: &str, in_title: bool) -> Option<Option<String>> {
+    let source = |m: Option<regex::Match>| {
+        m.map(|m| {
+            m.as_str()
+                .trim()
+                .trim_end_matches([',', ';', '.'])
+                .to_string()
+        })
+        .filter(|source| !source.is_empty())
+    };
+
+    if let Some(caps) = ARRANGEMENT_MARKER_REGEX.captures(text)
+        && (in_title || caps.get(1).is_some())
+    {
+        Some(source(caps.get(1)))
+    } else if in_title {
+        ARRANGEMENT_AFTER_REGEX
+            .captures(text)
+            .map(|caps| source(caps.get(1)))
+    } else {
+        None
+    }
+}
+
+// Lost, fragmentary and unfinished works are marked as such in a notes or
+// status column, or in brackets after the title: "Symphony (unfinished)".
+// Bare words in the title don't count, so "The Lost Chord" is complete.
+fn detect_status(title: &str, other_cells: &[&str]) -> WorkStatus {
+    let markers = [
+        (WorkStatus::Lost, r"(?i)\blost\b"),
+        (WorkStatus::Fragment, r"(?i)\bfragment(?:s|ary)?\b"),
+        (WorkStatus::Incomplete, r"(?i)\b(?:incomplete|unfinished)\b"),
+    ];
+    let bracketed_regex = Regex::new(r"[(\[]([^()\[\]]*)[)\]]").unwrap();
+    let bracketed: Vec<&str> = bracketed_regex
+        .captures_iter(title)
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+        .collect();
+
+    for (status, pattern) in markers {
+        let regex = Regex::new(pattern).unwrap();
+        if bracketed
+            .iter()
+            .chain(other_cells)
+            .any(|text| regex.is_match(text))
+        {
+            return status;
+        }
+    }
+    WorkStatus::Complete
+}
+
+// A trailing parenthetical counts as an alternate title only when it reads
+// like a name: capitalised, at least two words, and free of digits and key
+// words. That keeps "(K. 551)", "(1911)" or "(in C major)" on the title.
+fn is_alternate_title(text: &str) -> bool {
+    let key_words_regex = Regex::new(r"(?i)\b(major|minor|flat|sharp)\b").unwrap();
+
+    text.chars().next().is_some_and(|c| c.is_uppercase())
+        && text.split_whitespace().count() >= 2
+        && !text.chars().any(|c| c.is_ascii_digit())
+        && !key_words_regex.is_match(text)
+}
+
+fn split_alternate_titles(title: &str) -> (String, Vec<String>) {
+    let trailing_parens_regex = Regex::new(r"\s*\(([^()]*)\)\s*$").unwrap();
+
+    let mut title = title.trim().to_string();
+    let mut alt_titles = Vec::new();
+
+    while let Some(caps) = trailing_parens_regex.captures(&title) {
+        let inner = caps[1].trim().to_string();
+        let start = caps.get(0).unwrap().start();
+        if start == 0 || !is_alternate_title(&inner) {
+            break;
+        }
+        alt_titles.insert(0, inner);
+        title.truncate(start);
+    }
+
+    (title, alt_titles)
+}
+
+// Work-type words Wikipedia adds to article titles to tell works apart,
+// as in "Fidelio (opera)" or "Orfeo (Monteverdi opera)"
+const DISAMBIGUATION_WORDS: &[&str] = &[
+    "opera",
+    "operetta",
+    "ballet",
+    "symphony",
+    "composition",
+    "song",
+    "piece",
+    "oratorio",
+    "cantata",
+    "mass",
+    "suite",
+    "concerto",
+    "sonata",
+    "quartet",
+    "musical",
+    "work",
+];
+
+// Remove a redundant qualifier from a title: a "Beethoven: " style prefix,
+// or a trailing Wikipedia disambiguation suffix such as "(Beethoven)",
+// "(opera)" or "(Mozart opera)". The suffix may only name the composer
+// and work types, so "(K. 551)", "(in C major)" or "(Haydn)" on a Brahms
+// work are left alone. Returns None when the title has no such qualifier.
+fn strip_composer_qualifier(title: &str, composer_name: &str) -> Option<String> {
+    let full_name = composer_name.trim().to_lowercase();
+    let surname = full_name.split_whitespace().last()?.to_string();
+    let is_composer = |text: &str| {
+        let text = text.trim().to_lowercase();
+        text == full_name || text == surname
+    };
+    let is_disambiguation = |text: &str| {
+        let text = text.trim().to_lowercase();
+        let text = text.strip_prefix(&full_name).unwrap_or(&text);
+        let words: Vec<&str> = text.split_whitespace().collect();
+        !words.is_empty()
+            && words.iter().all(|word| {
+                *word == surname
+                    || DISAMBIGUATION_WORDS.iter().any(|w| {
+                        *word == *w
+                            || word.strip_suffix('s') == Some(*w)
+                            || word.strip_suffix("es") == Some(*w)
+                    })
+            })
+    };
+
+    let prefix_regex = Regex::new(r"^\s*([^:]+?)\s*:\s*(.+)$").unwrap();
+    let suffix_regex = Regex::new(r"^(.+?)\s*\(([^()]+)\)\s*$").unwrap();
+
+    if let Some(caps) = prefix_regex.captures(title)
+        && is_composer(&caps[1])
+    {
+        return Some(caps[2].trim().to_string());
+    }
+    if let Some(caps) = suffix_regex.captures(title)
+        && (is_composer(&caps[2]) || is_disambiguation(&caps[2]))
+    {
+        return Some(caps[1].trim().to_string());
+    }
+    None
+}
+
+fn push_distinct(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+// Composition and publication years from a cell that marks both, such as
+// "1804 (comp.) / 1806 (pub.)" or "composed 1804; published 1806". Each
+// part separated by "/", ";" or "," is read on its own; None unless both
+// kinds are found.
+fn composition_and_publication_years(text: &str) -> Option<(String, String)> {
+    let year_regex = Regex::new(r"\b(1[5-9]\d{2}|20[0-2]\d)\b").unwrap();
+    let composed_regex = Regex::new(r"(?i)\bcomp(?:\.|osed\b|osition\b|\b)").unwrap();
+    let published_regex = Regex::new(r"(?i)\bpub(?:\.|l\.|lished\b|lication\b|\b)").unwrap();
+
+    let mut composed = None;
+    let mut published = None;
+    for part in text.split(['/', ';', ',']) {
+        let Some(year) = year_regex.find(part).map(|m| m.as_str().to_string()) else {
+            continue;
+        };
+        if composed.is_none() && composed_regex.is_match(part) {
+            composed = Some(year);
+        } else if published.is_none() && published_regex.is_match(part) {
+            published = Some(year);
+        }
+    }
+    composed.zip(published)
+}
+
+// A year read from a year-labelled column is trusted. Otherwise the first
+// plausible year elsewhere in the row (often the title or a notes column)
+// is used, flagged as low confidence.
+fn reconcile_year(
+    composition: &mut Composition,
+    raw_data: &RawCompositionData,
+    year_from_column: bool,
+    extractor: &dyn FieldExtractor,
+) {
+    if year_from_column {
+        composition.year_confidence = Some(YearConfidence::High);
+        return;
+    }
+
+    let scraped_year = raw_data
+        .headers
+        .iter()
+        .zip(&raw_data.cell_data)
+        .filter(|(header, _)| extractor.categorize(header) != Some(Field::Year))
+        .find_map(|(header, cell_data)| {
+            extractor
+                .extract(Field::Year, cell_data)
+                .map(|year| (header, year))
+        });
+
+    if let Some((header, year)) = scraped_year {
+        composition.year = Some(year);
+        composition
+            .field_provenance
+            .insert(Field::Year.as_str().to_string(), header.clone());
+    }
+    if composition.year.is_some() {
+        composition.year_confidence = Some(YearConfidence::Low);
+    }
+}
+
+// The core fields canonicalize_raw_data_with filled from their own labeled
+// columns, before any fallback ran
+#[derive(Clone, Copy, Debug, Default)]
+struct LabeledFields {
+    title: bool,
+    key_or_opus: bool,
+    genre: bool,
+}
+
+// The mean, over title, year, key/opus and genre, of 1.0 for a value read
+// from a column labeled for it, 0.5 for one inferred some other way and
+// 0.0 for none. Inferred means: a title taken from the first linked cell;
+// a year scraped from another column or a year cell with no year in it
+// (YearConfidence::Low); a key or opus found in the title or work URL; a
+// genre from the table's caption or section heading. A row with all four
+// labeled scores 1.0; a bare linked title with nothing else 0.125.
+fn confidence_score(composition: &Composition, labeled: LabeledFields) -> f32 {
+    let score = |labeled: bool, present: bool| match (labeled, present) {
+        (true, _) => 1.0,
+        (false, true) => 0.5,
+        (false, false) => 0.0,
+    };
+    let year = match composition.year_confidence {
+        Some(YearConfidence::High) => 1.0,
+        Some(YearConfidence::Low) => 0.5,
+        None => 0.0,
+    };
+    let scores = [
+        score(labeled.title, !composition.title.is_empty()),
+        year,
+        score(
+            labeled.key_or_opus,
+            composition.key.is_some() || composition.opus.is_some(),
+        ),
+        score(labeled.genre, composition.genre.is_some()),
+    ];
+    scores.iter().sum::<f32>() / scores.len() as f32
+}
+
+// Whether a canonical field has been filled, for field_provenance
+fn has_field(composition: &Composition, field: Field) -> bool {
+    match field {
+        Field::Title => !composition.title.is_empty(),
+        Field::Year => composition.year.is_some(),
+        Field::Key => !composition.key_all.is_empty(),
+        Field::Opus => !composition.opus_all.is_empty(),
+        Field::Genre => composition.genre.is_some(),
+        Field::CatalogNumber => !composition.catalog_number_all.is_empty(),
+        Field::Instrumentation => composition.instrumentation.is_some(),
+        Field::Duration => composition.duration.is_some(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn canonicalize_raw_data(raw_data: RawCompositionData) -> Composition {
+    canonicalize_raw_data_with(raw_data, &FieldCanonicalizer::new())
+}
+
+pub fn canonicalize_raw_data_with(
+    raw_data: RawCompositionData,
+    extractor: &dyn FieldExtractor,
+) -> Composition {
+    let mut composition = Composition {
+        composer_name: raw_data.composer_name.clone(),
+        composer_url: raw_data.composer_url.clone(),
+        source_url: raw_data.source_url.clone(),
+        title: String::new(),
+        alt_titles: Vec::new(),
+        work_url: None,
+        year: None,
+        year_confidence: None,
+        composition_year: None,
+        publication_year: None,
+        year_numeric: None,
+        year_qualifier: None,
+        year_approximate: false,
+        key: None,
+        opus: None,
+        genre: None,
+        genres: Vec::new(),
+        catalog_number: None,
+        key_all: Vec::new(),
+        opus_all: Vec::new(),
+        opus_detail: None,
+        catalog_number_all: Vec::new(),
+        catalog_sort_key: None,
+        instrumentation: None,
+        duration: None,
+        duration_seconds: None,
+        iswc: None,
+        is_arrangement: false,
+        arrangement_of: None,
+        status: WorkStatus::Complete,
+        composer_known: None,
+        confidence: 0.0,
+        field_provenance: HashMap::new(),
+        additional_info: HashMap::new(),
+        raw_data: Some(raw_data.clone()),
+    };
+
+    // Map headers to canonical fields
+    let mut field_mappings: HashMap<Field, Vec<usize>> = HashMap::new();
+
+    for (idx, header) in raw_data.headers.iter().enumerate() {
+        if let Some(field) = extractor.categorize(header) {
+            field_mappings.entry(field).or_default().push(idx);
+        }
+    }
+    for (field, indices) in field_mappings.iter_mut() {
+        indices.sort_by_key(|&idx| extractor.column_rank(*field, &raw_data.headers[idx]));
+    }
+
+    let mut year_from_column = false;
+    // The year column's whole cell, e.g. "c. 1804" where year is "1804"
+    let mut year_text = None;
+
+    // Extract data based on mappings
+    for (field, indices) in field_mappings {
+        for &idx in &indices {
+            if idx < raw_data.cell_data.len() {
+                let cell_data = &raw_data.cell_data[idx];
+                let cell_link = raw_data.cell_links.get(idx).and_then(|l| l.as_ref());
+                let was_set = has_field(&composition, field);
+
+                match field {
+                    Field::Title if composition.title.is_empty() => {
+                        if let Some(title) = extractor.extract(Field::Title, cell_data) {
+                            composition.title = title;
                             composition.work_url = cell_link.map(|s| s.to_string());
                         }
                     }
-                    "year" => {
-                        if composition.year.is_none() {
-                            if let Some(year) = canonicalizer.extract_year_from_text(cell_data) {
-                                composition.year = Some(year);
-                            } else if !cell_data.is_empty() {
-                                composition.year = Some(cell_data.clone());
-                            }
+                    Field::Year if composition.year.is_none() => {
+                        if let Some((composed, published)) =
+                            composition_and_publication_years(cell_data)
+                        {
+                            composition.year = Some(composed.clone());
+                            year_text = Some(composed.clone());
+                            composition.composition_year = Some(composed);
+                            composition.publication_year = Some(published);
+                            year_from_column = true;
+                        } else if let Some(year) = extractor.extract(Field::Year, cell_data) {
+                            composition.year = Some(year);
+                            year_text = Some(cell_data.clone());
+                            year_from_column = true;
+                        } else if !cell_data.is_empty() {
+                            composition.year = Some(cell_data.clone());
                         }
                     }
-                    "key" => {
-                        if composition.key.is_none() {
-                            if let Some(key) = canonicalizer.extract_key_from_text(cell_data) {
-                                composition.key = Some(key);
-                            } else if !cell_data.is_empty() {
-                                composition.key = Some(cell_data.clone());
-                            }
+                    Field::Key => {
+                        if let Some(key) = extractor.extract(Field::Key, cell_data) {
+                            push_distinct(&mut composition.key_all, key);
+                        } else if !cell_data.is_empty() {
+                            push_distinct(&mut composition.key_all, cell_data.clone());
                         }
                     }
-                    "opus" => {
-                        if composition.opus.is_none() {
-                            if let Some(opus) = canonicalizer.extract_opus_from_text(cell_data) {
-                                composition.opus = Some(opus);
-                            } else if !cell_data.is_empty() {
-                                composition.opus = Some(cell_data.clone());
-                            }
+                    Field::Opus => {
+                        if let Some(opus) = extractor.extract(Field::Opus, cell_data) {
+                            push_distinct(&mut composition.opus_all, opus);
+                        } else if !cell_data.is_empty() {
+                            push_distinct(&mut composition.opus_all, cell_data.clone());
                         }
                     }
-                    "genre" => {
-                        if composition.genre.is_none() && !cell_data.is_empty() {
-                            composition.genre = Some(cell_data.clone());
+                    Field::Genre if composition.genre.is_none() => {
+                        if let Some(genre) = extractor.extract(Field::Genre, cell_data) {
+                            composition.genres = Genre::all_from_text(&genre);
+                            composition.genre = split_genre_cell(&genre)
+                                .first()
+                                .map(|first| first.to_string());
                         }
                     }
-                    "catalog_number" => {
-                        if composition.catalog_number.is_none() && !cell_data.is_empty() {
-                            composition.catalog_number = Some(cell_data.clone());
+                    Field::CatalogNumber => {
+                        if let Some(catalog_number) =
+                            extractor.extract(Field::CatalogNumber, cell_data)
+                        {
+                            push_distinct(&mut composition.catalog_number_all, catalog_number);
                         }
                     }
-                    "instrumentation" => {
-                        if composition.instrumentation.is_none() && !cell_data.is_empty() {
-                            composition.instrumentation = Some(cell_data.clone());
-                        }
+                    Field::Instrumentation if composition.instrumentation.is_none() => {
+                        composition.instrumentation =
+                            extractor.extract(Field::Instrumentation, cell_data);
                     }
-                    "duration" => {
-                        if composition.duration.is_none() && !cell_data.is_empty() {
-                            composition.duration = Some(cell_data.clone());
-                        }
+                    Field::Duration if composition.duration.is_none() => {
+                        composition.duration = extractor.extract(Field::Duration, cell_data);
                     }
                     _ => {}
                 }
+
+                if !was_set && has_field(&composition, field) {
+                    composition
+                        .field_provenance
+                        .insert(field.as_str().to_string(), raw_data.headers[idx].clone());
+                }
             }
         }
     }
 
+    let labeled = LabeledFields {
+        title: !composition.title.is_empty(),
+        key_or_opus: !composition.key_all.is_empty() || !composition.opus_all.is_empty(),
+        genre: composition.genre.is_some(),
+    };
+
+    reconcile_year(&mut composition, &raw_data, year_from_column, extractor);
+    if let Some(year_text) = year_text.or_else(|| composition.year.clone()) {
+        composition.set_year_numeric(&year_text);
+    }
+
+    composition.duration_seconds = composition
+        .duration
+        .as_deref()
+        .and_then(parse_duration_seconds);
+
+    composition.iswc = raw_data.cell_data.iter().find_map(|cell| find_iswc(cell));
+
+    composition.key = composition.key_all.first().cloned();
+    composition.opus = composition.opus_all.first().cloned();
+    composition.catalog_number = composition.catalog_number_all.first().cloned();
+
     // Fallback: if no title found, use first non-empty cell with a link
     if composition.title.is_empty() {
         for (idx, cell_data) in raw_data.cell_data.iter().enumerate() {
@@ -553,135 +2816,3496 @@ fn canonicalize_raw_data(raw_data: RawCompositionData) -> Composition {
             {
                 composition.title = cell_data.clone();
                 composition.work_url = raw_data.cell_links[idx].clone();
+                if let Some(header) = raw_data.headers.get(idx) {
+                    composition
+                        .field_provenance
+                        .insert(Field::Title.as_str().to_string(), header.clone());
+                }
                 break;
             }
         }
     }
 
-    // Store unmapped data in additional_info
-    for (idx, (header, cell_data)) in raw_data
+    if let Some(clean_title) = strip_composer_qualifier(&composition.title, &raw_data.composer_name)
+    {
+        let original_title = std::mem::replace(&mut composition.title, clean_title);
+        composition
+            .additional_info
+            .insert("original_title".to_string(), original_title);
+    }
+
+    let (title, alt_titles) = split_alternate_titles(&composition.title);
+    composition.title = title;
+    composition.alt_titles = alt_titles;
+
+    if let Some(arrangement_of) = detect_arrangement(&composition.title, true).or_else(|| {
+        raw_data
+            .cell_data
+            .iter()
+            .find_map(|text| detect_arrangement(text, false))
+    }) {
+        composition.is_arrangement = true;
+        composition.arrangement_of = arrangement_of;
+    }
+
+    let other_cells: Vec<&str> = raw_data
         .headers
         .iter()
-        .zip(raw_data.cell_data.iter())
-        .enumerate()
+        .zip(&raw_data.cell_data)
+        .filter(|(header, _)| extractor.categorize(header) != Some(Field::Title))
+        .map(|(_, cell_data)| cell_data.as_str())
+        .collect();
+    composition.status = detect_status(&composition.title, &other_cells);
+
+    // Piano works in particular often give their key only in the title
+    if composition.key.is_none()
+        && let Some(key) = extractor.extract(Field::Key, &composition.title)
+    {
+        composition.key_all.push(key.clone());
+        composition.key = Some(key);
+    }
+
+    // Likewise the opus ("Piano Sonata No. 14, Op. 27 No. 2"). Catalogue-only
+    // titles such as "BWV 1007" or "K. 525" have no opus and don't match.
+    if composition.opus.is_none()
+        && let Some(opus) = extractor.extract(Field::Opus, &composition.title)
     {
-        if canonicalizer.categorize_header(header).is_none() && !cell_data.is_empty() {
+        composition.opus_all.push(opus.clone());
+        composition.opus = Some(opus);
+    }
+
+    // Then the work article's title, e.g. ".../wiki/BWV_1007". A bare
+    // "No. 5" is kept as additional_info["number"] when there is no
+    // catalogue number or opus to hold it.
+    if (composition.catalog_number.is_none() || composition.opus.is_none())
+        && let Some(work_url) = composition.work_url.clone()
+    {
+        let numbers = numbers_from_work_url(&work_url);
+        if composition.catalog_number.is_none()
+            && let Some(catalog_number) = numbers.catalog_number
+        {
+            composition.catalog_number_all.push(catalog_number.clone());
+            composition.catalog_number = Some(catalog_number);
+        }
+        if composition.opus.is_none()
+            && let Some(opus) = numbers.opus
+        {
+            composition.opus_all.push(opus.clone());
+            composition.opus = Some(opus);
+        }
+        if composition.catalog_number.is_none()
+            && composition.opus.is_none()
+            && let Some(number) = numbers.number
+        {
+            composition
+                .additional_info
+                .entry("number".to_string())
+                .or_insert(number);
+        }
+    }
+
+    composition.catalog_sort_key = composition
+        .catalog_number
+        .as_deref()
+        .and_then(catalog_sort_key);
+
+    // Structured form of the chosen opus, from the opus columns or the title
+    if let Some(opus) = &composition.opus {
+        composition.opus_detail = raw_data
+            .headers
+            .iter()
+            .zip(&raw_data.cell_data)
+            .filter(|(header, _)| extractor.categorize(header) == Some(Field::Opus))
+            .map(|(_, cell_data)| cell_data)
+            .chain(std::iter::once(&composition.title))
+            .filter_map(|text| Opus::parse(text))
+            .find(|detail| detail.to_string() == *opus);
+    }
+
+    // Tables grouped by genre often only say so in their caption or heading
+    if composition.genre.is_none() {
+        composition.genre = [&raw_data.table_caption, &raw_data.section_heading]
+            .into_iter()
+            .flatten()
+            .find(|text| Genre::from_text(text) != Genre::Other)
+            .cloned();
+        composition.genres = composition
+            .genre
+            .as_deref()
+            .map(Genre::from_text)
+            .into_iter()
+            .collect();
+    }
+
+    // Store unmapped data in additional_info
+    for (header, cell_data) in raw_data.headers.iter().zip(raw_data.cell_data.iter()) {
+        if extractor.categorize(header).is_none() && !cell_data.is_empty() {
             composition
                 .additional_info
                 .insert(header.clone(), cell_data.clone());
         }
     }
 
+    // Cells past the last header (merged header cells, a column only some
+    // rows have) are kept under column_N, N being the cell's position
+    if raw_data.cell_data.len() > raw_data.headers.len() {
+        warn!(
+            "Row {} of table {} on {} has {} cells but {} headers",
+            raw_data.row_index,
+            raw_data.table_index,
+            raw_data.source_url,
+            raw_data.cell_data.len(),
+            raw_data.headers.len()
+        );
+        for (idx, cell_data) in raw_data
+            .cell_data
+            .iter()
+            .enumerate()
+            .skip(raw_data.headers.len())
+        {
+            if !cell_data.is_empty() {
+                composition
+                    .additional_info
+                    .insert(format!("column_{}", idx), cell_data.clone());
+            }
+        }
+    }
+
+    composition.confidence = confidence_score(&composition, labeled);
     composition
 }
 
-pub async fn get_works(composer_name: &str) -> Result<()> {
-    let base_wiki_url = "https://en.wikipedia.org";
-    let compositions_url = format!(
-        "{}/wiki/List_of_compositions_by_{}",
-        base_wiki_url,
-        composer_name.replace(" ", "_")
-    );
-
-    let composer_url = format!("{}/wiki/{}", base_wiki_url, composer_name.replace(" ", "_"));
-
-    info!(
-        "Fetching works for {} from {}",
-        composer_name, compositions_url
-    );
+// Members of a "Category:Compositions by X" page become minimal rows: the
+// article title and link, plus any opus number found in the title. A year
+// in the title is picked up, with low confidence, by reconcile_year.
+fn extract_category_members(
+    document: &Html,
+    composer_name: &str,
+    composer_url: &str,
+    base_url: &str,
+    table_index: usize,
+) -> Vec<RawCompositionData> {
+    let member_selector = Selector::parse("#mw-pages li a[href^=\"/wiki/\"]").unwrap();
+    let canonicalizer = FieldCanonicalizer::new();
 
-    let response = reqwest::get(&compositions_url).await?;
-    let html = response.text().await?;
-    let document = Html::parse_document(&html);
+    let headers = vec!["Title".to_string(), "Opus".to_string()];
 
-    let table_selector = Selector::parse("table").unwrap();
+    document
+        .select(&member_selector)
+        .enumerate()
+        .filter_map(|(row_index, link)| {
+            let title = link.text().collect::<String>().trim().to_string();
+            let href = link.value().attr("href")?;
+            if title.is_empty() {
+                return None;
+            }
+            let work_url = format!("{}{}", base_url, href);
+            let opus = canonicalizer
+                .extract_opus_from_text(&title)
+                .unwrap_or_default();
 
-    // Stage 1: Extract and save raw data
-    let raw_filename = format!("raw-info-{}.json", composer_name.replace(" ", "_"));
-    let (raw_tx, raw_rx) = mpsc::channel::<RawCompositionData>(100);
+            Some(RawCompositionData {
+                schema_version: RAW_SCHEMA_VERSION,
+                composer_name: composer_name.to_string(),
+                composer_url: composer_url.to_string(),
+                source_url: work_url.clone(),
+                table_index,
+                row_index,
+                headers: headers.clone(),
+                cell_data: vec![title, opus],
+                cell_links: vec![Some(work_url), None],
+                raw_html_snippet: link.html(),
+                section_heading: None,
+                table_caption: None,
+                table_classes: Vec::new(),
+                table_id: None,
+                was_collapsed: false,
+            })
+        })
+        .collect()
+}
 
-    let raw_filename_clone = raw_filename.clone();
-    let raw_writer_handle =
-        tokio::spawn(async move { raw_data_writer_task(raw_rx, &raw_filename_clone).await });
+// The path of a file:// URL
+pub fn local_path(url: &str) -> Option<&str> {
+    url.strip_prefix("file://")
+}
 
-    let mut all_raw_data = Vec::new();
+// A page's HTML, or None if it doesn't exist. file:// URLs are read from
+// disk, so saved pages go through the same extraction as fetched ones.
+pub(crate) async fn fetch_html(
+    client: &reqwest::Client,
+    url: &str,
+    http: &HttpOptions,
+) -> Result<Option<String>> {
+    if let Some(path) = local_path(url) {
+        return match tokio::fs::read_to_string(path).await {
+            Ok(html) => Ok(Some(html)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        };
+    }
+    let response = get_with_retry(client, url, http).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(response.error_for_status()?.text().await?))
+}
 
-    // Process all tables on the page
-    for (table_index, table) in document.select(&table_selector).enumerate() {
-        let raw_data_list = extract_raw_table_data(
-            table,
-            composer_name,
-            &composer_url,
-            &compositions_url,
-            table_index,
-        );
+// Walk a category listing and its "next page" continuations. Each page's
+// members get their own table_index so row indices stay unique.
+async fn fetch_category_raw_data(
+    client: &reqwest::Client,
+    category_url: &str,
+    composer_name: &str,
+    composer_url: &str,
+    base_url: &str,
+    http: &HttpOptions,
+) -> Result<Vec<RawCompositionData>> {
+    let mut raw_data = Vec::new();
+    let mut visited_pages = HashSet::new();
+    let mut next_url = Some(category_url.to_string());
 
-        for raw_data in raw_data_list {
-            // Send to raw data writer
-            if let Err(e) = raw_tx.send(raw_data.clone()).await {
-                error!("Error sending raw data through channel: {}", e);
-            }
-            all_raw_data.push(raw_data);
+    while let Some(page_url) = next_url.take() {
+        if !visited_pages.insert(page_url.clone()) {
+            break;
         }
+        let Some(html) = fetch_html(client, &page_url, http).await? else {
+            break;
+        };
+        let document = Html::parse_document(&html);
+        raw_data.extend(extract_category_members(
+            &document,
+            composer_name,
+            composer_url,
+            base_url,
+            visited_pages.len() - 1,
+        ));
+        next_url = find_next_page_url(&document, &page_url);
     }
 
-    drop(raw_tx);
-    raw_writer_handle.await??;
-
-    info!(
-        "Saved {} raw composition records to {}",
-        all_raw_data.len(),
-        raw_filename
-    );
+    Ok(raw_data)
+}
 
-    // Stage 2: Canonicalize and save processed compositions
-    let (comp_tx, comp_rx) = mpsc::channel::<Composition>(100);
-    let comp_writer_handle =
-        tokio::spawn(async move { composition_writer_task(comp_rx, "compositions.json").await });
+// Title patterns of works-list articles, most common first
+const LIST_PAGE_TEMPLATES: &[&str] = &[
+    "List_of_compositions_by_{}",
+    "List_of_works_by_{}",
+    "List_of_compositions_by_{}_(composer)",
+    "List_of_works_by_{}_(composer)",
+    "List_of_musical_compositions_by_{}",
+];
 
-    let mut canonicalized_count = 0;
-    for raw_data in all_raw_data {
-        let composition = canonicalize_raw_data(raw_data);
+fn list_page_candidates(base_url: &str, composer_name: &str) -> Vec<String> {
+    let name = wiki_slug(composer_name);
+    LIST_PAGE_TEMPLATES
+        .iter()
+        .map(|template| format!("{}/wiki/{}", base_url, template.replace("{}", &name)))
+        .collect()
+}
 
-        // Only save compositions with meaningful titles
-        if !composition.title.is_empty() && composition.title.len() > 2 {
-            if let Err(e) = comp_tx.send(composition).await {
-                error!("Error sending composition through channel: {}", e);
-            } else {
-                canonicalized_count += 1;
-            }
+// Try each candidate list page in turn and return the first that exists,
+// with its HTML. Wikipedia's own redirects already cover many variants, so
+// this only costs extra requests for composers whose page isn't found.
+async fn find_list_page(
+    client: &reqwest::Client,
+    base_url: &str,
+    composer_name: &str,
+    http: &HttpOptions,
+) -> Result<Option<(String, String)>> {
+    for url in list_page_candidates(base_url, composer_name) {
+        if let Some(html) = fetch_html(client, &url, http).await? {
+            info!("Using works list {}", url);
+            return Ok(Some((url, html)));
         }
+        info!("No works list at {}", url);
     }
+    Ok(None)
+}
 
-    drop(comp_tx);
-    comp_writer_handle.await??;
-
-    info!(
-        "Canonicalized and saved {} compositions to compositions.json",
-        canonicalized_count
-    );
-
-    Ok(())
+// The raw rows of every table and work list on a list page
+fn extract_list_page(
+    html: &str,
+    composer_name: &str,
+    composer_url: &str,
+    page_url: &str,
+    options: &WorksOptions,
+) -> Vec<RawCompositionData> {
+    let document = Html::parse_document(html);
+    match &options.composer_sections {
+        Some(composer_headings) => extract_raw_data_by_composer(
+            &document,
+            composer_name,
+            composer_url,
+            page_url,
+            &options.base_url,
+            &options.table_limits,
+            composer_headings,
+        ),
+        None => extract_raw_data_with_limits(
+            &document,
+            composer_name,
+            composer_url,
+            page_url,
+            &options.table_limits,
+        ),
+    }
 }
 
-// Helper function to process raw data files later if needed
-pub async fn reprocess_raw_data(raw_filename: &str) -> Result<Vec<Composition>> {
-    use tokio::fs::File;
-    use tokio::io::{AsyncBufReadExt, BufReader};
+// The title of a /wiki/ URL without the suffix that marks one part of a
+// list split across pages: "(A–M)", "(N–Z)", "(part 2)", "(1830–1849)".
+// Returns the title and whether it had such a suffix.
+fn split_list_stem(url: &str) -> Option<(String, bool)> {
+    let part_regex =
+        Regex::new(r"^(.*?)\s*\((?:[A-Z]\s*-\s*[A-Z]|[Pp]art\s+\d+|\d{3,4}\s*-\s*\d{3,4})\)$")
+            .unwrap();
+    let title = normalize_dashes(&wiki_title(url)?);
+    Some(match part_regex.captures(&title) {
+        Some(caps) => (caps[1].to_string(), true),
+        None => (title, false),
+    })
+}
 
-    let file = File::open(raw_filename).await?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-    let mut compositions = Vec::new();
+// The other parts of a works list split across pages, as linked from one
+// of them: by a navigation template, a hatnote or an index page, all of
+// which link to titles sharing the list's name with a different part
+// suffix. The page's canonical link stands in for `page_url` when it has
+// one, since "List of compositions by X" often redirects to its "(A–M)"
+// part. Links are resolved against `base_url`, in page order.
+pub fn continuation_pages(html: &str, page_url: &str, base_url: &str) -> Vec<String> {
+    let canonical_selector = Selector::parse("link[rel='canonical']").unwrap();
+    let link_selector = Selector::parse("a[href^='/wiki/']").unwrap();
+    let document = Html::parse_document(html);
+    let page_url = document
+        .select(&canonical_selector)
+        .next()
+        .and_then(|link| link.value().attr("href"))
+        .unwrap_or(page_url);
+    let Some((stem, _)) = split_list_stem(page_url) else {
+        return Vec::new();
+    };
+    let own_title = wiki_title(page_url);
 
-    while let Some(line) = lines.next_line().await? {
-        if let Ok(raw_data) = serde_json::from_str::<RawCompositionData>(&line) {
-            let composition = canonicalize_raw_data(raw_data);
-            if !composition.title.is_empty() {
-                compositions.push(composition);
+    let mut pages: Vec<String> = Vec::new();
+    for link in document.select(&link_selector) {
+        let Some(href) = link.value().attr("href") else {
+            continue;
+        };
+        let href = href.split('#').next().unwrap_or_default();
+        if wiki_title(href) == own_title {
+            continue;
+        }
+        if let Some((link_stem, true)) = split_list_stem(href)
+            && link_stem == stem
+        {
+            let url = format!("{}{}", base_url, href);
+            if !pages.contains(&url) {
+                pages.push(url);
             }
         }
     }
+    pages
+}
+
+// The rows of a split list's pages as one list, table indices carrying on
+// from page to page so tables stay distinct. Also returns the first
+// table_index of each page after the first, for drop_repeats_across_pages.
+pub fn merge_continuation_rows(
+    pages: Vec<Vec<RawCompositionData>>,
+) -> (Vec<RawCompositionData>, Vec<usize>) {
+    let mut merged = Vec::new();
+    let mut page_starts = Vec::new();
+    let mut table_offset = 0;
+    for (page, rows) in pages.into_iter().enumerate() {
+        if page > 0 {
+            page_starts.push(table_offset);
+        }
+        let next_offset = table_offset
+            + rows
+                .iter()
+                .map(|raw_data| raw_data.table_index + 1)
+                .max()
+                .unwrap_or(0);
+        merged.extend(rows.into_iter().map(|mut raw_data| {
+            raw_data.table_index += table_offset;
+            raw_data
+        }));
+        table_offset = next_offset;
+    }
+    (merged, page_starts)
+}
 
-    Ok(compositions)
+// Drop compositions a split list already has on an earlier page (same
+// title and catalog number or opus, see MasterIndex), compared as the run
+// canonicalized them. Repeats within a page are left alone. A
+// composition's page is read from its raw table_index against
+// `page_starts` (see merge_continuation_rows).
+pub fn drop_repeats_across_pages(
+    compositions: Vec<Composition>,
+    page_starts: &[usize],
+) -> Vec<Composition> {
+    let page_of = |composition: &Composition| {
+        let table_index = composition
+            .raw_data
+            .as_ref()
+            .map_or(0, |raw_data| raw_data.table_index);
+        page_starts
+            .iter()
+            .filter(|&&start| start <= table_index)
+            .count()
+    };
+
+    let mut earlier_pages = MasterIndex::default();
+    let mut this_page = MasterIndex::default();
+    let mut current_page = 0;
+    let mut kept = Vec::with_capacity(compositions.len());
+    for composition in compositions {
+        let page = page_of(&composition);
+        if page != current_page {
+            earlier_pages
+                .keys
+                .extend(std::mem::take(&mut this_page.keys));
+            current_page = page;
+        }
+        if earlier_pages.contains(&composition) {
+            info!(
+                "Dropping \"{}\", already listed on an earlier page",
+                composition.title
+            );
+            continue;
+        }
+        this_page.insert(&composition);
+        kept.push(composition);
+    }
+    kept
+}
+
+// Estimates from preflight at or above this are logged as a warning
+pub const LARGE_WORKS_ESTIMATE: usize = 1000;
+
+// Roughly how many works get_works would find for a composer: the rows of
+// the candidate work tables and lists on its list page, counted without
+// canonicalizing or writing anything. The wikitext and category fallbacks
+// are not tried, so a composer whose list page has no usable tables
+// estimates 0.
+pub async fn preflight(composer_name: &str, options: &WorksOptions) -> Result<usize> {
+    let client = build_client(&options.http)?;
+    let list_page = match &options.works_url {
+        Some(url) => fetch_html(&client, url, &options.http)
+            .await?
+            .map(|html| (url.clone(), html)),
+        None => find_list_page(&client, &options.base_url, composer_name, &options.http).await?,
+    };
+    let Some((url, html)) = list_page else {
+        return Err(WorksError::NotFound {
+            url: list_page_candidates(&options.base_url, composer_name).remove(0),
+        }
+        .into());
+    };
+
+    let composer_url = format!("{}/wiki/{}", options.base_url, wiki_slug(composer_name));
+    let estimate = extract_list_page(&html, composer_name, &composer_url, &url, options).len();
+    if estimate >= LARGE_WORKS_ESTIMATE {
+        warn!(
+            "{} lists about {} works at {}; consider --max-tables or --max-rows-per-table",
+            composer_name, estimate, url
+        );
+    } else {
+        info!(
+            "{} lists about {} works at {}",
+            composer_name, estimate, url
+        );
+    }
+    Ok(estimate)
+}
+
+// Scrape one composer's works page; returns the number of compositions saved.
+pub async fn get_works(composer_name: &str, options: &WorksOptions) -> Result<usize> {
+    get_works_with(composer_name, options, output_sink(options).await?).await
+}
+
+// As get_works, writing compositions to `sink` instead of the files
+pub async fn get_works_with(
+    composer_name: &str,
+    options: &WorksOptions,
+    sink: Box<dyn CompositionSink>,
+) -> Result<usize> {
+    let (sink, writer) = SharedSink::spawn(sink);
+    let result = get_works_into(composer_name, options, &sink).await;
+    drop(sink);
+
+    let outputs = writer.await??;
+    if !outputs.is_empty() {
+        info!("Compositions written to {}", outputs.join(", "));
+    }
+    result
+}
+
+// Stage 2 for a set of raw rows, shared by scraping and reprocessing:
+// summary rows are skipped (unless kept), then rows are canonicalized and
+// only those with a meaningful title that doesn't just repeat the composer
+// are kept
+pub fn canonicalize_rows(
+    raw_rows: Vec<RawCompositionData>,
+    extractor: &dyn FieldExtractor,
+    keep_total_rows: bool,
+    life: Option<LifeYears>,
+) -> Vec<Composition> {
+    let before = raw_rows.len();
+    let raw_rows: Vec<RawCompositionData> = raw_rows
+        .into_iter()
+        .filter(|raw_data| keep_total_rows || !is_total_row(raw_data))
+        .collect();
+    if raw_rows.len() < before {
+        info!("Skipped {} total/summary rows", before - raw_rows.len());
+    }
+
+    raw_rows
+        .into_iter()
+        .map(|raw_data| canonicalize_raw_data_with(raw_data, extractor))
+        .filter(has_meaningful_title)
+        .filter(|composition| {
+            let echo = is_composer_echo(composition, life);
+            if echo {
+                info!(
+                    "Dropping row that repeats the composer: {:?}",
+                    composition.raw_data.as_ref().map(|raw| &raw.cell_data)
+                );
+            }
+            !echo
+        })
+        .collect()
+}
+
+// Whether a title is long enough to name a work: three characters or
+// more, counted as chars so "Ré" is as short as "Re" and a single CJK
+// character doesn't pass on its three UTF-8 bytes
+pub fn has_meaningful_title(composition: &Composition) -> bool {
+    composition.title.chars().count() > 2
+}
+
+// Marks a raw_html_snippet cut short by truncate_snippet
+pub const SNIPPET_ELLIPSIS: &str = "…";
+
+// Cut a snippet to at most `max_bytes`, ending on a char boundary with
+// SNIPPET_ELLIPSIS so a truncated snippet can't pass for a whole row. A
+// limit of 0 empties it.
+pub fn truncate_snippet(snippet: &mut String, max_bytes: usize) {
+    if snippet.len() <= max_bytes {
+        return;
+    }
+    let marker = if max_bytes >= SNIPPET_ELLIPSIS.len() {
+        SNIPPET_ELLIPSIS
+    } else {
+        ""
+    };
+    let mut end = max_bytes - marker.len();
+    while !snippet.is_char_boundary(end) {
+        end -= 1;
+    }
+    snippet.truncate(end);
+    snippet.push_str(marker);
+}
+
+// Summary rows at the foot of a works table: the first filled cell is a
+// label ("Total: 2 works", "Subtotal 12", "Count: 40"), or is just "Total"
+// with nothing but counts beside it, or a single cell standing in for a row
+// of several columns mentions a total ("104 symphonies in total"). The label
+// needs its colon or number so "Total Eclipse" and "Count Ory" stay titles.
+pub fn is_total_row(raw_data: &RawCompositionData) -> bool {
+    let label_regex =
+        Regex::new(r"(?i)^\s*(?:grand\s+)?(?:sub-?)?totals?\s*(?::|\d)|^\s*(?:sum|count)\s*:")
+            .unwrap();
+    let bare_label_regex = Regex::new(r"(?i)^\s*(?:grand\s+)?(?:sub-?)?totals?\s*$").unwrap();
+    let count_regex = Regex::new(r"^\s*\d[\d,.]*\s*$").unwrap();
+    let mention_regex = Regex::new(r"(?i)\b(?:(?:sub-?)?totals?|sum|count)\b").unwrap();
+
+    let filled: Vec<&String> = raw_data
+        .cell_data
+        .iter()
+        .filter(|cell| !cell.trim().is_empty())
+        .collect();
+    let Some(first) = filled.first() else {
+        return false;
+    };
+    label_regex.is_match(first)
+        || (bare_label_regex.is_match(first)
+            && filled[1..].iter().all(|cell| count_regex.is_match(cell)))
+        || (raw_data.headers.len() >= 2
+            && filled.len() == 1
+            && first.chars().any(|c| c.is_ascii_digit())
+            && mention_regex.is_match(first))
+}
+
+// Rows where the composer's page header leaked into the works table: the
+// title is just the composer's name, or every filled cell is the composer's
+// own lifespan, e.g. "(1685–1750)", on its own or after the name:
+// "Johann Sebastian Bach (1685–1750)"
+pub fn is_composer_echo(composition: &Composition, life: Option<LifeYears>) -> bool {
+    if fold_for_matching(&composition.title) == fold_for_matching(&composition.composer_name) {
+        return true;
+    }
+    let (Some(life), Some(raw_data)) = (life, &composition.raw_data) else {
+        return false;
+    };
+    let Some(birth) = life.birth else {
+        return false;
+    };
+
+    let lifespan_regex =
+        Regex::new(r"^\(?\s*(?:c\.\s*)?(\d{3,4})\s*-\s*(?:c\.\s*)?(\d{3,4})?\s*\)?$").unwrap();
+    let is_lifespan = |cell: &str| {
+        lifespan_regex
+            .captures(&normalize_dashes(cell))
+            .is_some_and(|caps| {
+                caps[1].parse() == Ok(birth)
+                    && caps.get(2).map(|death| death.as_str().parse().ok()) == life.death.map(Some)
+            })
+    };
+    let named_regex = Regex::new(r"^(.*?)\s*(\([^()]*\))$").unwrap();
+    let is_echo_cell = |cell: &str| {
+        is_lifespan(cell)
+            || named_regex.captures(cell).is_some_and(|caps| {
+                fold_for_matching(&caps[1]) == fold_for_matching(&composition.composer_name)
+                    && is_lifespan(&caps[2])
+            })
+    };
+    let mut filled = raw_data
+        .cell_data
+        .iter()
+        .filter(|cell| !cell.trim().is_empty())
+        .peekable();
+    filled.peek().is_some() && filled.all(|cell| is_echo_cell(cell.trim()))
+}
+
+// Keep only compositions with a work_url; returns how many were dropped
+pub fn retain_linked(compositions: &mut Vec<Composition>) -> usize {
+    let before = compositions.len();
+    compositions.retain(Composition::has_work_url);
+    before - compositions.len()
+}
+
+// As get_works, sending compositions to a sink shared with other composers
+pub async fn get_works_into(
+    composer_name: &str,
+    options: &WorksOptions,
+    sink: &SharedSink,
+) -> Result<usize> {
+    let base_wiki_url = options.base_url.as_str();
+    let composer_url = format!("{}/wiki/{}", base_wiki_url, wiki_slug(composer_name));
+
+    info!("Fetching works for {}", composer_name);
+
+    let client = build_client(&options.http)?;
+    let list_page = match &options.works_url {
+        Some(url) => {
+            let Some(html) = fetch_html(&client, url, &options.http).await? else {
+                return Err(WorksError::NotFound { url: url.clone() }.into());
+            };
+            info!("Using works list {}", url);
+            Some((url.clone(), html))
+        }
+        None => find_list_page(&client, base_wiki_url, composer_name, &options.http).await?,
+    };
+    let compositions_url = match &list_page {
+        Some((url, _)) => url.clone(),
+        None => list_page_candidates(base_wiki_url, composer_name).remove(0),
+    };
+
+    // The pages the rows come from, for ATTRIBUTION.json
+    let mut source_urls = Vec::new();
+    if list_page.is_some() {
+        source_urls.push(compositions_url.clone());
+    }
+
+    // Process all tables and work lists on the page
+    let mut all_raw_data = match &list_page {
+        Some((_, html)) => extract_list_page(
+            html,
+            composer_name,
+            &composer_url,
+            &compositions_url,
+            options,
+        ),
+        None => Vec::new(),
+    };
+
+    // A saved page has nothing to fall back to offline
+    let local = options
+        .works_url
+        .as_deref()
+        .is_some_and(|url| local_path(url).is_some());
+
+    // Where each continuation page's tables start, once merged
+    let mut page_starts = Vec::new();
+    if options.follow_continuations
+        && !local
+        && let Some((_, html)) = &list_page
+    {
+        let mut pages = vec![std::mem::take(&mut all_raw_data)];
+        for url in continuation_pages(html, &compositions_url, base_wiki_url) {
+            match fetch_html(&client, &url, &options.http).await {
+                Ok(Some(html)) => {
+                    info!("Merging continuation page {}", url);
+                    pages.push(extract_list_page(
+                        &html,
+                        composer_name,
+                        &composer_url,
+                        &url,
+                        options,
+                    ));
+                    source_urls.push(url);
+                }
+                Ok(None) => warn!("Continuation page {} not found", url),
+                Err(e) if e.downcast_ref::<BudgetExhausted>().is_some() => return Err(e),
+                Err(e) => warn!("Error fetching continuation page {}: {}", url, e),
+            }
+        }
+        (all_raw_data, page_starts) = merge_continuation_rows(pages);
+    }
+
+    if all_raw_data.is_empty() && list_page.is_some() && options.wikitext_fallback && !local {
+        let raw_url = format!("{}?action=raw", compositions_url);
+        info!(
+            "No rows in the list page HTML; trying its wikitext {}",
+            raw_url
+        );
+        match fetch_html(&client, &raw_url, &options.http).await {
+            Ok(Some(wikitext)) => {
+                all_raw_data = extract_raw_wikitext_data(
+                    &wikitext,
+                    composer_name,
+                    &composer_url,
+                    &compositions_url,
+                    base_wiki_url,
+                );
+            }
+            Ok(None) => warn!("No wikitext at {}", raw_url),
+            Err(e) if e.downcast_ref::<BudgetExhausted>().is_some() => return Err(e),
+            Err(e) => warn!("Error fetching wikitext {}: {}", raw_url, e),
+        }
+    }
+
+    // Fall back to the "Compositions by X" category when the list page has
+    // no usable tables or lists (or doesn't exist)
+    if all_raw_data.is_empty() && !local {
+        let category_url = format!(
+            "{}/wiki/Category:Compositions_by_{}",
+            base_wiki_url,
+            wiki_slug(composer_name)
+        );
+        info!(
+            "No work tables or lists found; trying category page {}",
+            category_url
+        );
+        all_raw_data = fetch_category_raw_data(
+            &client,
+            &category_url,
+            composer_name,
+            &composer_url,
+            base_wiki_url,
+            &options.http,
+        )
+        .await?;
+        if !all_raw_data.is_empty() {
+            source_urls.push(category_url);
+        }
+    }
+
+    if all_raw_data.is_empty() {
+        return Err(match &list_page {
+            Some((_, html)) => WorksError::NoContentExtracted {
+                url: compositions_url,
+                html_bytes: html.len(),
+            },
+            None => WorksError::NotFound {
+                url: compositions_url,
+            },
+        }
+        .into());
+    }
+
+    let field_map = options
+        .field_map
+        .as_deref()
+        .map(FieldMap::load)
+        .transpose()?;
+    if let Some(field_map) = &field_map {
+        let changed = apply_table_headers(
+            &mut all_raw_data,
+            &compositions_url,
+            &field_map.table_headers,
+        );
+        if changed > 0 {
+            info!("Applied supplied headers to {} headerless rows", changed);
+        }
+    }
+
+    if let Some(max_bytes) = options.max_snippet_bytes {
+        for raw_data in &mut all_raw_data {
+            truncate_snippet(&mut raw_data.raw_html_snippet, max_bytes);
+        }
+    }
+
+    // Stage 1: Extract and save raw data
+    let raw_filename = format!(
+        "raw-info-{}.json{}",
+        file_stem(composer_name),
+        if options.gzip { ".gz" } else { "" }
+    );
+    let (raw_tx, raw_rx) = mpsc::channel::<RawCompositionData>(100);
+
+    let raw_filename_clone = raw_filename.clone();
+    let strict = options.strict;
+    let raw_writer_handle =
+        tokio::spawn(
+            async move { raw_data_writer_task(raw_rx, &raw_filename_clone, strict).await },
+        );
+
+    for raw_data in &all_raw_data {
+        // Send to raw data writer
+        if let Err(e) = raw_tx.send(raw_data.clone()).await {
+            error!("Error sending raw data through channel: {}", e);
+        }
+    }
+
+    drop(raw_tx);
+    raw_writer_handle.await??;
+
+    info!(
+        "Saved {} raw composition records to {}",
+        all_raw_data.len(),
+        raw_filename
+    );
+
+    if options.debug_tables {
+        let dir = PathBuf::from(format!("debug-tables-{}", file_stem(composer_name)));
+        let paths = write_debug_tables(&dir, &all_raw_data).await?;
+        info!("Wrote {} table dumps to {}", paths.len(), dir.display());
+    }
+
+    let misaligned_tables = if options.strict {
+        find_misaligned_tables(&all_raw_data, &compositions_url)
+    } else {
+        Vec::new()
+    };
+
+    // Stage 2: Canonicalize and save processed compositions
+    let mut canonicalizer = FieldCanonicalizer::new();
+    if let Some(field_map) = &field_map {
+        canonicalizer = canonicalizer.with_field_map(field_map)?;
+    }
+    let canonicalizer = Arc::new(canonicalizer);
+
+    let life = options.composer_years.get(composer_name).copied();
+    let mut compositions = canonicalize_rows(
+        all_raw_data,
+        canonicalizer.as_ref(),
+        options.keep_total_rows,
+        life,
+    );
+    if !page_starts.is_empty() {
+        compositions = drop_repeats_across_pages(compositions, &page_starts);
+    }
+
+    if options.only_with_url {
+        let dropped = retain_linked(&mut compositions);
+        info!(
+            "Dropped {} compositions without a work_url for {}",
+            dropped, composer_name
+        );
+    }
+
+    if let Some(deep) = &options.deep {
+        let work_urls;
+        (compositions, work_urls) =
+            enrich_compositions(&client, &options.http, canonicalizer, compositions, deep).await?;
+        source_urls.extend(work_urls);
+    }
+
+    let summary_filename = format!("composer-summary-{}.json", file_stem(composer_name));
+    let mut summary = ComposerSummary::from_compositions(composer_name, &compositions);
+    summary.list_page = list_page.map(|(url, _)| url);
+    summary.misaligned_tables = misaligned_tables;
+    summary.save(&summary_filename).await?;
+
+    if let Err(e) = record_sources(ATTRIBUTION_FILENAME, composer_name, source_urls).await {
+        warn!("Error recording sources in {}: {}", ATTRIBUTION_FILENAME, e);
+    }
+
+    if options.index {
+        let index_filename = index_filename(composer_name);
+        write_works_index(&index_filename, &compositions).await?;
+        info!("Wrote works index {}", index_filename);
+    }
+
+    let mut canonicalized_count = 0;
+    for mut composition in compositions {
+        if options.slim {
+            composition.raw_data = None;
+        }
+        if !options.debug {
+            composition.field_provenance.clear();
+        }
+
+        if let Err(e) = sink.send(composition).await {
+            error!("Error sending composition through channel: {}", e);
+        } else {
+            canonicalized_count += 1;
+        }
+    }
+
+    info!(
+        "Canonicalized {} compositions; summary in {}",
+        canonicalized_count, summary_filename
+    );
+
+    Ok(canonicalized_count)
+}
+
+// Parse one raw-info line written by any earlier version of the scraper into
+// the current layout. `format_version` overrides the line's own
+// schema_version. Fields added since version 0 (section_heading,
+// table_caption) default via serde; version 0 rows missing the debugging
+// columns cell_links or raw_html_snippet get empty ones.
+fn migrate_raw_line(line: &str, format_version: Option<u32>) -> Result<RawCompositionData> {
+    let mut value: serde_json::Value = serde_json::from_str(line)?;
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("raw line is not a JSON object"))?;
+
+    let version = format_version
+        .or_else(|| {
+            object
+                .get("schema_version")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+        })
+        .unwrap_or(0);
+    if version > RAW_SCHEMA_VERSION {
+        anyhow::bail!(
+            "schema_version {} is newer than this build supports ({})",
+            version,
+            RAW_SCHEMA_VERSION
+        );
+    }
+
+    if version == 0 {
+        let cell_count = object
+            .get("cell_data")
+            .and_then(|cells| cells.as_array())
+            .map_or(0, Vec::len);
+        object
+            .entry("cell_links")
+            .or_insert_with(|| vec![serde_json::Value::Null; cell_count].into());
+        object
+            .entry("raw_html_snippet")
+            .or_insert_with(|| "".into());
+    }
+    object.insert("schema_version".to_string(), RAW_SCHEMA_VERSION.into());
+
+    Ok(serde_json::from_value(value)?)
+}
+
+// Canonicalize a raw-info file, skipping its first `skip` lines so a crashed
+// run can resume where it stopped. Lines from older schema versions are
+// migrated first; lines that still fail to parse are logged and skipped.
+pub async fn reprocess_raw_data(
+    raw_filename: &str,
+    skip: usize,
+    format_version: Option<u32>,
+) -> Result<Vec<Composition>> {
+    let mut lines = open_lines(raw_filename).await?;
+    let mut raw_rows = Vec::new();
+    let mut line_number = 0;
+
+    while let Some(line) = lines.next_line().await? {
+        line_number += 1;
+        if line_number <= skip {
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        match migrate_raw_line(&line, format_version) {
+            Ok(raw_data) => raw_rows.push(raw_data),
+            Err(e) => warn!("Skipping {} line {}: {}", raw_filename, line_number, e),
+        }
+    }
+
+    // The same filters as a scrape; composer years aren't known here, so
+    // only rows naming the composer count as echoes
+    Ok(canonicalize_rows(
+        raw_rows,
+        &FieldCanonicalizer::new(),
+        false,
+        None,
+    ))
+}
+
+#[derive(Clone, Debug)]
+pub struct ReprocessOptions {
+    pub output: String,
+    // Raw lines already canonicalized by an earlier, interrupted run
+    pub skip: usize,
+    // Add to the existing output instead of starting it fresh
+    pub append: bool,
+    // Treat every raw line as this schema version instead of its own
+    pub format_version: Option<u32>,
+    // Keep field_provenance in the output
+    pub debug: bool,
+}
+
+// Stage 2 on its own: rebuild compositions from a raw-info file without
+// re-scraping. Returns the number of compositions written.
+pub async fn reprocess_to_file(raw_filename: &str, options: &ReprocessOptions) -> Result<usize> {
+    let mut compositions =
+        reprocess_raw_data(raw_filename, options.skip, options.format_version).await?;
+
+    let mut writer = create_writer(&options.output, options.append).await?;
+    for composition in &mut compositions {
+        if !options.debug {
+            composition.field_provenance.clear();
+        }
+        write_line(&mut writer, composition).await?;
+    }
+    writer.shutdown().await?;
+
+    info!(
+        "Reprocessed {} compositions from {} into {}",
+        compositions.len(),
+        raw_filename,
+        options.output
+    );
+    Ok(compositions.len())
+}
+
+#[derive(Clone, Debug)]
+pub struct ReprocessAllOptions {
+    // Write every composer's compositions to this one file instead of
+    // compositions-<name>.json beside each raw-info-<name>.json
+    pub combined_output: Option<String>,
+    // Raw files reprocessed at once
+    pub concurrency: usize,
+    pub format_version: Option<u32>,
+}
+
+impl Default for ReprocessAllOptions {
+    fn default() -> Self {
+        Self {
+            combined_output: None,
+            concurrency: 4,
+            format_version: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReprocessTotals {
+    pub files: usize,
+    pub compositions: usize,
+    // Raw files that could not be reprocessed, with the error
+    pub failed: Vec<(String, String)>,
+}
+
+// raw-info-<name>.json and raw-info-<name>.json.gz in `dir`, sorted by name
+async fn raw_files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("raw-info-") && (name.ends_with(".json") || name.ends_with(".json.gz"))
+        {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+// compositions-<name>.json next to raw-info-<name>.json. Only the file name
+// is rewritten, so a directory that happens to contain "raw-info-" is kept.
+fn compositions_path_for(raw_file: &Path) -> PathBuf {
+    let name = raw_file
+        .file_name()
+        .map(|name| {
+            name.to_string_lossy()
+                .replacen("raw-info-", "compositions-", 1)
+        })
+        .unwrap_or_default();
+    raw_file.with_file_name(name)
+}
+
+// reprocess_to_file for every raw-info file in `dir`, e.g. after a schema or
+// canonicalization change. Files are reprocessed concurrently; each gets its
+// own compositions-<name>.json (gzipped if the raw file is) unless
+// `combined_output` is set, which collects them in file-name order. A file
+// that fails is recorded in the totals and doesn't stop the others.
+pub async fn reprocess_all(dir: &str, options: &ReprocessAllOptions) -> Result<ReprocessTotals> {
+    let raw_files = raw_files_in(Path::new(dir)).await?;
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(options.concurrency.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (index, raw_path) in raw_files.iter().enumerate() {
+        let raw_file = raw_path.to_string_lossy().to_string();
+        let output = compositions_path_for(raw_path)
+            .to_string_lossy()
+            .to_string();
+        let semaphore = semaphore.clone();
+        let format_version = options.format_version;
+        let per_file_output = options.combined_output.is_none();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = async {
+                let mut compositions = reprocess_raw_data(&raw_file, 0, format_version).await?;
+                for composition in &mut compositions {
+                    composition.field_provenance.clear();
+                }
+                if !per_file_output {
+                    return Ok((compositions.len(), compositions));
+                }
+                let mut writer = create_writer(&output, false).await?;
+                for composition in &compositions {
+                    write_line(&mut writer, composition).await?;
+                }
+                writer.shutdown().await?;
+                info!(
+                    "Reprocessed {} compositions from {} into {}",
+                    compositions.len(),
+                    raw_file,
+                    output
+                );
+                Ok::<_, anyhow::Error>((compositions.len(), Vec::new()))
+            }
+            .await;
+            (index, raw_file, result)
+        });
+    }
+
+    let mut totals = ReprocessTotals::default();
+    let mut combined: Vec<Vec<Composition>> = vec![Vec::new(); raw_files.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, raw_file, result) = joined?;
+        match result {
+            Ok((count, compositions)) => {
+                totals.files += 1;
+                totals.compositions += count;
+                combined[index] = compositions;
+            }
+            Err(e) => {
+                warn!("Error reprocessing {}: {}", raw_file, e);
+                totals.failed.push((raw_file, e.to_string()));
+            }
+        }
+    }
+    totals.failed.sort();
+
+    if let Some(output) = &options.combined_output {
+        let mut writer = create_writer(output, false).await?;
+        for composition in combined.iter().flatten() {
+            write_line(&mut writer, composition).await?;
+        }
+        writer.shutdown().await?;
+        info!(
+            "Reprocessed {} compositions from {} files into {}",
+            totals.compositions, totals.files, output
+        );
+    }
+
+    Ok(totals)
+}
+
+// Streams already-canonical compositions back out of a JSONL file such as
+// compositions.json or compositions.json.gz. Malformed lines are logged and
+// skipped.
+pub struct CompositionReader {
+    path: String,
+    lines: JsonlLines,
+    line_number: usize,
+}
+
+impl CompositionReader {
+    pub async fn next(&mut self) -> Option<Result<Composition>> {
+        loop {
+            let line = match self.lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Composition>(&line) {
+                Ok(composition) => return Some(Ok(composition)),
+                Err(e) => warn!(
+                    "Skipping malformed line {} in {}: {}",
+                    self.line_number, self.path, e
+                ),
+            }
+        }
+    }
+}
+
+pub async fn read_compositions(path: &str) -> Result<CompositionReader> {
+    Ok(CompositionReader {
+        path: path.to_string(),
+        lines: open_lines(path).await?,
+        line_number: 0,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // A table row by "Test Composer"; other modules' tests build their
+    // fixtures from it too
+    pub(crate) fn raw_row(headers: &[&str], cells: &[&str]) -> RawCompositionData {
+        RawCompositionData {
+            schema_version: RAW_SCHEMA_VERSION,
+            composer_name: "Test Composer".to_string(),
+            composer_url: "https://en.wikipedia.org/wiki/Test_Composer".to_string(),
+            source_url: "https://en.wikipedia.org/wiki/List_of_compositions_by_Test_Composer"
+                .to_string(),
+            table_index: 0,
+            row_index: 0,
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            cell_data: cells.iter().map(|c| c.to_string()).collect(),
+            cell_links: vec![None; cells.len()],
+            raw_html_snippet: String::new(),
+            section_heading: None,
+            table_caption: None,
+            table_classes: Vec::new(),
+            table_id: None,
+            was_collapsed: false,
+        }
+    }
+
+    #[test]
+    fn keeps_every_opus_when_two_columns_map_to_opus() {
+        let raw = raw_row(
+            &["Title", "Opus", "Op. (revised)"],
+            &["Three Pieces", "Op. 11", "Op. 11a"],
+        );
+
+        let composition = canonicalize_raw_data(raw);
+
+        assert_eq!(composition.opus.as_deref(), Some("11"));
+        assert_eq!(composition.opus_all, vec!["11", "11a"]);
+        assert!(composition.key_all.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_compositions_skips_malformed_lines() {
+        let first = canonicalize_raw_data(raw_row(&["Title"], &["Symphony No. 1"]));
+        let second = canonicalize_raw_data(raw_row(&["Title"], &["Symphony No. 2"]));
+        let contents = format!(
+            "{}\n{{\"title\": \"truncated\n{}\n",
+            serde_json::to_string(&first).unwrap(),
+            serde_json::to_string(&second).unwrap()
+        );
+        let path =
+            std::env::temp_dir().join(format!("read-compositions-{}.json", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        let mut reader = read_compositions(path.to_str().unwrap()).await.unwrap();
+        let mut titles = Vec::new();
+        while let Some(composition) = reader.next().await {
+            titles.push(composition.unwrap().title);
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(titles, vec!["Symphony No. 1", "Symphony No. 2"]);
+    }
+
+    #[test]
+    fn extracts_works_from_bulleted_lists_under_work_headings() {
+        let html = r#"
+            <html><body>
+            <h2>Biography</h2>
+            <ul><li><a href="/wiki/Vienna" title="Vienna">Vienna</a> (1790)</li></ul>
+            <h2>Selected works</h2>
+            <h3>Operas</h3>
+            <ul>
+              <li><a href="/wiki/Le_nozze_di_Figaro" title="Le nozze di Figaro">Le nozze di Figaro</a> (1786)</li>
+              <li>Lost Serenade in D (c. 1775)</li>
+            </ul>
+            <h2>References</h2>
+            <ul><li>Some Book (1990)</li></ul>
+            </body></html>
+        "#;
+        let document = Html::parse_document(html);
+
+        let raw_data = extract_raw_data(&document, "Test Composer", "composer_url", "page_url");
+        let compositions: Vec<Composition> =
+            raw_data.into_iter().map(canonicalize_raw_data).collect();
+
+        assert_eq!(compositions.len(), 2);
+        assert_eq!(compositions[0].title, "Le nozze di Figaro");
+        assert_eq!(compositions[0].year.as_deref(), Some("1786"));
+        assert_eq!(
+            compositions[0].work_url.as_deref(),
+            Some("https://en.wikipedia.org/wiki/Le_nozze_di_Figaro")
+        );
+        assert_eq!(compositions[1].title, "Lost Serenade in D");
+        assert_eq!(compositions[1].year.as_deref(), Some("1775"));
+        assert_eq!(
+            compositions[1]
+                .raw_data
+                .as_ref()
+                .unwrap()
+                .section_heading
+                .as_deref(),
+            Some("Operas")
+        );
+    }
+
+    #[test]
+    fn finds_additional_info_under_varied_headers() {
+        for header in ["Premiere", "Première", "Premiere venue", "Premeire"] {
+            let composition =
+                canonicalize_raw_data(raw_row(&["Title", header], &["Jeux", "15 May 1913"]));
+            assert_eq!(
+                composition.get_field_fuzzy("premiere"),
+                Some("15 May 1913"),
+                "header {header:?}"
+            );
+        }
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Librettist", "Comments"],
+            &["Oedipus rex", "Jean Cocteau", "Opera-oratorio"],
+        ));
+        assert_eq!(
+            composition.get_field_fuzzy("libretto"),
+            Some("Jean Cocteau")
+        );
+        assert_eq!(composition.get_field_fuzzy("notes"), Some("Opera-oratorio"));
+        assert_eq!(composition.get_field_fuzzy("premiere"), None);
+    }
+
+    #[test]
+    fn derives_a_numeric_year_and_qualifier() {
+        assert_eq!(
+            parse_year_numeric("1804"),
+            Some((1804, YearQualifier::Exact))
+        );
+        assert_eq!(
+            parse_year_numeric("c. 1804"),
+            Some((1804, YearQualifier::Approximate))
+        );
+        assert_eq!(
+            parse_year_numeric("circa 1720"),
+            Some((1720, YearQualifier::Approximate))
+        );
+        assert_eq!(
+            parse_year_numeric("1791?"),
+            Some((1791, YearQualifier::Approximate))
+        );
+        assert_eq!(
+            parse_year_numeric("1804–06"),
+            Some((1804, YearQualifier::RangeStart))
+        );
+        assert_eq!(
+            parse_year_numeric("1804 - 1808"),
+            Some((1804, YearQualifier::RangeStart))
+        );
+        assert_eq!(
+            parse_year_numeric("ca. 1830–1835"),
+            Some((1830, YearQualifier::Approximate))
+        );
+        assert_eq!(
+            parse_year_numeric("first performed in 1913 in Paris"),
+            Some((1913, YearQualifier::Exact))
+        );
+        assert_eq!(parse_year_numeric("unknown"), None);
+
+        let composition =
+            canonicalize_raw_data(raw_row(&["Title", "Year"], &["Eroica", "c. 1803–04"]));
+        assert_eq!(composition.year.as_deref(), Some("1803"));
+        assert_eq!(composition.year_numeric, Some(1803));
+        assert_eq!(composition.year_qualifier, Some(YearQualifier::Approximate));
+
+        let undated = canonicalize_raw_data(raw_row(&["Title"], &["Fragment"]));
+        assert_eq!(undated.year_numeric, None);
+        assert_eq!(undated.year_qualifier, None);
+    }
+
+    #[test]
+    fn reads_durations_in_seconds() {
+        for (text, seconds) in [
+            ("25 min", 1500),
+            ("25 mins", 1500),
+            ("25 minutes", 1500),
+            ("25'", 1500),
+            ("25′", 1500),
+            ("25'30\"", 1530),
+            ("c. 12 min", 720),
+            ("1 h 20 min", 4800),
+            ("2 hours", 7200),
+            ("1.5 hours", 5400),
+            ("20–25 minutes", 1200),
+            ("25:30", 1530),
+            ("1:02:15", 3735),
+            // Footnoted
+            ("25 min[3]", 1500),
+            ("25[a] minutes", 1500),
+            ("12:05[citation needed]", 725),
+            // Thousands-separated
+            ("1,234 seconds", 1234),
+            ("1,234 s", 1234),
+            ("1,200 sec[2]", 1200),
+        ] {
+            assert_eq!(parse_duration_seconds(text), Some(seconds), "{}", text);
+        }
+        for text in ["", "25", "unknown", "5 movements", "Op. 10"] {
+            assert_eq!(parse_duration_seconds(text), None, "{}", text);
+        }
+
+        let composition =
+            canonicalize_raw_data(raw_row(&["Title", "Duration"], &["Boléro", "c. 15 min[4]"]));
+        assert_eq!(composition.duration.as_deref(), Some("c. 15 min[4]"));
+        assert_eq!(composition.duration_seconds, Some(900));
+    }
+
+    #[test]
+    fn labeled_rows_score_higher_confidence_than_fallbacks() {
+        let labeled = canonicalize_raw_data(raw_row(
+            &["Title", "Year", "Key", "Genre"],
+            &["Symphony No. 40", "1788", "G minor", "Symphony"],
+        ));
+        assert_eq!(labeled.confidence, 1.0);
+
+        // No title column: the title comes from the first linked cell
+        let mut raw = raw_row(&["Nr.", "Notes"], &["Eine kleine Nachtmusik", "Serenade"]);
+        raw.cell_links = vec![
+            Some("https://en.wikipedia.org/wiki/Eine_kleine_Nachtmusik".to_string()),
+            None,
+        ];
+        let fallback = canonicalize_raw_data(raw);
+        assert_eq!(fallback.title, "Eine kleine Nachtmusik");
+        assert_eq!(fallback.confidence, 0.125);
+
+        // Key read from the title rather than a key column
+        let inferred = canonicalize_raw_data(raw_row(
+            &["Title", "Year", "Genre"],
+            &["Symphony in G minor", "1788", "Symphony"],
+        ));
+        assert_eq!(inferred.confidence, 0.875);
+        assert!(labeled.confidence > inferred.confidence);
+        assert!(inferred.confidence > fallback.confidence);
+    }
+
+    #[test]
+    fn reads_a_valid_iswc_from_any_cell() {
+        assert_eq!(
+            find_iswc("ISWC T-034.524.680-1[2]").as_deref(),
+            Some("T-034.524.680-1")
+        );
+        // Wrong check digit, missing digits, no separators
+        for text in ["T-034.524.680-2", "T-34.524.680-1", "T0345246801", ""] {
+            assert_eq!(find_iswc(text), None, "{}", text);
+        }
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year", "Codes"],
+            &["Adagio for Strings", "1936", "T-010.140.233-8"],
+        ));
+        assert_eq!(composition.iswc.as_deref(), Some("T-010.140.233-8"));
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year", "Codes"],
+            &["Adagio for Strings", "1936", "T-010.140.233-1"],
+        ));
+        assert_eq!(composition.iswc, None);
+    }
+
+    #[test]
+    fn marks_uncertain_years_as_approximate() {
+        for (cell, year) in [
+            ("1785?", 1785),
+            ("1785 ?", 1785),
+            ("ca. 1720", 1720),
+            ("c.1720", 1720),
+            ("circa 1720", 1720),
+            ("probably 1601", 1601),
+            ("Probably in 1601", 1601),
+            ("likely 1601", 1601),
+        ] {
+            let composition = canonicalize_raw_data(raw_row(&["Title", "Year"], &["Work", cell]));
+            assert_eq!(
+                composition.year.as_deref(),
+                Some(year.to_string().as_str()),
+                "{}",
+                cell
+            );
+            assert_eq!(composition.year_numeric, Some(year), "{}", cell);
+            assert!(composition.year_approximate, "{}", cell);
+        }
+
+        let exact = canonicalize_raw_data(raw_row(&["Title", "Year"], &["Work", "1785"]));
+        assert!(!exact.year_approximate);
+        // A marker counts only as a word of its own, just before the year
+        for cell in ["unlikely 1785", "likely revised after 1785"] {
+            let composition = canonicalize_raw_data(raw_row(&["Title", "Year"], &["Work", cell]));
+            assert!(!composition.year_approximate, "{}", cell);
+        }
+    }
+
+    #[test]
+    fn records_the_header_behind_each_canonical_field() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Opus", "Work", "Key", "Date", "Scoring", "Remarks"],
+            &[
+                "Op. 9 No. 2",
+                "Nocturne in E-flat major",
+                "E♭ major",
+                "1830–32",
+                "piano",
+                "Dedicated to Marie Pleyel",
+            ],
+        ));
+        assert_eq!(
+            composition.field_provenance,
+            HashMap::from(
+                [
+                    ("opus", "Opus"),
+                    ("title", "Work"),
+                    ("key", "Key"),
+                    ("year", "Date"),
+                    ("instrumentation", "Scoring"),
+                ]
+                .map(|(field, header)| (field.to_string(), header.to_string()))
+            )
+        );
+
+        // A year picked out of another column names that column
+        let scraped = canonicalize_raw_data(raw_row(
+            &["Title", "Remarks"],
+            &["Ballade No. 1", "Published 1836"],
+        ));
+        assert_eq!(
+            scraped.field_provenance.get("year").map(String::as_str),
+            Some("Remarks")
+        );
+    }
+
+    #[test]
+    fn splits_combined_composition_and_publication_dates() {
+        let composition =
+            |date: &str| canonicalize_raw_data(raw_row(&["Title", "Date"], &["Eroica", date]));
+
+        let combined = composition("1804 (comp.) / 1806 (pub.)");
+        assert_eq!(combined.year.as_deref(), Some("1804"));
+        assert_eq!(combined.composition_year.as_deref(), Some("1804"));
+        assert_eq!(combined.publication_year.as_deref(), Some("1806"));
+        assert_eq!(combined.year_confidence, Some(YearConfidence::High));
+
+        // Publication first still keeps year as the composition year
+        let reversed = composition("published 1806; composed 1803–04");
+        assert_eq!(reversed.year.as_deref(), Some("1803"));
+        assert_eq!(reversed.publication_year.as_deref(), Some("1806"));
+
+        let single = composition("1804");
+        assert_eq!(single.year.as_deref(), Some("1804"));
+        assert_eq!(single.composition_year, None);
+        assert_eq!(single.publication_year, None);
+
+        // Only one kind marked
+        assert_eq!(composition("1804 (comp.)").composition_year, None);
+    }
+
+    #[test]
+    fn year_confidence_depends_on_where_the_year_came_from() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year", "Notes"],
+            &["Octet", "1923", "Revised 1952"],
+        ));
+        assert_eq!(composition.year.as_deref(), Some("1923"));
+        assert_eq!(composition.year_confidence, Some(YearConfidence::High));
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year", "Notes"],
+            &["Octet", "unknown", "Premiered 1923, Paris"],
+        ));
+        assert_eq!(composition.year.as_deref(), Some("1923"));
+        assert_eq!(composition.year_confidence, Some(YearConfidence::Low));
+
+        let composition = canonicalize_raw_data(raw_row(&["Title"], &["Octet"]));
+        assert_eq!(composition.year, None);
+        assert_eq!(composition.year_confidence, None);
+    }
+
+    #[test]
+    fn hidden_sort_keys_are_left_out_of_cell_text() {
+        let html = r#"
+            <html><body>
+            <table class="wikitable sortable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr>
+                <td><span style="display: none">001</span><a href="/wiki/Symphony_No._1">Symphony No. 1</a></td>
+                <td><span class="sortkey">1876-11-04</span>1876</td>
+              </tr>
+            </table>
+            </body></html>
+        "#;
+        let document = Html::parse_document(html);
+
+        let raw_data = extract_raw_data(&document, "Test Composer", "composer_url", "page_url");
+
+        assert_eq!(raw_data[0].cell_data, vec!["Symphony No. 1", "1876"]);
+    }
+
+    #[tokio::test]
+    async fn reprocesses_version_0_raw_files() {
+        let path = std::env::temp_dir().join(format!("raw-v0-{}.json", std::process::id()));
+        // As written before schema_version, section_heading and table_caption
+        let v0_lines = [
+            r#"{"composer_name":"Igor Stravinsky","composer_url":"https://en.wikipedia.org/wiki/Igor_Stravinsky","source_url":"https://en.wikipedia.org/wiki/The_Firebird","table_index":0,"row_index":0,"headers":["Title","Year"],"cell_data":["The Firebird","1910"],"cell_links":["https://en.wikipedia.org/wiki/The_Firebird",null],"raw_html_snippet":"<tr></tr>"}"#,
+            r#"{"composer_name":"Igor Stravinsky","composer_url":"https://en.wikipedia.org/wiki/Igor_Stravinsky","source_url":"page_url","table_index":0,"row_index":1,"headers":["Title","Year"],"cell_data":["Petrushka","1911"]}"#,
+            r#"{"schema_version":99,"composer_name":"Igor Stravinsky"}"#,
+        ];
+        std::fs::write(&path, v0_lines.join("\n")).unwrap();
+
+        let compositions = reprocess_raw_data(path.to_str().unwrap(), 0, None)
+            .await
+            .unwrap();
+
+        assert_eq!(compositions.len(), 2);
+        assert_eq!(compositions[0].title, "The Firebird");
+        assert_eq!(compositions[1].title, "Petrushka");
+        assert_eq!(compositions[1].year.as_deref(), Some("1911"));
+        let raw = compositions[1].raw_data.as_ref().unwrap();
+        assert_eq!(raw.schema_version, RAW_SCHEMA_VERSION);
+        assert_eq!(raw.cell_links, vec![None, None]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn key_is_taken_from_the_title_when_no_key_column_has_one() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &["Nocturne in E-flat major, Op. 9 No. 2", "1832"],
+        ));
+        assert_eq!(composition.key.as_deref(), Some("E-flat major"));
+        assert_eq!(composition.key_all, vec!["E-flat major"]);
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Key"],
+            &["Nocturne in E-flat major", "E♭ major"],
+        ));
+        assert_eq!(composition.key.as_deref(), Some("E♭ major"));
+    }
+
+    #[test]
+    fn key_is_found_for_every_tonic_and_accidental_spelling() {
+        let canonicalizer = FieldCanonicalizer::new();
+        let cases = [
+            ("Mass in C major", "C major"),
+            ("String Quartet in C♯ minor", "C♯ minor"),
+            ("Prelude in D-flat major", "D-flat major"),
+            ("Piano Concerto in D minor", "D minor"),
+            ("Nocturne in E-flat major", "E-flat major"),
+            ("Sonata in E♭ Major", "E♭ Major"),
+            ("Impromptu in F sharp minor", "F sharp minor"),
+            ("Romance in F#minor", "F#minor"),
+            ("Ballade in G minor", "G minor"),
+            ("Étude in A♭ major", "A♭ major"),
+            ("Scherzo in B–flat Minor", "B-flat Minor"),
+            ("Trio in Bb major", "Bb major"),
+        ];
+        for (title, key) in cases {
+            assert_eq!(
+                canonicalizer.extract_key_from_text(title).as_deref(),
+                Some(key),
+                "{}",
+                title
+            );
+        }
+        assert_eq!(
+            canonicalizer.extract_key_from_text("a minor revision"),
+            None
+        );
+        assert_eq!(canonicalizer.extract_key_from_text("Suite No. 2"), None);
+    }
+
+    #[test]
+    fn opus_is_taken_from_the_title_but_not_from_catalogue_numbers() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &["Piano Sonata No. 14, Op. 27 No. 2", "1801"],
+        ));
+        assert_eq!(composition.opus.as_deref(), Some("27"));
+        assert_eq!(composition.opus_all, vec!["27"]);
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &["Cello Suite No. 1 in G major, BWV 1007", "1720"],
+        ));
+        assert_eq!(composition.opus, None);
+        assert!(composition.opus_all.is_empty());
+    }
+
+    #[test]
+    fn composition_url_indicators_match_whole_path_tokens() {
+        for url in [
+            "https://en.wikipedia.org/wiki/Symphony_No._5_(Beethoven)",
+            "https://en.wikipedia.org/wiki/Piano_Sonatas,_Op._2_(Beethoven)",
+            "https://en.wikipedia.org/wiki/Goldberg_Variations",
+            "https://en.wikipedia.org/wiki/Cello_Suites_(Bach)",
+        ] {
+            assert!(is_likely_composition_url(url), "{url}");
+        }
+        for url in [
+            "https://en.wikipedia.org/wiki/Bavaria",
+            "https://en.wikipedia.org/wiki/Massachusetts",
+            "https://en.wikipedia.org/wiki/Allied_Occupation",
+            "https://en.wikipedia.org/wiki/Network_Rail",
+            "https://en.wikipedia.org/wiki/Songhai_Empire",
+            "https://en.wikipedia.org/wiki/Opera_house",
+            "https://en.wikipedia.org/wiki/Opera house",
+            "https://en.wikipedia.org/wiki/Royal_Opera_House",
+            "https://en.wikipedia.org/wiki/Carnegie_Hall_(concert_hall)",
+        ] {
+            assert!(!is_likely_composition_url(url), "{url}");
+        }
+    }
+
+    #[test]
+    fn non_composition_indicators_match_whole_path_tokens() {
+        for url in [
+            "https://en.wikipedia.org/wiki/Don_Giovanni_(opera)",
+            "https://en.wikipedia.org/wiki/Operetta",
+            "https://en.wikipedia.org/wiki/Piano_Sonata_No._14_(Beethoven)",
+        ] {
+            assert!(!is_likely_non_composition_url(url), "{url}");
+        }
+        for url in [
+            "https://en.wikipedia.org/wiki/Category:Compositions_by_Franz_Liszt",
+            "https://en.wikipedia.org/wiki/List_of_operas_by_Handel",
+            "https://en.wikipedia.org/wiki/Romantic_era",
+            "https://en.wikipedia.org/wiki/Opera_house",
+            "https://en.wikipedia.org/wiki/Opera house",
+        ] {
+            assert!(is_likely_non_composition_url(url), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_a_saved_page_from_a_file_url() {
+        let path = std::env::temp_dir().join(format!("saved-page-{}.html", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"<html><body>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Gymnopédie No. 1</td><td>1888</td></tr>
+            </table>
+            </body></html>"#,
+        )
+        .unwrap();
+        let url = format!("file://{}", path.to_str().unwrap());
+        let http = HttpOptions::default();
+        let client = build_client(&http).unwrap();
+
+        let html = fetch_html(&client, &url, &http).await.unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let raw = extract_raw_data(
+            &Html::parse_document(&html),
+            "Erik Satie",
+            "composer_url",
+            &url,
+        );
+
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw[0].cell_data, vec!["Gymnopédie No. 1", "1888"]);
+        assert_eq!(raw[0].source_url, url);
+        assert!(fetch_html(&client, &url, &http).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn tries_list_page_templates_until_one_exists() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // Anything not mounted is a 404
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wiki/List_of_compositions_by_John_Adams_(composer)"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+            .mount(&server)
+            .await;
+
+        let http = HttpOptions::default();
+        let (url, _) = find_list_page(
+            &build_client(&http).unwrap(),
+            &server.uri(),
+            "John Adams",
+            &http,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert!(url.ends_with("/wiki/List_of_compositions_by_John_Adams_(composer)"));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn preflight_counts_the_rows_of_work_tables() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let html = r#"<html><body>
+            <h2>Orchestral</h2>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Symphony No. 1</td><td>1876</td></tr>
+              <tr><td>Symphony No. 2</td><td>1877</td></tr>
+              <tr><td>Academic Festival Overture</td><td>1880</td></tr>
+            </table>
+            <h2>Chamber</h2>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Clarinet Quintet</td><td>1891</td></tr>
+              <tr><td>Horn Trio</td><td>1865</td></tr>
+            </table>
+            </body></html>"#;
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wiki/List_of_compositions_by_Johannes_Brahms"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(html))
+            .mount(&server)
+            .await;
+
+        let options = WorksOptions {
+            base_url: server.uri(),
+            ..Default::default()
+        };
+        assert_eq!(preflight("Johannes Brahms", &options).await.unwrap(), 5);
+
+        let missing = preflight("Nobody", &options).await.unwrap_err();
+        assert!(matches!(
+            missing.downcast_ref::<WorksError>(),
+            Some(WorksError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn catalog_sort_keys_order_numerically_within_a_system() {
+        let mut catalog_numbers = vec![
+            "BWV 1007",
+            "BWV 10",
+            "BWV 565",
+            "BWV 1",
+            "BWV 1007a",
+            "Hob. XVI:52",
+            "Hob. IX:3",
+            "K. 331",
+            "K. 40",
+        ];
+        catalog_numbers.sort_by_key(|number| catalog_sort_key(number).unwrap());
+        assert_eq!(
+            catalog_numbers,
+            vec![
+                "BWV 1",
+                "BWV 10",
+                "BWV 565",
+                "BWV 1007",
+                "BWV 1007a",
+                "Hob. IX:3",
+                "Hob. XVI:52",
+                "K. 40",
+                "K. 331",
+            ]
+        );
+        assert_eq!(catalog_sort_key("K. 331").as_deref(), Some("K 000331"));
+        assert_eq!(catalog_sort_key("K 331").as_deref(), Some("K 000331"));
+        assert_eq!(catalog_sort_key("1007").as_deref(), Some("001007"));
+        assert_eq!(catalog_sort_key("unknown"), None);
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Catalogue"],
+            &["Cello Suite No. 1", "BWV 1007"],
+        ));
+        assert_eq!(composition.catalog_sort_key.as_deref(), Some("BWV 001007"));
+    }
+
+    #[test]
+    fn flags_tables_whose_rows_are_wider_or_narrower_than_their_headers() {
+        let headers = ["Title", "Year", "Key", "Scoring"];
+        let aligned = raw_row(
+            &headers,
+            &["Symphony No. 1", "1876", "C minor", "orchestra"],
+        );
+        let short_by_one = raw_row(&headers, &["Tragic Overture", "1880", "D minor"]);
+        let mut shifted = raw_row(&headers, &["1883"]);
+        shifted.table_index = 2;
+        shifted.row_index = 4;
+        shifted.source_url = "https://en.wikipedia.org/wiki/Symphony_No._3_(Brahms)".to_string();
+        // Linked rows each carry their own work URL; still one table
+        let mut also_shifted = raw_row(&headers, &["1885", "E minor", "orchestra", "", "", ""]);
+        also_shifted.table_index = 2;
+        also_shifted.row_index = 5;
+        also_shifted.source_url =
+            "https://en.wikipedia.org/wiki/Symphony_No._4_(Brahms)".to_string();
+        let mut headerless = raw_row(&[], &["Lullaby", "1868"]);
+        headerless.table_index = 3;
+
+        let page_url = "https://en.wikipedia.org/wiki/List_of_compositions_by_Johannes_Brahms";
+        let tables = find_misaligned_tables(
+            &[aligned, short_by_one, shifted, also_shifted, headerless],
+            page_url,
+        );
+        assert_eq!(
+            tables,
+            vec![MisalignedTable {
+                source_url: page_url.to_string(),
+                table_index: 2,
+                headers: 4,
+                misaligned_rows: 2,
+                first_row_index: 4,
+                first_row_cells: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn catalog_number_and_number_are_read_from_the_work_url() {
+        let mut raw = raw_row(&["Title", "Year"], &["Cello Suite No. 1", "1720"]);
+        raw.cell_links[0] = Some("https://en.wikipedia.org/wiki/BWV_1007".to_string());
+        let composition = canonicalize_raw_data(raw);
+        assert_eq!(composition.catalog_number.as_deref(), Some("BWV 1007"));
+        assert_eq!(composition.catalog_number_all, vec!["BWV 1007"]);
+        assert_eq!(composition.opus, None);
+
+        let mut raw = raw_row(&["Title", "Year"], &["Fifth Symphony", "1808"]);
+        raw.cell_links[0] =
+            Some("https://en.wikipedia.org/wiki/Symphony_No._5_(Beethoven)".to_string());
+        let composition = canonicalize_raw_data(raw);
+        assert_eq!(composition.catalog_number, None);
+        assert_eq!(
+            composition
+                .additional_info
+                .get("number")
+                .map(String::as_str),
+            Some("5")
+        );
+
+        // A catalogue column always wins over the URL
+        let mut raw = raw_row(
+            &["Title", "Catalogue"],
+            &["Eine kleine Nachtmusik", "K. 525"],
+        );
+        raw.cell_links[0] = Some("https://en.wikipedia.org/wiki/K._526".to_string());
+        let composition = canonicalize_raw_data(raw);
+        assert_eq!(composition.catalog_number.as_deref(), Some("K. 525"));
+
+        assert_eq!(
+            numbers_from_work_url("https://en.wikipedia.org/wiki/Piano_Sonata_No._14_(Op._27)"),
+            WorkUrlNumbers {
+                catalog_number: None,
+                opus: Some("27".to_string()),
+                number: Some("14".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn detects_arrangements_and_their_sources() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &[
+                "Toccata and Fugue in D minor (Arr. of Bach BWV 565)",
+                "1899",
+            ],
+        ));
+        assert!(composition.is_arrangement);
+        assert_eq!(composition.arrangement_of.as_deref(), Some("Bach BWV 565"));
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Notes"],
+            &[
+                "Chaconne",
+                "Transcription of Bach's Partita No. 2, for piano",
+            ],
+        ));
+        assert!(composition.is_arrangement);
+        assert_eq!(
+            composition.arrangement_of.as_deref(),
+            Some("Bach's Partita No. 2, for piano")
+        );
+
+        let composition =
+            canonicalize_raw_data(raw_row(&["Title"], &["Pulcinella (after Pergolesi)"]));
+        assert_eq!(composition.arrangement_of.as_deref(), Some("Pergolesi"));
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Notes"],
+            &["Boléro", "Composed after 1927"],
+        ));
+        assert!(!composition.is_arrangement);
+        assert_eq!(composition.arrangement_of, None);
+
+        // A note about a later arrangement doesn't make the work one
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Notes"],
+            &[
+                "Pictures at an Exhibition",
+                "orchestral arrangement by Ravel (1922)",
+            ],
+        ));
+        assert!(!composition.is_arrangement);
+    }
+
+    // Writes one file at an explicit path, the way JsonlFileSink writes
+    // compositions.json, without touching the working directory
+    struct TempFileSink {
+        filename: String,
+        sender: mpsc::Sender<Composition>,
+        writer: JoinHandle<Result<()>>,
+    }
+
+    impl TempFileSink {
+        fn new(filename: String) -> Self {
+            let (sender, rx) = mpsc::channel::<Composition>(100);
+            let path = filename.clone();
+            let writer = tokio::spawn(async move {
+                composition_writer_task(rx, &path, FlushPolicy::default(), true).await
+            });
+            Self {
+                filename,
+                sender,
+                writer,
+            }
+        }
+    }
+
+    impl CompositionSink for TempFileSink {
+        fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+            Box::pin(async move {
+                self.sender.send(composition).await.map_err(|_| {
+                    anyhow::anyhow!("Error trying to send Composition through Channel")
+                })
+            })
+        }
+
+        fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+            Box::pin(async move {
+                drop(self.sender);
+                self.writer.await??;
+                Ok(vec![self.filename])
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn one_writer_serves_many_concurrent_producers() {
+        let path = std::env::temp_dir().join(format!("shared-writer-{}.json", std::process::id()));
+        let filename = path.to_str().unwrap().to_string();
+        let (sink, writer) = SharedSink::spawn(Box::new(TempFileSink::new(filename.clone())));
+
+        // Each producer stands in for one composer's get_works_into run
+        let producers: Vec<_> = (0..8)
+            .map(|producer| {
+                let sink = sink.clone();
+                tokio::spawn(async move {
+                    for i in 0..50 {
+                        let title = format!("Work {producer}-{i} {}", "x".repeat(500));
+                        let composition = canonicalize_raw_data(raw_row(&["Title"], &[&title]));
+                        sink.send(composition).await?;
+                        tokio::task::yield_now().await;
+                    }
+                    anyhow::Ok(())
+                })
+            })
+            .collect();
+        drop(sink);
+        for producer in producers {
+            producer.await.unwrap().unwrap();
+        }
+        assert_eq!(writer.await.unwrap().unwrap(), vec![filename]);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let titles: HashSet<String> = contents
+            .lines()
+            .map(|line| serde_json::from_str::<Composition>(line).unwrap().title)
+            .collect();
+        assert_eq!(titles.len(), 400);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn multi_value_genre_cells_fill_genres_and_keep_the_first_as_genre() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Genre"],
+            &["Ave verum corpus", "Sacred; Choral; A cappella"],
+        ));
+        assert_eq!(composition.genre.as_deref(), Some("Sacred"));
+        assert_eq!(composition.genres, vec![Genre::Choral]);
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Genre"],
+            &["Le Bourgeois gentilhomme", "Ballet, incidental music"],
+        ));
+        assert_eq!(composition.genre.as_deref(), Some("Ballet"));
+        assert_eq!(composition.genres, vec![Genre::Ballet, Genre::Film]);
+    }
+
+    #[test]
+    fn table_caption_is_used_as_genre_fallback() {
+        let html = r#"
+            <html><body>
+            <table class="wikitable sortable" id="piano-works">
+              <caption>Works for solo piano</caption>
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Kreisleriana</td><td>1838</td></tr>
+            </table>
+            </body></html>
+        "#;
+        let document = Html::parse_document(html);
+
+        let raw_data = extract_raw_data(&document, "Test Composer", "composer_url", "page_url");
+        let compositions: Vec<Composition> =
+            raw_data.into_iter().map(canonicalize_raw_data).collect();
+
+        assert_eq!(compositions.len(), 1);
+        assert_eq!(
+            compositions[0].genre.as_deref(),
+            Some("Works for solo piano")
+        );
+        let raw = compositions[0].raw_data.as_ref().unwrap();
+        assert_eq!(raw.table_caption.as_deref(), Some("Works for solo piano"));
+        assert_eq!(raw.table_classes, vec!["wikitable", "sortable"]);
+        assert_eq!(raw.table_id.as_deref(), Some("piano-works"));
+        assert_eq!(raw.section_heading, None);
+    }
+
+    #[test]
+    fn splits_alternate_language_titles_out_of_the_title() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &["The Rite of Spring (Le Sacre du printemps)", "1913"],
+        ));
+
+        assert_eq!(composition.title, "The Rite of Spring");
+        assert_eq!(composition.alt_titles, vec!["Le Sacre du printemps"]);
+    }
+
+    #[test]
+    fn keeps_catalog_numbers_in_parentheses_on_the_title() {
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &["Symphony No. 41 (K. 551)", "1788"],
+        ));
+
+        assert_eq!(composition.title, "Symphony No. 41 (K. 551)");
+        assert!(composition.alt_titles.is_empty());
+    }
+
+    #[test]
+    fn misspelled_and_abbreviated_headers_map_by_edit_distance() {
+        let canonicalizer = FieldCanonicalizer::new();
+        for (header, field) in [
+            ("Ttile", "title"),
+            ("Compositon", "title"),
+            ("Yr.", "year"),
+            ("Yr. composed", "year"),
+            ("Yaer", "year"),
+            ("Genra", "genre"),
+            ("Catalgoue", "catalog_number"),
+            ("Instrumentaton", "instrumentation"),
+            ("Scorng", "instrumentation"),
+            ("Durtion", "duration"),
+            ("Lenght", "duration"),
+        ] {
+            assert_eq!(
+                canonicalizer.categorize_header(header),
+                Some(field),
+                "{}",
+                header
+            );
+        }
+        // Near only to keywords they aren't
+        for header in [
+            "Place",
+            "Notes",
+            "Act",
+            "Text",
+            "Score",
+            "Source",
+            "Role",
+            "Yrs",
+            "Venue",
+            "Composer",
+            "Composers",
+        ] {
+            assert_eq!(canonicalizer.categorize_header(header), None, "{}", header);
+        }
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Composer"],
+            &["Goldberg Variations", "Johann Sebastian Bach"],
+        ));
+        assert_eq!(composition.year, None);
+
+        assert_eq!(
+            canonicalizer.categorize_header("Instrmentaton"),
+            Some("instrumentation")
+        );
+        let strict = FieldCanonicalizer::new().with_fuzzy_threshold(1);
+        assert_eq!(strict.categorize_header("Ttile"), Some("title"));
+        assert_eq!(strict.categorize_header("Instrmentaton"), None);
+        let off = FieldCanonicalizer::new().with_fuzzy_threshold(0);
+        assert_eq!(off.categorize_header("Genra"), None);
+        assert_eq!(off.categorize_header("Title"), Some("title"));
+
+        let field_map: FieldMap = serde_json::from_str(r#"{"fuzzy_threshold": 0}"#).unwrap();
+        let mapped = FieldCanonicalizer::new()
+            .with_field_map(&field_map)
+            .unwrap();
+        assert_eq!(mapped.categorize_header("Ttile"), None);
+
+        let composition = canonicalize_raw_data(raw_row(&["Ttile", "Yr."], &["Requiem", "1791"]));
+        assert_eq!(composition.title, "Requiem");
+        assert_eq!(composition.year.as_deref(), Some("1791"));
+    }
+
+    #[test]
+    fn field_map_maps_previously_unknown_headers() {
+        let path = std::env::temp_dir().join(format!("field-map-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{
+                "mappings": [
+                    {"pattern": "Uraufführung", "field": "year"},
+                    {"pattern": "^BV$", "field": "catalog_number", "regex": true}
+                ],
+                "ignore": ["Name des Werks"]
+            }"#,
+        )
+        .unwrap();
+        let field_map = FieldMap::load(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let default = FieldCanonicalizer::new();
+        let custom = FieldCanonicalizer::new()
+            .with_field_map(&field_map)
+            .unwrap();
+
+        assert_eq!(default.categorize_header("Uraufführung"), None);
+        assert_eq!(custom.categorize_header("Uraufführung"), Some("year"));
+        assert_eq!(custom.categorize_header("BV"), Some("catalog_number"));
+        assert_eq!(default.categorize_header("Name des Werks"), Some("title"));
+        assert_eq!(custom.categorize_header("Name des Werks"), None);
+    }
+
+    #[test]
+    fn collapsible_work_tables_are_kept_and_marked() {
+        // A long opera list folded away under its section, as on
+        // "List of operas by Gioachino Rossini"
+        let html = r#"<html><body>
+            <h2>Operas</h2>
+            <table class="wikitable sortable mw-collapsible mw-collapsed">
+              <tr><th>Title</th><th>Genre</th><th>Premiere</th></tr>
+              <tr><td>Tancredi</td><td>melodramma eroico</td><td>1813</td></tr>
+              <tr><td>Il barbiere di Siviglia</td><td>opera buffa</td><td>1816</td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Petite messe solennelle</td><td>1863</td></tr>
+            </table>
+            </body></html>"#;
+        let raw = extract_raw_data(
+            &Html::parse_document(html),
+            "Gioachino Rossini",
+            "composer_url",
+            "page_url",
+        );
+
+        let rows: Vec<(&str, bool)> = raw
+            .iter()
+            .map(|raw_data| (raw_data.cell_data[0].as_str(), raw_data.was_collapsed))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("Tancredi", true),
+                ("Il barbiere di Siviglia", true),
+                ("Petite messe solennelle", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn collapsible_navboxes_are_skipped() {
+        // Trimmed from the "Ludwig van Beethoven" navbox at the foot of his
+        // list of compositions
+        let html = r#"<html><body>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Opus</th></tr>
+              <tr><td>Symphony No. 3</td><td>Op. 55</td></tr>
+            </table>
+            <div role="navigation" class="navbox" aria-labelledby="Ludwig_van_Beethoven">
+              <table class="nowraplinks mw-collapsible autocollapse navbox-inner">
+                <tr><th scope="col" class="navbox-title" colspan="2">Ludwig van Beethoven</th></tr>
+                <tr><th scope="row" class="navbox-group">Symphonies</th>
+                    <td class="navbox-list"><a href="/wiki/Symphony_No._1_(Beethoven)">No. 1</a> · <a href="/wiki/Symphony_No._2_(Beethoven)">No. 2</a></td></tr>
+                <tr><th scope="row" class="navbox-group">Operas</th>
+                    <td class="navbox-list"><a href="/wiki/Fidelio">Fidelio</a></td></tr>
+              </table>
+            </div>
+            </body></html>"#;
+        let raw = extract_raw_data(
+            &Html::parse_document(html),
+            "Ludwig van Beethoven",
+            "composer_url",
+            "page_url",
+        );
+
+        assert_eq!(raw.len(), 1);
+        assert_eq!(raw[0].cell_data[0], "Symphony No. 3");
+        assert!(!raw[0].was_collapsed);
+    }
+
+    #[test]
+    fn oversized_tables_are_skipped_and_table_count_is_capped() {
+        let navbox_rows: String = (0..50)
+            .map(|i| format!("<tr><td>Link {}</td><td>Link {}</td></tr>", i, i + 50))
+            .collect();
+        let html = format!(
+            r#"<html><body>
+            <table class="navbox"><tr><th>Title</th><th>See also</th></tr>{}</table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Kinderszenen</td><td>1838</td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Dichterliebe</td><td>1840</td></tr>
+            </table>
+            </body></html>"#,
+            navbox_rows
+        );
+        let document = Html::parse_document(&html);
+        let titles = |limits: TableLimits| -> Vec<String> {
+            extract_raw_data_with_limits(
+                &document,
+                "Robert Schumann",
+                "composer_url",
+                "page_url",
+                &limits,
+            )
+            .into_iter()
+            .map(|raw_data| raw_data.cell_data[0].clone())
+            .collect()
+        };
+
+        assert_eq!(titles(TableLimits::default()).len(), 52);
+        assert_eq!(
+            titles(TableLimits {
+                max_rows_per_table: Some(20),
+                ..Default::default()
+            }),
+            vec!["Kinderszenen", "Dichterliebe"]
+        );
+        assert_eq!(
+            titles(TableLimits {
+                max_rows_per_table: Some(20),
+                max_tables: Some(1),
+                ..Default::default()
+            }),
+            vec!["Kinderszenen"]
+        );
+    }
+
+    #[test]
+    fn combined_pages_attribute_each_section_to_its_composer() {
+        let html = r#"<html><body>
+            <p>Works by members of the Bach family.</p>
+            <h2>Johann Christoph Bach (1642–1703)</h2>
+            <h3>Motets</h3>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Catalogue</th></tr>
+              <tr><td>Der Gerechte, ob er gleich zu zeitlich stirbt</td><td>JCB 6</td></tr>
+            </table>
+            <h2>Johann Michael Bach</h2>
+            <h3>Sacred works</h3>
+            <ul><li>Halt, was du hast</li></ul>
+            <h2>See also</h2>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Altbachisches Archiv</td><td>1935</td></tr>
+            </table>
+            </body></html>"#;
+        let composer_headings = ComposerHeadings {
+            names: vec!["Johann Michael Bach".to_string()],
+        };
+
+        let raw_data = extract_raw_data_by_composer(
+            &Html::parse_document(html),
+            "Bach family",
+            "https://en.wikipedia.org/wiki/Bach_family",
+            "page_url",
+            "https://en.wikipedia.org",
+            &TableLimits::default(),
+            &composer_headings,
+        );
+        let rows: Vec<(&str, &str, &str)> = raw_data
+            .iter()
+            .map(|raw| {
+                (
+                    raw.cell_data[0].as_str(),
+                    raw.composer_name.as_str(),
+                    raw.composer_url.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "Der Gerechte, ob er gleich zu zeitlich stirbt",
+                    "Johann Christoph Bach",
+                    "https://en.wikipedia.org/wiki/Johann_Christoph_Bach"
+                ),
+                (
+                    "Altbachisches Archiv",
+                    "Bach family",
+                    "https://en.wikipedia.org/wiki/Bach_family"
+                ),
+                (
+                    "Halt, was du hast",
+                    "Johann Michael Bach",
+                    "https://en.wikipedia.org/wiki/Johann_Michael_Bach"
+                ),
+            ]
+        );
+        assert_eq!(raw_data[0].section_heading.as_deref(), Some("Motets"));
+        assert_eq!(
+            canonicalize_raw_data(raw_data[2].clone()).composer_name,
+            "Johann Michael Bach"
+        );
+    }
+
+    #[test]
+    fn section_composer_urls_escape_the_name() {
+        let html = r#"<html><body>
+            <h2>Gilbert & Sullivan</h2>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>The Mikado</td><td>1885</td></tr>
+            </table>
+            <h2>Weill/Brecht (1928–1930)</h2>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Die Dreigroschenoper</td><td>1928</td></tr>
+            </table>
+            </body></html>"#;
+        let composer_headings = ComposerHeadings {
+            names: vec!["Gilbert & Sullivan".to_string()],
+        };
+
+        let raw_data = extract_raw_data_by_composer(
+            &Html::parse_document(html),
+            "Collaborations",
+            "composer_url",
+            "page_url",
+            "https://en.wikipedia.org",
+            &TableLimits::default(),
+            &composer_headings,
+        );
+        let urls: Vec<&str> = raw_data
+            .iter()
+            .map(|raw| raw.composer_url.as_str())
+            .collect();
+
+        assert_eq!(
+            urls,
+            vec![
+                "https://en.wikipedia.org/wiki/Gilbert_%26_Sullivan",
+                "https://en.wikipedia.org/wiki/Weill%2FBrecht",
+            ]
+        );
+    }
+
+    #[test]
+    fn supplied_headers_name_the_columns_of_headerless_tables() {
+        let html = r#"<html><body>
+            <table class="wikitable">
+              <tr><td>Gymnopédies</td><td>1888</td><td>piano</td></tr>
+              <tr><td>Gnossiennes</td><td>1890</td><td>piano</td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Parade</td><td>1917</td></tr>
+            </table>
+            </body></html>"#;
+        let page_url = "https://en.wikipedia.org/wiki/List_of_compositions_by_Erik_Satie";
+        let mut raw_data =
+            extract_raw_data(&Html::parse_document(html), "Erik Satie", "", page_url);
+        assert_eq!(
+            raw_data[0].headers,
+            vec!["column_0", "column_1", "column_2"]
+        );
+        let unmapped = canonicalize_raw_data(raw_data[0].clone());
+        assert_eq!(unmapped.title, "");
+        assert_eq!(unmapped.year_confidence, Some(YearConfidence::Low));
+
+        let field_map: FieldMap = serde_json::from_str(
+            r#"{"table_headers": [
+                {"url": "https://example.org/wiki/Other_page", "headers": ["Year", "Title"]},
+                {"url": "List_of_compositions_by_Erik_Satie", "table_index": 0,
+                 "headers": ["Title", "Year"]}
+            ]}"#,
+        )
+        .unwrap();
+        let changed = apply_table_headers(&mut raw_data, page_url, &field_map.table_headers);
+
+        assert_eq!(changed, 2);
+        assert_eq!(raw_data[0].headers, vec!["Title", "Year", "column_2"]);
+        let composition = canonicalize_raw_data(raw_data[1].clone());
+        assert_eq!(composition.title, "Gnossiennes");
+        assert_eq!(composition.year.as_deref(), Some("1890"));
+        assert_eq!(composition.year_confidence, Some(YearConfidence::High));
+        // Tables with their own headers are left alone
+        assert_eq!(raw_data[2].headers, vec!["Title", "Year"]);
+    }
+
+    #[test]
+    fn cells_beyond_the_headers_go_to_additional_info() {
+        let html = r#"<html><body>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th><th>Notes</th></tr>
+              <tr><td>Pictures at an Exhibition</td><td>1874</td><td>Piano suite</td><td>orch. Ravel 1922</td></tr>
+            </table>
+            </body></html>"#;
+        let raw_data = extract_raw_data(
+            &Html::parse_document(html),
+            "Modest Mussorgsky",
+            "",
+            "page_url",
+        );
+        assert_eq!(raw_data[0].cell_data.len(), 4);
+
+        let composition = canonicalize_raw_data(raw_data[0].clone());
+        assert_eq!(composition.title, "Pictures at an Exhibition");
+        assert_eq!(composition.year.as_deref(), Some("1874"));
+        assert_eq!(
+            composition.additional_info.get("Notes").map(String::as_str),
+            Some("Piano suite")
+        );
+        assert_eq!(
+            composition
+                .additional_info
+                .get("column_3")
+                .map(String::as_str),
+            Some("orch. Ravel 1922")
+        );
+    }
+
+    #[test]
+    fn rows_repeating_the_composer_are_recognised() {
+        let html = r#"<html><body>
+            <table class="wikitable">
+              <tr><th>Title</th><th>BWV</th><th>Year</th></tr>
+              <tr><td>Johann Sebastian Bach (1685–1750)</td><td></td><td></td></tr>
+              <tr><td>Johann Sebastian Bach</td><td></td><td></td></tr>
+              <tr><td>Mass in B minor</td><td>BWV 232</td><td>1749</td></tr>
+              <tr><td>Cantata</td><td></td><td>1714–1716</td></tr>
+            </table>
+            </body></html>"#;
+        let compositions: Vec<Composition> = extract_raw_data(
+            &Html::parse_document(html),
+            "Johann Sebastian Bach",
+            "",
+            "page_url",
+        )
+        .into_iter()
+        .map(canonicalize_raw_data)
+        .collect();
+        // Every row has a title, so none is dropped before the echo check
+        assert!(compositions.iter().all(has_meaningful_title));
+        let life = Some(LifeYears {
+            birth: Some(1685),
+            death: Some(1750),
+        });
+
+        let echoes: Vec<bool> = compositions
+            .iter()
+            .map(|composition| is_composer_echo(composition, life))
+            .collect();
+        assert_eq!(echoes, vec![true, true, false, false]);
+
+        // Without known years only the name check applies
+        assert!(!is_composer_echo(&compositions[0], None));
+        assert!(is_composer_echo(&compositions[1], None));
+        // A different lifespan is a real date range
+        let other = Some(LifeYears {
+            birth: Some(1685),
+            death: Some(1759),
+        });
+        assert!(!is_composer_echo(&compositions[0], other));
+    }
+
+    #[test]
+    fn narrow_tables_are_skipped() {
+        let html = r#"<html><body>
+            <table class="wikitable">
+              <tr><th>Contents</th></tr>
+              <tr><td>Early life</td></tr>
+              <tr><td>Works</td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Key</th><th>Year</th></tr>
+              <tr><td>Carnaval</td><td>A-flat major</td><td>1835</td></tr>
+            </table>
+            </body></html>"#;
+        let document = Html::parse_document(html);
+        let titles = |limits: TableLimits| -> Vec<String> {
+            extract_raw_data_with_limits(&document, "Robert Schumann", "", "page_url", &limits)
+                .into_iter()
+                .map(|raw_data| raw_data.cell_data[0].clone())
+                .collect()
+        };
+
+        assert_eq!(titles(TableLimits::default()), vec!["Carnaval"]);
+        assert_eq!(
+            titles(TableLimits {
+                min_columns: 4,
+                ..Default::default()
+            }),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            titles(TableLimits {
+                min_columns: 0,
+                ..Default::default()
+            }),
+            vec!["Early life", "Works", "Carnaval"]
+        );
+    }
+
+    // Keeps everything in memory, for checking what a run produced
+    struct VecSink(Arc<std::sync::Mutex<Vec<Composition>>>);
+
+    impl CompositionSink for VecSink {
+        fn write(&mut self, composition: Composition) -> SinkFuture<'_, ()> {
+            self.0.lock().unwrap().push(composition);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn finish(self: Box<Self>) -> SinkFuture<'static, Vec<String>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn splitting_parquet_output_by_genre_is_refused() {
+        let options = WorksOptions {
+            format: OutputFormat::Parquet,
+            split_by_genre: true,
+            ..Default::default()
+        };
+        let error = output_sink(&options).await.err().unwrap();
+        assert!(error.to_string().contains("--split-by-genre"));
+    }
+
+    #[tokio::test]
+    async fn shared_sink_writes_every_composer_to_a_custom_sink() {
+        let written = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (sink, writer) = SharedSink::spawn(Box::new(VecSink(written.clone())));
+
+        let mut handles = Vec::new();
+        for title in ["Gaspard de la nuit", "Miroirs", "Boléro"] {
+            let sink = sink.clone();
+            let composition = canonicalize_raw_data(raw_row(&["Title"], &[title]));
+            handles.push(tokio::spawn(async move { sink.send(composition).await }));
+        }
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+        drop(sink);
+
+        assert!(writer.await.unwrap().unwrap().is_empty());
+        let mut titles: Vec<String> = written
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|composition| composition.title.clone())
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Boléro", "Gaspard de la nuit", "Miroirs"]);
+    }
+
+    #[test]
+    fn repeats_across_continuation_pages_are_dropped_as_the_run_maps_headers() {
+        // Only the field map makes "Werk" the title column
+        let field_map: FieldMap =
+            serde_json::from_str(r#"{"mappings": [{"pattern": "werk", "field": "title"}]}"#)
+                .unwrap();
+        let canonicalizer = FieldCanonicalizer::new()
+            .with_field_map(&field_map)
+            .unwrap();
+        let page = |rows: &[(&str, &str)]| -> Vec<RawCompositionData> {
+            rows.iter()
+                .map(|(title, year)| raw_row(&["Werk", "Jahr"], &[title, year]))
+                .collect()
+        };
+
+        let (rows, page_starts) = merge_continuation_rows(vec![
+            page(&[
+                ("Ständchen", "1828"),
+                ("Ständchen", "1828"),
+                ("Winterreise", "1827"),
+            ]),
+            page(&[("Winterreise", "1827"), ("Forellenquintett", "1819")]),
+        ]);
+        assert_eq!(page_starts, vec![1]);
+        assert_eq!(
+            rows.iter().map(|r| r.table_index).collect::<Vec<_>>(),
+            vec![0, 0, 0, 1, 1]
+        );
+
+        let compositions: Vec<Composition> = rows
+            .into_iter()
+            .map(|raw_data| canonicalize_raw_data_with(raw_data, &canonicalizer))
+            .collect();
+        let titles: Vec<String> = drop_repeats_across_pages(compositions, &page_starts)
+            .into_iter()
+            .map(|composition| composition.title)
+            .collect();
+        // The repeat within the first page stays
+        assert_eq!(
+            titles,
+            vec!["Ständchen", "Ständchen", "Winterreise", "Forellenquintett"]
+        );
+    }
+
+    #[test]
+    fn truncates_snippets_to_the_configured_limit() {
+        let row = "<tr><td><a href=\"/wiki/Symphony_No._9_(Dvořák)\">Symphony No. 9</a></td></tr>";
+
+        let mut snippet = row.to_string();
+        truncate_snippet(&mut snippet, 40);
+        assert!(snippet.len() <= 40);
+        assert!(snippet.ends_with(SNIPPET_ELLIPSIS));
+        assert!(row.starts_with(snippet.trim_end_matches(SNIPPET_ELLIPSIS)));
+
+        // "ř" spans bytes 42..44; the cut backs off to a char boundary
+        let mut snippet = row.to_string();
+        truncate_snippet(&mut snippet, 46);
+        assert_eq!(snippet, "<tr><td><a href=\"/wiki/Symphony_No._9_(Dvo…");
+
+        let mut snippet = row.to_string();
+        truncate_snippet(&mut snippet, row.len());
+        assert_eq!(snippet, row);
+
+        // Every cut through a CJK title lands on a char boundary
+        let row = "<tr><td>交響曲第1番</td></tr>";
+        for max_bytes in 0..=row.len() {
+            let mut snippet = row.to_string();
+            truncate_snippet(&mut snippet, max_bytes);
+            assert!(snippet.len() <= max_bytes, "{}", max_bytes);
+        }
+
+        let mut snippet = row.to_string();
+        truncate_snippet(&mut snippet, 0);
+        assert_eq!(snippet, "");
+    }
+
+    #[test]
+    fn title_length_is_counted_in_characters() {
+        let titled = |title: &str| {
+            let composition = canonicalize_raw_data(raw_row(&["Title", "Year"], &[title, "1900"]));
+            has_meaningful_title(&composition)
+        };
+        assert!(titled("Boléro"));
+        assert!(titled("Été"));
+        assert!(titled("交響曲"));
+        assert!(titled("Op."));
+        // Two characters, though more than two bytes
+        assert!(!titled("Ré"));
+        assert!(!titled("雪"));
+        assert!(!titled("–"));
+        assert!(!titled(""));
+
+        let composition = canonicalize_raw_data(RawCompositionData {
+            composer_name: "武満徹".to_string(),
+            ..raw_row(&["Title", "Year"], &["ノヴェンバー・ステップス", "1967"])
+        });
+        assert_eq!(composition.title, "ノヴェンバー・ステップス");
+        assert_eq!(composition.year_numeric, Some(1967));
+    }
+
+    #[test]
+    fn skips_a_trailing_total_row() {
+        let html = r#"<html><body>
+            <h2>Symphonies</h2>
+            <table class="wikitable">
+              <tr><th>Hob.</th><th>Title</th><th>Key</th><th>Year</th></tr>
+              <tr><td>I:1</td><td>Symphony No. 1</td><td>D major</td><td>1759</td></tr>
+              <tr><td>I:94</td><td>Symphony No. 94 "Surprise"</td><td>G major</td><td>1791</td></tr>
+              <tr><td>I:104</td><td>Symphony No. 104 "London"</td><td>D major</td><td>1795</td></tr>
+              <tr><td colspan="4">104 symphonies in total</td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>The Creation</td><td>1798</td></tr>
+              <tr><td>Count Ory Variations</td><td>1790</td></tr>
+              <tr><td>Total Eclipse</td><td>1999</td></tr>
+              <tr><td><b>Total:</b> 3 works</td><td></td></tr>
+            </table>
+            </body></html>"#;
+        let raw = extract_raw_data_with_limits(
+            &Html::parse_document(html),
+            "Joseph Haydn",
+            "https://en.wikipedia.org/wiki/Joseph_Haydn",
+            "https://en.wikipedia.org/wiki/List_of_compositions_by_Joseph_Haydn",
+            &TableLimits::default(),
+        );
+        assert_eq!(raw.len(), 8);
+
+        let titles: Vec<String> = raw
+            .into_iter()
+            .filter(|raw_data| !is_total_row(raw_data))
+            .map(|raw_data| canonicalize_raw_data(raw_data).title)
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Symphony No. 1",
+                "Symphony No. 94 \"Surprise\"",
+                "Symphony No. 104 \"London\"",
+                "The Creation",
+                "Count Ory Variations",
+                "Total Eclipse",
+            ]
+        );
+        assert!(is_total_row(&raw_row(
+            &["Title", "Year"],
+            &["Subtotal", "12"]
+        )));
+        assert!(is_total_row(&raw_row(
+            &["Title", "Year"],
+            &["Count: 40", ""]
+        )));
+        assert!(!is_total_row(&raw_row(
+            &["Title", "Year"],
+            &["Summer Nights", "1841"]
+        )));
+        assert!(!is_total_row(&raw_row(
+            &["Title", "Year"],
+            &["Total Eclipse", "1999"]
+        )));
+        assert!(is_total_row(&raw_row(
+            &["Title", "Year", "Duration"],
+            &["Total", "", "1,204"]
+        )));
+    }
+
+    #[tokio::test]
+    async fn a_list_page_without_tables_or_lists_is_an_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // A script-rendered shell: the page exists but holds no works. The
+        // category page isn't mounted, so that fallback finds nothing too.
+        let html = r#"<html><body><div id="app"></div>
+            <script src="/static/app.js"></script></body></html>"#;
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/wiki/List_of_compositions_by_Erik_Satie"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(html))
+            .mount(&server)
+            .await;
+
+        let written = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let options = WorksOptions {
+            base_url: server.uri(),
+            wikitext_fallback: false,
+            ..Default::default()
+        };
+        let e = get_works_with("Erik Satie", &options, Box::new(VecSink(written.clone())))
+            .await
+            .unwrap_err();
+
+        let url = format!("{}/wiki/List_of_compositions_by_Erik_Satie", server.uri());
+        match e.downcast_ref::<WorksError>() {
+            Some(WorksError::NoContentExtracted {
+                url: error_url,
+                html_bytes,
+            }) => {
+                assert_eq!(*error_url, url);
+                assert_eq!(*html_bytes, html.len());
+            }
+            other => panic!("expected NoContentExtracted, got {:?}", other),
+        }
+        assert!(e.to_string().contains("bytes of HTML"));
+        assert!(written.lock().unwrap().is_empty());
+        assert!(matches!(
+            crate::batch::ComposerOutcome::from_result(&Err(e)),
+            crate::batch::ComposerOutcome::NoContent { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn dedup_sink_writes_only_compositions_missing_from_the_master() {
+        let composition = |title: &str, catalog: &str| {
+            canonicalize_raw_data(raw_row(&["Title", "Catalogue"], &[title, catalog]))
+        };
+        let master = [
+            composition("Gymnopédie No. 1", ""),
+            composition("Symphony No. 1", "Hob. I:1"),
+        ];
+        let path = std::env::temp_dir().join(format!("master-{}.json", std::process::id()));
+        let contents: String = master
+            .iter()
+            .map(|c| serde_json::to_string(c).unwrap() + "\n")
+            .collect();
+        std::fs::write(&path, contents).unwrap();
+        let index = MasterIndex::load(path.to_str().unwrap()).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(index.len(), 2);
+
+        let written = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut sink = Box::new(DedupSink::new(Box::new(VecSink(written.clone())), index));
+        let scrape = [
+            // Same work, spelled without the accent
+            composition("Gymnopedie No. 1", ""),
+            composition("Symphony No. 1", "Hob. I:1"),
+            composition("Symphony No. 2", "Hob. I:2"),
+            composition("Symphony No. 2", "Hob. I:2"),
+            // Same title, different catalog number
+            composition("Symphony No. 1", "Hob. I:108"),
+        ];
+        for composition in scrape {
+            sink.write(composition).await.unwrap();
+        }
+        assert_eq!((sink.new, sink.skipped), (2, 3));
+        sink.finish().await.unwrap();
+
+        let written = written.lock().unwrap();
+        let written: Vec<(&str, Option<&str>)> = written
+            .iter()
+            .map(|c| (c.title.as_str(), c.catalog_number.as_deref()))
+            .collect();
+        assert_eq!(
+            written,
+            vec![
+                ("Symphony No. 2", Some("Hob. I:2")),
+                ("Symphony No. 1", Some("Hob. I:108"))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pretty_sink_writes_an_array_alongside_the_inner_sink() {
+        let path = std::env::temp_dir().join(format!("pretty-{}.json", std::process::id()));
+        let written = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut sink = Box::new(PrettyJsonSink::new(
+            Box::new(VecSink(written.clone())),
+            path.to_str().unwrap(),
+        ));
+
+        for title in ["Gymnopédie No. 1", "Gnossienne No. 1"] {
+            sink.write(canonicalize_raw_data(raw_row(&["Title"], &[title])))
+                .await
+                .unwrap();
+        }
+        let outputs = sink.finish().await.unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(outputs, vec![path.to_str().unwrap().to_string()]);
+        assert_eq!(written.lock().unwrap().len(), 2);
+        assert!(contents.starts_with("[\n  {"));
+        let compositions: Vec<Composition> = serde_json::from_str(&contents).unwrap();
+        assert_eq!(compositions[1].title, "Gnossienne No. 1");
+    }
+
+    #[tokio::test]
+    async fn flushed_output_survives_a_crash_as_complete_lines() {
+        let path = std::env::temp_dir().join(format!("crash-{}.json", std::process::id()));
+        let filename = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let (tx, rx) = mpsc::channel::<Composition>(100);
+        let flush = FlushPolicy {
+            every_records: Some(2),
+            interval: None,
+        };
+        let writer_filename = filename.clone();
+        let handle = tokio::spawn(async move {
+            composition_writer_task(rx, &writer_filename, flush, false).await
+        });
+
+        for i in 0..5 {
+            let title = format!("Étude No. {}", i + 1);
+            tx.send(canonicalize_raw_data(raw_row(&["Title"], &[&title])))
+                .await
+                .unwrap();
+        }
+        // Wait for the second flush, then kill the writer mid-run
+        for _ in 0..100 {
+            let written = std::fs::read_to_string(&path).unwrap_or_default();
+            if written.lines().count() >= 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        handle.abort();
+        let _ = handle.await;
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written.ends_with('\n'));
+        let titles: Vec<String> = written
+            .lines()
+            .map(|line| serde_json::from_str::<Composition>(line).unwrap().title)
+            .collect();
+        assert_eq!(
+            titles[..4],
+            ["Étude No. 1", "Étude No. 2", "Étude No. 3", "Étude No. 4"]
+        );
+    }
+
+    #[tokio::test]
+    async fn debug_tables_writes_one_file_per_table() {
+        let html = r#"
+            <html><body>
+            <table class="wikitable" id="operas">
+              <caption>Operas</caption>
+              <tr><th>Title</th><th>Year</th></tr>
+              <tr><td>Rinaldo</td><td>1711</td></tr>
+              <tr><td>Alcina</td><td>1735</td></tr>
+            </table>
+            <table class="wikitable">
+              <tr><th>Title</th><th>HWV</th></tr>
+              <tr><td>Water Music</td><td>348</td></tr>
+            </table>
+            </body></html>
+        "#;
+        let raw_data = extract_raw_data(
+            &Html::parse_document(html),
+            "George Frideric Handel",
+            "composer_url",
+            "page_url",
+        );
+        let dir = std::env::temp_dir().join(format!("debug-tables-{}", std::process::id()));
+
+        let paths = write_debug_tables(&dir, &raw_data).await.unwrap();
+
+        assert_eq!(
+            paths,
+            vec![dir.join("table-0.json"), dir.join("table-1.json")]
+        );
+        let first: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&paths[0]).unwrap()).unwrap();
+        assert_eq!(first["caption"], "Operas");
+        assert_eq!(first["classes"], serde_json::json!(["wikitable"]));
+        assert_eq!(first["id"], "operas");
+        assert_eq!(first["headers"], serde_json::json!(["Title", "Year"]));
+        assert_eq!(first["rows"].as_array().unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detects_lost_and_unfinished_works() {
+        let composition = canonicalize_raw_data(raw_row(&["Title"], &["Symphony (unfinished)"]));
+        assert_eq!(composition.status, WorkStatus::Incomplete);
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Comments"],
+            &["Symphony in E-flat", "Score lost; known only from a letter"],
+        ));
+        assert_eq!(composition.status, WorkStatus::Lost);
+
+        let composition = canonicalize_raw_data(raw_row(&["Title"], &["The Lost Chord"]));
+        assert_eq!(composition.status, WorkStatus::Complete);
+    }
+
+    #[test]
+    fn custom_extractor_overrides_the_default_rules() {
+        // Catalogues the default patterns don't know, with "RV" numbers kept
+        // whole and "Anh." columns treated as catalogue numbers too
+        struct VivaldiExtractor(FieldCanonicalizer);
+
+        impl FieldExtractor for VivaldiExtractor {
+            fn categorize(&self, header: &str) -> Option<Field> {
+                match header {
+                    "RV" | "Anh." => Some(Field::CatalogNumber),
+                    _ => self.0.categorize(header),
+                }
+            }
+
+            fn extract(&self, field: Field, cell: &str) -> Option<String> {
+                match field {
+                    Field::CatalogNumber => Some(format!("RV {}", cell.trim())),
+                    _ => self.0.extract(field, cell),
+                }
+            }
+        }
+
+        let raw = raw_row(&["Title", "RV", "Year"], &["La primavera", "269", "1720"]);
+        let default = canonicalize_raw_data(raw.clone());
+        let custom = canonicalize_raw_data_with(raw, &VivaldiExtractor(FieldCanonicalizer::new()));
+
+        assert_eq!(default.catalog_number, None);
+        assert_eq!(
+            default.additional_info.get("RV").map(String::as_str),
+            Some("269")
+        );
+        assert_eq!(custom.catalog_number.as_deref(), Some("RV 269"));
+        assert!(custom.additional_info.is_empty());
+        assert_eq!(custom.title, "La primavera");
+        assert_eq!(custom.year.as_deref(), Some("1720"));
+    }
+
+    #[test]
+    fn only_linked_compositions_survive_retain_linked() {
+        let mut linked = raw_row(&["Title"], &["Symphony No. 1"]);
+        linked.cell_links = vec![Some(
+            "https://en.wikipedia.org/wiki/Symphony_No._1".to_string(),
+        )];
+        let unlinked = raw_row(&["Title", "Year"], &["Untitled sketch", "1890"]);
+        let mut compositions: Vec<Composition> = [linked, unlinked]
+            .into_iter()
+            .map(canonicalize_raw_data)
+            .collect();
+
+        assert_eq!(retain_linked(&mut compositions), 1);
+        assert_eq!(compositions.len(), 1);
+        assert_eq!(compositions[0].title, "Symphony No. 1");
+    }
+
+    #[test]
+    fn prefers_the_composed_column_when_several_map_to_year() {
+        let raw = raw_row(
+            &["Title", "Date published", "Date composed"],
+            &["Lyric Pieces, Op. 12", "1867", "1864–1867"],
+        );
+        let composition = canonicalize_raw_data(raw.clone());
+        assert_eq!(composition.year.as_deref(), Some("1864"));
+
+        let field_map: FieldMap =
+            serde_json::from_str(r#"{"priority": {"year": ["published"]}}"#).unwrap();
+        let canonicalizer = FieldCanonicalizer::new()
+            .with_field_map(&field_map)
+            .unwrap();
+        let composition = canonicalize_raw_data_with(raw, &canonicalizer);
+        assert_eq!(composition.year.as_deref(), Some("1867"));
+    }
+
+    #[test]
+    fn strips_composer_name_prefix_and_suffix_from_titles() {
+        let mut raw = raw_row(&["Title"], &["Beethoven: Symphony No. 5"]);
+        raw.composer_name = "Ludwig van Beethoven".to_string();
+        let prefixed = canonicalize_raw_data(raw);
+
+        let mut raw = raw_row(&["Title"], &["Symphony No. 5 (Ludwig van Beethoven)"]);
+        raw.composer_name = "Ludwig van Beethoven".to_string();
+        let suffixed = canonicalize_raw_data(raw);
+
+        assert_eq!(prefixed.title, "Symphony No. 5");
+        assert_eq!(
+            prefixed
+                .additional_info
+                .get("original_title")
+                .map(String::as_str),
+            Some("Beethoven: Symphony No. 5")
+        );
+        assert_eq!(suffixed.title, "Symphony No. 5");
+        assert!(suffixed.alt_titles.is_empty());
+    }
+
+    #[test]
+    fn strips_disambiguation_suffixes_but_not_catalogue_numbers() {
+        let title_of = |title: &str, composer_name: &str| {
+            let mut raw = raw_row(&["Title"], &[title]);
+            raw.composer_name = composer_name.to_string();
+            canonicalize_raw_data(raw)
+        };
+
+        let composition = title_of("Fidelio (Beethoven)", "Ludwig van Beethoven");
+        assert_eq!(composition.title, "Fidelio");
+        assert_eq!(
+            composition
+                .additional_info
+                .get("original_title")
+                .map(String::as_str),
+            Some("Fidelio (Beethoven)")
+        );
+        assert_eq!(
+            title_of("Orfeo (Monteverdi opera)", "Claudio Monteverdi").title,
+            "Orfeo"
+        );
+        assert_eq!(
+            title_of("Semele (oratorio)", "George Frideric Handel").title,
+            "Semele"
+        );
+        assert_eq!(
+            title_of("Missa solemnis (mass)", "Ludwig van Beethoven").title,
+            "Missa solemnis"
+        );
+        assert_eq!(
+            title_of("Missa brevis (Mozart Mass)", "Wolfgang Amadeus Mozart").title,
+            "Missa brevis"
+        );
+        assert_eq!(
+            title_of("Six Quartets (Haydn quartets)", "Joseph Haydn").title,
+            "Six Quartets"
+        );
+
+        let composition = title_of("Symphony No. 41 (K. 551)", "Wolfgang Amadeus Mozart");
+        assert_eq!(composition.title, "Symphony No. 41 (K. 551)");
+        assert!(!composition.additional_info.contains_key("original_title"));
+        assert_eq!(
+            title_of("Fantasia (in C major)", "Robert Schumann").title,
+            "Fantasia (in C major)"
+        );
+    }
+
+    #[test]
+    fn keeps_qualifiers_that_are_not_the_composer() {
+        let mut raw = raw_row(&["Title"], &["Variations on a Theme (Haydn)"]);
+        raw.composer_name = "Johannes Brahms".to_string();
+
+        let composition = canonicalize_raw_data(raw);
+
+        assert_eq!(composition.title, "Variations on a Theme (Haydn)");
+        assert!(!composition.additional_info.contains_key("original_title"));
+    }
+
+    #[tokio::test]
+    async fn category_pages_are_followed_and_titles_enriched() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let first_page = r#"<html><body>
+            <div id="mw-subcategories"><ul><li><a href="/wiki/Category:Operas_by_X">Operas by X</a></li></ul></div>
+            <div id="mw-pages">
+              <a href="/wiki/Category:Compositions_by_X?pagefrom=S">next page</a>
+              <div class="mw-category"><ul>
+                <li><a href="/wiki/Piano_Sonata,_Op._1_(X)" title="Piano Sonata, Op. 1 (X)">Piano Sonata, Op. 1 (X)</a></li>
+                <li><a href="/wiki/Requiem_(1791)" title="Requiem (1791)">Requiem (1791)</a></li>
+              </ul></div>
+            </div></body></html>"#;
+        let second_page = r#"<html><body><div id="mw-pages"><ul>
+                <li><a href="/wiki/Symphony_No._1_(X)" title="Symphony No. 1 (X)">Symphony No. 1 (X)</a></li>
+            </ul></div></body></html>"#;
+        Mock::given(method("GET"))
+            .and(path("/wiki/Category:Compositions_by_X"))
+            .and(query_param("pagefrom", "S"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(second_page))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/wiki/Category:Compositions_by_X"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(first_page))
+            .mount(&server)
+            .await;
+
+        let http = HttpOptions::default();
+        let raw_data = fetch_category_raw_data(
+            &build_client(&http).unwrap(),
+            &format!("{}/wiki/Category:Compositions_by_X", server.uri()),
+            "X",
+            "composer_url",
+            &server.uri(),
+            &http,
+        )
+        .await
+        .unwrap();
+        let compositions: Vec<Composition> =
+            raw_data.into_iter().map(canonicalize_raw_data).collect();
+
+        let titles: Vec<&str> = compositions.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Piano Sonata, Op. 1", "Requiem (1791)", "Symphony No. 1"]
+        );
+        assert_eq!(compositions[0].opus.as_deref(), Some("1"));
+        assert_eq!(compositions[1].year.as_deref(), Some("1791"));
+        // Work links point at the wiki the category came from
+        assert_eq!(
+            compositions[2].work_url,
+            Some(format!("{}/wiki/Symphony_No._1_(X)", server.uri()))
+        );
+    }
+
+    #[tokio::test]
+    async fn reprocessing_drops_the_rows_a_scrape_would() {
+        let raw_path =
+            std::env::temp_dir().join(format!("reprocess-filters-{}.json", std::process::id()));
+        let rows = [
+            raw_row(&["Title", "Year"], &["Symphony No. 1", "1759"]),
+            raw_row(&["Title", "Year"], &["Symphony No. 104", "1795"]),
+            // Too short to name a work, and the composer's own name
+            raw_row(&["Title", "Year"], &["No", "1795"]),
+            raw_row(&["Title", "Year"], &["Test Composer", ""]),
+            raw_row(&["Title", "Year"], &["Total: 104 symphonies", ""]),
+        ];
+        let lines: Vec<String> = rows
+            .iter()
+            .map(|row| serde_json::to_string(row).unwrap())
+            .collect();
+        std::fs::write(&raw_path, lines.join("\n")).unwrap();
+
+        let compositions = reprocess_raw_data(raw_path.to_str().unwrap(), 0, None)
+            .await
+            .unwrap();
+        std::fs::remove_file(&raw_path).unwrap();
+
+        let titles: Vec<&str> = compositions.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Symphony No. 1", "Symphony No. 104"]);
+    }
+
+    #[tokio::test]
+    async fn reprocess_skips_lines_and_appends() {
+        let dir = std::env::temp_dir();
+        let raw_path = dir.join(format!("reprocess-raw-{}.json", std::process::id()));
+        let output_path = dir.join(format!("reprocess-out-{}.json", std::process::id()));
+        let raw_lines: Vec<String> = ["Symphony No. 1", "Symphony No. 2", "Symphony No. 3"]
+            .iter()
+            .map(|title| serde_json::to_string(&raw_row(&["Title"], &[title])).unwrap())
+            .collect();
+        std::fs::write(&raw_path, raw_lines.join("\n")).unwrap();
+        std::fs::write(&output_path, "{\"existing\":true}\n").unwrap();
+
+        let raw_filename = raw_path.to_str().unwrap();
+        let titles = reprocess_raw_data(raw_filename, 1, None).await.unwrap();
+        let titles: Vec<&str> = titles.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Symphony No. 2", "Symphony No. 3"]);
+
+        let options = ReprocessOptions {
+            output: output_path.to_str().unwrap().to_string(),
+            skip: 2,
+            append: true,
+            format_version: None,
+            debug: false,
+        };
+        assert_eq!(reprocess_to_file(raw_filename, &options).await.unwrap(), 1);
+        let output = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(output.lines().nth(1).unwrap().contains("Symphony No. 3"));
+
+        std::fs::remove_file(&raw_path).unwrap();
+        std::fs::remove_file(&output_path).unwrap();
+    }
+
+    #[test]
+    fn compositions_path_rewrites_only_the_file_name() {
+        assert_eq!(
+            compositions_path_for(Path::new(
+                "/data/raw-info-2024/raw-info-Clara_Schumann.json"
+            )),
+            PathBuf::from("/data/raw-info-2024/compositions-Clara_Schumann.json")
+        );
+        assert_eq!(
+            compositions_path_for(Path::new("raw-info-Amy_Beach.json.gz")),
+            PathBuf::from("compositions-Amy_Beach.json.gz")
+        );
+    }
+
+    #[tokio::test]
+    async fn reprocess_all_handles_every_raw_file_in_a_directory() {
+        let dir = std::env::temp_dir().join(format!("reprocess-all-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write_raw = |name: &str, titles: &[&str]| {
+            let lines: Vec<String> = titles
+                .iter()
+                .map(|title| serde_json::to_string(&raw_row(&["Title"], &[title])).unwrap())
+                .collect();
+            std::fs::write(dir.join(name), lines.join("\n")).unwrap();
+        };
+        write_raw(
+            "raw-info-Clara_Schumann.json",
+            &["Piano Concerto", "Piano Trio"],
+        );
+        write_raw("raw-info-Fanny_Mendelssohn.json", &["Das Jahr"]);
+        std::fs::write(dir.join("composers.json"), "not a raw file\n").unwrap();
+        let dir_name = dir.to_str().unwrap();
+
+        let totals = reprocess_all(dir_name, &ReprocessAllOptions::default())
+            .await
+            .unwrap();
+        assert_eq!((totals.files, totals.compositions), (2, 3));
+        assert!(totals.failed.is_empty());
+        let clara = std::fs::read_to_string(dir.join("compositions-Clara_Schumann.json")).unwrap();
+        assert_eq!(clara.lines().count(), 2);
+        assert!(dir.join("compositions-Fanny_Mendelssohn.json").exists());
+
+        let combined = dir.join("all-compositions.json");
+        let options = ReprocessAllOptions {
+            combined_output: Some(combined.to_str().unwrap().to_string()),
+            concurrency: 1,
+            ..Default::default()
+        };
+        let totals = reprocess_all(dir_name, &options).await.unwrap();
+        let combined = std::fs::read_to_string(&combined).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(totals.compositions, 3);
+        let titles: Vec<String> = combined
+            .lines()
+            .map(|line| serde_json::from_str::<Composition>(line).unwrap().title)
+            .collect();
+        assert_eq!(titles, vec!["Piano Concerto", "Piano Trio", "Das Jahr"]);
+    }
+
+    #[test]
+    fn parses_opus_numbers_suffixes_and_posthumous_works() {
+        let parse = |text: &str| Opus::parse(text).unwrap();
+
+        let quartets = parse("String Quartets, Op. 59, Nos. 1–3");
+        assert_eq!(quartets.number.as_deref(), Some("59"));
+        assert_eq!(quartets.numbers.as_deref(), Some("1-3"));
+        assert_eq!(quartets.to_string(), "59");
+
+        let sonata = parse("Piano Sonata No. 14, Op. 27 No. 2");
+        assert_eq!(sonata.numbers.as_deref(), Some("2"));
+
+        let posth = parse("Waltz in A minor, Op. posth.");
+        assert!(posth.posthumous);
+        assert_eq!(posth.number, None);
+        assert_eq!(posth.to_string(), "posth.");
+
+        let waltzes = parse("Waltzes, Op. 69 (posth.)");
+        assert!(waltzes.posthumous);
+        assert_eq!(waltzes.to_string(), "69");
+
+        let letters = parse("Op. 10a/b");
+        assert_eq!(letters.suffixes, vec!["a", "b"]);
+        assert_eq!(letters.to_string(), "10a/b");
+
+        assert_eq!(parse("Études, Op. 10–12").range_end.as_deref(), Some("12"));
+        assert_eq!(Opus::parse("BWV 1007"), None);
+
+        let composition = canonicalize_raw_data(raw_row(
+            &["Title", "Year"],
+            &["String Quartets, Op. 59, Nos. 1–3", "1806"],
+        ));
+        assert_eq!(composition.opus.as_deref(), Some("59"));
+        assert_eq!(composition.opus_detail, Some(quartets));
+    }
+
+    #[test]
+    fn opus_ranges_parse_with_any_dash() {
+        let canonicalizer = FieldCanonicalizer::new();
+        for dash in ["-", "\u{2013}", "\u{2014}", "\u{2212}"] {
+            assert_eq!(
+                canonicalizer
+                    .extract_opus_from_text(&format!("Études, Op. 10{dash}12"))
+                    .as_deref(),
+                Some("10-12"),
+                "dash {dash:?}"
+            );
+        }
+    }
 }
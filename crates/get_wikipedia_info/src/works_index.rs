@@ -0,0 +1,132 @@
+// A plain-text index of one composer's works, one "Composer - Title (Year)"
+// per line in catalogue order, written to <name>.txt with --index. Easy to
+// read, grep or paste into a playlist.
+
+use anyhow::Result;
+use std::cmp::Ordering;
+
+use crate::text::file_stem;
+use crate::works::Composition;
+
+pub fn index_filename(composer_name: &str) -> String {
+    format!("{}.txt", file_stem(composer_name))
+}
+
+// Leading number of an opus or number string and what follows it:
+// "10a/b" gives (Some(10), "a/b"); "posth." gives (None, "posth.")
+fn number_and_rest(text: &str) -> (Option<u32>, &str) {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    (text[..digits].parse().ok(), &text[digits..])
+}
+
+// Catalogue order: works with a catalogue number first, by
+// catalog_sort_key; then works with an opus, by opus number and the number
+// within it; then the rest. Ties go by year, then title.
+fn catalogue_order(a: &Composition, b: &Composition) -> Ordering {
+    let opus = |c: &Composition| {
+        c.opus.as_deref().map(|opus| {
+            let within = c
+                .opus_detail
+                .as_ref()
+                .and_then(|detail| detail.numbers.as_deref())
+                .and_then(|numbers| number_and_rest(numbers).0);
+            let (number, rest) = number_and_rest(opus);
+            (number.is_none(), number, rest.to_string(), within)
+        })
+    };
+    (a.catalog_sort_key.is_none(), &a.catalog_sort_key)
+        .cmp(&(b.catalog_sort_key.is_none(), &b.catalog_sort_key))
+        .then_with(|| {
+            let (a_opus, b_opus) = (opus(a), opus(b));
+            (a_opus.is_none(), a_opus).cmp(&(b_opus.is_none(), b_opus))
+        })
+        .then_with(|| {
+            (a.year_numeric.is_none(), a.year_numeric)
+                .cmp(&(b.year_numeric.is_none(), b.year_numeric))
+        })
+        .then_with(|| a.title.cmp(&b.title))
+}
+
+pub fn works_index_lines(compositions: &[Composition]) -> Vec<String> {
+    let mut ordered: Vec<&Composition> = compositions.iter().collect();
+    ordered.sort_by(|a, b| catalogue_order(a, b));
+    ordered
+        .into_iter()
+        .map(|composition| match &composition.year {
+            Some(year) => format!(
+                "{} - {} ({})",
+                composition.composer_name, composition.title, year
+            ),
+            None => format!("{} - {}", composition.composer_name, composition.title),
+        })
+        .collect()
+}
+
+pub async fn write_works_index(path: &str, compositions: &[Composition]) -> Result<()> {
+    let mut contents = works_index_lines(compositions).join("\n");
+    contents.push('\n');
+    tokio::fs::write(path, contents).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::works::canonicalize_raw_data;
+    use crate::works::tests::raw_row;
+
+    fn composition(headers: &[&str], cells: &[&str]) -> Composition {
+        let mut raw = raw_row(headers, cells);
+        raw.composer_name = "Johann Sebastian Bach".to_string();
+        raw.composer_url = "https://en.wikipedia.org/wiki/Johann_Sebastian_Bach".to_string();
+        canonicalize_raw_data(raw)
+    }
+
+    #[test]
+    fn lists_works_in_catalogue_order() {
+        let compositions = vec![
+            composition(&["Title", "Year"], &["Undated Chorale", ""]),
+            composition(
+                &["Title", "Opus", "Year"],
+                &["Clavier-Übung II", "Op. 2", "1735"],
+            ),
+            composition(
+                &["Title", "Catalogue", "Year"],
+                &["Cello Suite No. 1", "BWV 1007", "1720"],
+            ),
+            composition(
+                &["Title", "Opus", "Year"],
+                &["Clavier-Übung I", "Op. 1", "1731"],
+            ),
+            composition(
+                &["Title", "Catalogue", "Year"],
+                &["Toccata and Fugue in D minor", "BWV 565", "c. 1704"],
+            ),
+            composition(
+                &["Title", "Catalogue", "Year"],
+                &["Christ lag in Todes Banden", "BWV 4", "1707"],
+            ),
+            composition(
+                &["Title", "Opus", "Year"],
+                &["Clavier-Übung III", "Op. 10", "1739"],
+            ),
+        ];
+
+        assert_eq!(
+            works_index_lines(&compositions),
+            vec![
+                "Johann Sebastian Bach - Christ lag in Todes Banden (1707)",
+                "Johann Sebastian Bach - Toccata and Fugue in D minor (1704)",
+                "Johann Sebastian Bach - Cello Suite No. 1 (1720)",
+                "Johann Sebastian Bach - Clavier-Übung I (1731)",
+                "Johann Sebastian Bach - Clavier-Übung II (1735)",
+                "Johann Sebastian Bach - Clavier-Übung III (1739)",
+                "Johann Sebastian Bach - Undated Chorale",
+            ]
+        );
+        assert_eq!(
+            index_filename("Johann Sebastian Bach"),
+            "Johann_Sebastian_Bach.txt"
+        );
+    }
+}
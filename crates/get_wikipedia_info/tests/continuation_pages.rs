@@ -0,0 +1,98 @@
+// A works list split across two pages, "(A–M)" and "(N–Z)", each linking
+// to the other from a navigation box, merged by --follow-continuations.
+// get_works writes into the working directory, so this file holds a single
+// test that moves into its own temporary directory.
+
+use get_wikipedia_info::Composition;
+use get_wikipedia_info::works::{WorksOptions, get_works};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Served for "List of compositions by Franz Schubert" too, as Wikipedia
+// does by redirecting; its canonical link names the A–M part
+const SCHUBERT_A_M: &str = r##"<!DOCTYPE html>
+<html><head>
+<link rel="canonical" href="https://en.wikipedia.org/wiki/List_of_compositions_by_Franz_Schubert_(A%E2%80%93M)">
+</head><body>
+<div class="mw-parser-output">
+<div class="hatnote">This is part 1 of the list; see <a href="/wiki/List_of_compositions_by_Franz_Schubert_(N%E2%80%93Z)">part 2 (N–Z)</a>.</div>
+<table class="wikitable sortable">
+  <tr><th>D</th><th>Title</th><th>Year</th></tr>
+  <tr><td>D 328</td><td><a href="/wiki/Erlk%C3%B6nig_(Schubert)">Erlkönig</a></td><td>1815</td></tr>
+  <tr><td>D 759</td><td><a href="/wiki/Symphony_No._8_(Schubert)">Symphony No. 8 "Unfinished"</a></td><td>1822</td></tr>
+  <tr><td>D 911</td><td><a href="/wiki/Winterreise">Winterreise</a></td><td>1827</td></tr>
+</table>
+<div class="navbox"><a href="/wiki/List_of_compositions_by_Franz_Schubert_(A%E2%80%93M)">A–M</a> · <a href="/wiki/List_of_compositions_by_Franz_Schubert_(N%E2%80%93Z)#Songs">N–Z</a> · <a href="/wiki/Franz_Schubert">Franz Schubert</a></div>
+</div>
+</body></html>"##;
+
+// Repeats Winterreise, which the A–M page also lists
+const SCHUBERT_N_Z: &str = r##"<!DOCTYPE html>
+<html><body>
+<div class="mw-parser-output">
+<table class="wikitable sortable">
+  <tr><th>D</th><th>Title</th><th>Year</th></tr>
+  <tr><td>D 667</td><td><a href="/wiki/Trout_Quintet">Piano Quintet "Trout"</a></td><td>1819</td></tr>
+  <tr><td>D 911</td><td><a href="/wiki/Winterreise">Winterreise</a></td><td>1827</td></tr>
+  <tr><td>D 810</td><td><a href="/wiki/Death_and_the_Maiden_Quartet">String Quartet No. 14 "Death and the Maiden"</a></td><td>1824</td></tr>
+</table>
+<div class="navbox"><a href="/wiki/List_of_compositions_by_Franz_Schubert_(A%E2%80%93M)">A–M</a> · <a href="/wiki/List_of_compositions_by_Franz_Schubert_(N%E2%80%93Z)">N–Z</a></div>
+</div>
+</body></html>"##;
+
+#[tokio::test]
+async fn follow_continuations_merges_a_list_split_across_pages() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wiki/List_of_compositions_by_Franz_Schubert"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(SCHUBERT_A_M))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(
+            "/wiki/List_of_compositions_by_Franz_Schubert_(A%E2%80%93M)",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_string(SCHUBERT_A_M))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(
+            "/wiki/List_of_compositions_by_Franz_Schubert_(N%E2%80%93Z)",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_string(SCHUBERT_N_Z))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("continuation-pages-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+
+    let options = WorksOptions {
+        base_url: server.uri(),
+        follow_continuations: true,
+        ..Default::default()
+    };
+    let count = get_works("Franz Schubert", &options).await.unwrap();
+
+    let output = std::fs::read_to_string(dir.join("compositions.json")).unwrap();
+    let compositions: Vec<Composition> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let titles: Vec<&str> = compositions.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "Erlkönig",
+            "Symphony No. 8 \"Unfinished\"",
+            "Winterreise",
+            "Piano Quintet \"Trout\"",
+            "String Quartet No. 14 \"Death and the Maiden\"",
+        ]
+    );
+    assert_eq!(count, 5);
+}
@@ -0,0 +1,121 @@
+// End-to-end run of get_works against a local Wikipedia stand-in. get_works
+// writes into the working directory, so this file holds a single test that
+// moves into its own temporary directory.
+
+use get_wikipedia_info::Composition;
+use get_wikipedia_info::works::{WorksOptions, get_works};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Trimmed from "List of compositions by Johannes Brahms", keeping the page
+// furniture around the table that extraction has to see past
+const BRAHMS_LIST_PAGE: &str = r##"<!DOCTYPE html>
+<html><body>
+<div id="mw-content-text"><div class="mw-parser-output">
+<div class="hatnote">For a list by genre, see Brahms's works by genre.</div>
+<p>This is a list of compositions by <a href="/wiki/Johannes_Brahms">Johannes Brahms</a>.</p>
+<div id="toc" class="toc"><ul><li><a href="#Orchestral">1 Orchestral works</a></li></ul></div>
+<h2><span class="mw-headline" id="Orchestral">Orchestral works</span></h2>
+<table class="wikitable sortable">
+  <caption>Symphonies</caption>
+  <tbody>
+    <tr><th>Opus</th><th>Title</th><th>Key</th><th>Year</th></tr>
+    <tr><td>Op. 68</td><td><a href="/wiki/Symphony_No._1_(Brahms)">Symphony No. 1</a></td><td>C minor</td><td>1876</td></tr>
+    <tr><td>Op. 73</td><td><a href="/wiki/Symphony_No._2_(Brahms)">Symphony No. 2</a></td><td>D major</td><td>1877</td></tr>
+    <tr><td>Op. 90</td><td><a href="/wiki/Symphony_No._3_(Brahms)">Symphony No. 3</a></td><td>F major</td><td>1883</td></tr>
+    <tr><td>Op. 98</td><td><a href="/wiki/Symphony_No._4_(Brahms)">Symphony No. 4</a></td><td>E minor</td><td>1885</td></tr>
+  </tbody>
+</table>
+<h2><span class="mw-headline" id="References">References</span></h2>
+<div class="reflist"><ol class="references"><li>Swafford, Jan. <i>Johannes Brahms: A Biography</i>.</li></ol></div>
+<div role="navigation" class="navbox" aria-labelledby="Johannes_Brahms">
+  <table class="nowraplinks mw-collapsible autocollapse navbox-inner">
+    <tbody>
+      <tr><th scope="col" class="navbox-title" colspan="2">Johannes Brahms</th></tr>
+      <tr><th scope="row" class="navbox-group">Symphonies</th>
+          <td class="navbox-list"><a href="/wiki/Symphony_No._1_(Brahms)">No. 1</a> · <a href="/wiki/Symphony_No._2_(Brahms)">No. 2</a> · <a href="/wiki/Symphony_No._3_(Brahms)">No. 3</a> · <a href="/wiki/Symphony_No._4_(Brahms)">No. 4</a></td></tr>
+      <tr><th scope="row" class="navbox-group">Concertos</th>
+          <td class="navbox-list"><a href="/wiki/Violin_Concerto_(Brahms)">Violin Concerto</a> · <a href="/wiki/Double_Concerto_(Brahms)">Double Concerto</a></td></tr>
+      <tr><th scope="row" class="navbox-group">Choral</th>
+          <td class="navbox-list"><a href="/wiki/A_German_Requiem">Ein deutsches Requiem</a></td></tr>
+    </tbody>
+  </table>
+</div>
+</div></div>
+</body></html>"##;
+
+#[tokio::test]
+async fn get_works_writes_canonical_compositions_for_a_list_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wiki/List_of_compositions_by_Johannes_Brahms"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(BRAHMS_LIST_PAGE))
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("get-works-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+
+    let options = WorksOptions {
+        base_url: server.uri(),
+        ..Default::default()
+    };
+    let count = get_works("Johannes Brahms", &options).await.unwrap();
+
+    let output = std::fs::read_to_string(dir.join("compositions.json")).unwrap();
+    let compositions: Vec<Composition> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(dir.join("raw-info-Johannes_Brahms.json").exists());
+    assert!(dir.join("composer-summary-Johannes_Brahms.json").exists());
+    let attribution: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("ATTRIBUTION.json")).unwrap())
+            .unwrap();
+    assert_eq!(attribution["license"], "CC BY-SA 4.0");
+    assert_eq!(
+        attribution["sources"][0]["composer_name"],
+        "Johannes Brahms"
+    );
+    assert_eq!(
+        attribution["sources"][0]["source_urls"],
+        serde_json::json!([format!(
+            "{}/wiki/List_of_compositions_by_Johannes_Brahms",
+            server.uri()
+        )])
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(count, 4);
+    assert_eq!(compositions.len(), 4);
+
+    let first = &compositions[0];
+    assert_eq!(first.composer_name, "Johannes Brahms");
+    assert_eq!(
+        first.source_url,
+        "https://en.wikipedia.org/wiki/Symphony_No._1_(Brahms)"
+    );
+    assert_eq!(first.title, "Symphony No. 1");
+    assert_eq!(first.opus.as_deref(), Some("68"));
+    assert_eq!(first.key.as_deref(), Some("C minor"));
+    assert_eq!(first.year.as_deref(), Some("1876"));
+    assert_eq!(first.genre.as_deref(), Some("Symphonies"));
+
+    let titles: Vec<&str> = compositions.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        vec![
+            "Symphony No. 1",
+            "Symphony No. 2",
+            "Symphony No. 3",
+            "Symphony No. 4"
+        ]
+    );
+    // The navbox at the foot of the page contributes nothing
+    assert!(
+        compositions
+            .iter()
+            .all(|c| !c.title.contains("Requiem") && c.title != "Johannes Brahms")
+    );
+}
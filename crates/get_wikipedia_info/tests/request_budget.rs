@@ -0,0 +1,138 @@
+// A batch run with a request budget too small for every composer: it stops
+// at the first composer the budget can't cover, keeps what it wrote, and
+// leaves a run report that --retry-failed resumes from. get_works writes
+// into the working directory, so this file holds a single test that moves
+// into its own temporary directory.
+
+use std::sync::Arc;
+
+use get_wikipedia_info::Composition;
+use get_wikipedia_info::batch::{BatchOptions, RunReport, get_works_for_composers};
+use get_wikipedia_info::http::{HttpOptions, RequestBudget};
+use get_wikipedia_info::works::WorksOptions;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn list_page(rows: &[(&str, &str)]) -> String {
+    let rows: String = rows
+        .iter()
+        .map(|(title, year)| format!("<tr><td>{}</td><td>{}</td></tr>", title, year))
+        .collect();
+    format!(
+        "<html><body><table class=\"wikitable\"><tr><th>Title</th><th>Year</th></tr>{}</table></body></html>",
+        rows
+    )
+}
+
+fn composer_line(name: &str) -> String {
+    serde_json::json!({
+        "url": format!("https://en.wikipedia.org/wiki/{}", name.replace(' ', "_")),
+        "full_name": name,
+        "sort_name": name,
+        "list_of_compositions_url": "",
+        "birth_year": null,
+        "death_year": null,
+        "years_qualifier": "exact"
+    })
+    .to_string()
+}
+
+fn batch_options(server: &MockServer, budget: u64, retry_failed: Option<String>) -> BatchOptions {
+    BatchOptions {
+        composers_file: "composers.json".to_string(),
+        retry_failed,
+        report_file: "run-report.json".to_string(),
+        works: WorksOptions {
+            base_url: server.uri(),
+            http: HttpOptions {
+                request_budget: Some(Arc::new(RequestBudget::new(budget))),
+                ..HttpOptions::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn a_spent_request_budget_stops_the_batch_with_a_resumable_report() {
+    let server = MockServer::start().await;
+    for (name, rows) in [
+        (
+            "Clara_Schumann",
+            [("Piano Trio", "1846"), ("Three Romances", "1853")],
+        ),
+        (
+            "Fanny_Mendelssohn",
+            [("Das Jahr", "1841"), ("String Quartet", "1834")],
+        ),
+        (
+            "Amy_Beach",
+            [("Gaelic Symphony", "1896"), ("Piano Concerto", "1899")],
+        ),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(format!("/wiki/List_of_compositions_by_{}", name)))
+            .respond_with(ResponseTemplate::new(200).set_body_string(list_page(&rows)))
+            .mount(&server)
+            .await;
+    }
+
+    let dir = std::env::temp_dir().join(format!("request-budget-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    std::fs::write(
+        "composers.json",
+        ["Clara Schumann", "Fanny Mendelssohn", "Amy Beach"]
+            .map(composer_line)
+            .join("\n"),
+    )
+    .unwrap();
+
+    // One list page per composer: a budget of 1 covers only the first
+    let report = get_works_for_composers(&batch_options(&server, 1, None))
+        .await
+        .unwrap();
+    assert_eq!(report.succeeded, 1);
+    assert_eq!(report.failed, 0);
+    assert_eq!(report.remaining, vec!["Fanny Mendelssohn", "Amy Beach"]);
+
+    let checkpoint = RunReport::load("run-report.json").await.unwrap();
+    assert_eq!(checkpoint.remaining, report.remaining);
+    assert_eq!(checkpoint.composers[0].composer, "Clara Schumann");
+    let titles = |contents: String| -> Vec<String> {
+        contents
+            .lines()
+            .map(|line| serde_json::from_str::<Composition>(line).unwrap().title)
+            .collect()
+    };
+    assert_eq!(
+        titles(std::fs::read_to_string("compositions.json").unwrap()),
+        vec!["Piano Trio", "Three Romances"]
+    );
+
+    // Resuming from the report scrapes only the composers left over
+    let resumed = get_works_for_composers(&batch_options(
+        &server,
+        10,
+        Some("run-report.json".to_string()),
+    ))
+    .await
+    .unwrap();
+    let compositions = titles(std::fs::read_to_string(dir.join("compositions.json")).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(resumed.succeeded, 2);
+    assert!(resumed.remaining.is_empty());
+    assert_eq!(
+        compositions,
+        vec![
+            "Piano Trio",
+            "Three Romances",
+            "Das Jahr",
+            "String Quartet",
+            "Gaelic Symphony",
+            "Piano Concerto",
+        ]
+    );
+}
@@ -0,0 +1,91 @@
+// A batch run with --deep whose request budget runs out while fetching
+// work pages: the composer being enriched is not counted as scraped, none
+// of its partly enriched compositions are written, and the run report
+// leaves it to resume. get_works writes into the working directory, so
+// this file holds a single test that moves into its own temporary
+// directory.
+
+use std::sync::Arc;
+
+use get_wikipedia_info::batch::{BatchOptions, RunReport, get_works_for_composers};
+use get_wikipedia_info::http::{HttpOptions, RequestBudget};
+use get_wikipedia_info::infobox::DeepOptions;
+use get_wikipedia_info::works::WorksOptions;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SCHUMANN_LIST_PAGE: &str = r#"<html><body>
+<table class="wikitable">
+  <tr><th>Title</th><th>Year</th></tr>
+  <tr><td><a href="/wiki/Piano_Trio_(Clara_Schumann)">Piano Trio</a></td><td>1846</td></tr>
+  <tr><td><a href="/wiki/Three_Romances_for_Violin_and_Piano">Three Romances</a></td><td>1853</td></tr>
+</table>
+</body></html>"#;
+
+#[tokio::test]
+async fn a_budget_spent_during_enrichment_leaves_the_composer_to_resume() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wiki/List_of_compositions_by_Clara_Schumann"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(SCHUMANN_LIST_PAGE))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("request-budget-deep-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    let composer = |name: &str| {
+        serde_json::json!({
+            "url": format!("https://en.wikipedia.org/wiki/{}", name.replace(' ', "_")),
+            "full_name": name,
+            "sort_name": name,
+            "list_of_compositions_url": "",
+            "birth_year": null,
+            "death_year": null,
+            "years_qualifier": "exact"
+        })
+        .to_string()
+    };
+    std::fs::write(
+        "composers.json",
+        [composer("Clara Schumann"), composer("Amy Beach")].join("\n"),
+    )
+    .unwrap();
+
+    // The list page spends the whole budget; the first work page is refused
+    // before it is requested
+    let options = BatchOptions {
+        composers_file: "composers.json".to_string(),
+        report_file: "run-report.json".to_string(),
+        works: WorksOptions {
+            base_url: server.uri(),
+            deep: Some(DeepOptions {
+                concurrency: 1,
+                requests_per_second: 100.0,
+            }),
+            http: HttpOptions {
+                request_budget: Some(Arc::new(RequestBudget::new(1))),
+                ..HttpOptions::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = get_works_for_composers(&options).await.unwrap();
+
+    let checkpoint = RunReport::load("run-report.json").await.unwrap();
+    let compositions = std::fs::read_to_string("compositions.json").unwrap_or_default();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.succeeded, 0);
+    assert_eq!(report.failed, 0);
+    assert!(report.composers.is_empty());
+    assert_eq!(checkpoint.remaining, vec!["Clara Schumann", "Amy Beach"]);
+    assert_eq!(
+        checkpoint.unfinished_composers(),
+        vec!["Clara Schumann", "Amy Beach"]
+    );
+    assert!(compositions.is_empty());
+    server.verify().await;
+}
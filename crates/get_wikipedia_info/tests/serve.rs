@@ -0,0 +1,68 @@
+// The serve endpoint against a local Wikipedia stand-in. Scrapes write into
+// the working directory, so like get_works.rs this file holds a single test
+// that moves into its own temporary directory.
+#![cfg(feature = "server")]
+
+use get_wikipedia_info::Composition;
+use get_wikipedia_info::server::serve;
+use get_wikipedia_info::works::WorksOptions;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const BRAHMS_LIST_PAGE: &str = r#"<html><body>
+<table class="wikitable sortable">
+  <tr><th>Opus</th><th>Title</th><th>Key</th><th>Year</th></tr>
+  <tr><td>Op. 68</td><td>Symphony No. 1</td><td>C minor</td><td>1876</td></tr>
+  <tr><td>Op. 73</td><td>Symphony No. 2</td><td>D major</td><td>1877</td></tr>
+</table>
+</body></html>"#;
+
+#[tokio::test]
+async fn works_endpoint_streams_ndjson_and_caches_per_composer() {
+    let upstream = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wiki/List_of_compositions_by_Johannes_Brahms"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(BRAHMS_LIST_PAGE))
+        .mount(&upstream)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("serve-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = WorksOptions {
+        base_url: upstream.uri(),
+        wikitext_fallback: false,
+        ..Default::default()
+    };
+    let server = tokio::spawn(serve(listener, options));
+
+    let url = format!("http://{}/works?composer=Johannes%20Brahms", addr);
+    let mut bodies = Vec::new();
+    for _ in 0..2 {
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+        bodies.push(response.text().await.unwrap());
+    }
+    let missing = reqwest::get(format!("http://{}/works", addr))
+        .await
+        .unwrap();
+    server.abort();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let compositions: Vec<Composition> = bodies[0]
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(compositions.len(), 2);
+    assert_eq!(compositions[0].composer_name, "Johannes Brahms");
+    assert_eq!(compositions[1].title, "Symphony No. 2");
+
+    // The second request is served from the cache
+    assert_eq!(bodies[1], bodies[0]);
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 1);
+    assert_eq!(missing.status(), 400);
+}
@@ -0,0 +1,64 @@
+// Concurrent requests for one composer share a single scrape. Scrapes write
+// into the working directory, so this file holds a single test that moves
+// into its own temporary directory.
+#![cfg(feature = "server")]
+
+use get_wikipedia_info::server::serve;
+use get_wikipedia_info::works::WorksOptions;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SCHUMANN_LIST_PAGE: &str = r#"<html><body>
+<table class="wikitable sortable">
+  <tr><th>Opus</th><th>Title</th><th>Year</th></tr>
+  <tr><td>Op. 7</td><td>Piano Concerto</td><td>1835</td></tr>
+  <tr><td>Op. 17</td><td>Piano Trio</td><td>1846</td></tr>
+</table>
+</body></html>"#;
+
+#[tokio::test]
+async fn concurrent_requests_for_a_composer_wait_for_one_scrape() {
+    let upstream = MockServer::start().await;
+    // Slow enough that every request arrives while the first is scraping
+    Mock::given(method("GET"))
+        .and(path("/wiki/List_of_compositions_by_Clara_Schumann"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(SCHUMANN_LIST_PAGE)
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&upstream)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("serve-concurrent-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = WorksOptions {
+        base_url: upstream.uri(),
+        wikitext_fallback: false,
+        ..Default::default()
+    };
+    let server = tokio::spawn(serve(listener, options));
+
+    let url = format!("http://{}/works?composer=Clara%20Schumann", addr);
+    let requests: Vec<_> = (0..3)
+        .map(|_| {
+            let url = url.clone();
+            tokio::spawn(async move { reqwest::get(&url).await.unwrap().text().await.unwrap() })
+        })
+        .collect();
+    let mut bodies = Vec::new();
+    for request in requests {
+        bodies.push(request.await.unwrap());
+    }
+    server.abort();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(bodies[0].lines().count(), 2);
+    assert!(bodies.iter().all(|body| *body == bodies[0]));
+    assert_eq!(upstream.received_requests().await.unwrap().len(), 1);
+}
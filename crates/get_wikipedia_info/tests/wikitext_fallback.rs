@@ -0,0 +1,86 @@
+// get_works on a list page whose HTML has no usable tables, read from its
+// wikitext (?action=raw) instead. The titles are accented and CJK, as on
+// many real list pages. get_works writes into the working directory, so
+// this file holds a single test that moves into its own temporary
+// directory.
+
+use get_wikipedia_info::Composition;
+use get_wikipedia_info::works::{WorksOptions, get_works};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Rendered by script, so the served HTML holds no table
+const DVORAK_LIST_PAGE: &str = r#"<!DOCTYPE html>
+<html><body><div class="mw-parser-output"><p>Loading…</p></div></body></html>"#;
+
+const DVORAK_WIKITEXT: &str = r#"
+== Operas ==
+{| class="wikitable sortable"
+! B. !! Title !! Year
+|-
+| 203 || [[Rusalka (opera)|Rusalka]] – ''lyrická pohádka'' || 1900
+|-
+| 177
+| [[Jakobín|Jakobín]]
+| 1888
+|-
+| 195 || ''Čert a Káča'' || 1899
+|-
+| 178 || 交響曲第9番「新世界より」 || 1893
+|}
+"#;
+
+#[tokio::test]
+async fn get_works_reads_accented_wikitext_when_the_html_has_no_tables() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(
+            "/wiki/List_of_compositions_by_Anton%C3%ADn_Dvo%C5%99%C3%A1k",
+        ))
+        .and(query_param("action", "raw"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(DVORAK_WIKITEXT))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(
+            "/wiki/List_of_compositions_by_Anton%C3%ADn_Dvo%C5%99%C3%A1k",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_string(DVORAK_LIST_PAGE))
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("wikitext-fallback-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+
+    let options = WorksOptions {
+        base_url: server.uri(),
+        ..Default::default()
+    };
+    let count = get_works("Antonín Dvořák", &options).await.unwrap();
+
+    let output = std::fs::read_to_string(dir.join("compositions.json")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let compositions: Vec<Composition> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(count, 4);
+    let rows: Vec<(&str, Option<&str>)> = compositions
+        .iter()
+        .map(|c| (c.title.as_str(), c.year.as_deref()))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("Rusalka – lyrická pohádka", Some("1900")),
+            ("Jakobín", Some("1888")),
+            ("Čert a Káča", Some("1899")),
+            ("交響曲第9番「新世界より」", Some("1893")),
+        ]
+    );
+    server.verify().await;
+}
@@ -0,0 +1,66 @@
+// get_works with an explicit works page, for composers whose list isn't at
+// "List of compositions by X". Kept apart from get_works.rs because both
+// move into their own temporary working directory.
+
+use get_wikipedia_info::Composition;
+use get_wikipedia_info::works::{WorksOptions, get_works};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Trimmed from "List of solo piano compositions by Franz Liszt"
+const LISZT_PIANO_PAGE: &str = r#"<!DOCTYPE html>
+<html><body>
+<div id="mw-content-text"><div class="mw-parser-output">
+<h2><span class="mw-headline" id="Original_works">Original works</span></h2>
+<table class="wikitable sortable">
+  <tbody>
+    <tr><th>S.</th><th>Title</th><th>Year</th></tr>
+    <tr><td>S.139</td><td><a href="/wiki/Transcendental_%C3%89tudes">Transcendental Études</a></td><td>1852</td></tr>
+    <tr><td>S.178</td><td><a href="/wiki/Piano_Sonata_in_B_minor_(Liszt)">Piano Sonata in B minor</a></td><td>1853</td></tr>
+  </tbody>
+</table>
+</div></div>
+</body></html>"#;
+
+#[tokio::test]
+async fn get_works_scrapes_the_given_works_url() {
+    // Nothing is mounted at the guessed list page, so only a request for the
+    // given URL can find the table
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/wiki/List_of_solo_piano_compositions_by_Franz_Liszt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(LISZT_PIANO_PAGE))
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("works-url-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+
+    let works_url = format!(
+        "{}/wiki/List_of_solo_piano_compositions_by_Franz_Liszt",
+        server.uri()
+    );
+    let options = WorksOptions {
+        base_url: server.uri(),
+        works_url: Some(works_url.clone()),
+        wikitext_fallback: false,
+        ..Default::default()
+    };
+    let count = get_works("Franz Liszt", &options).await.unwrap();
+
+    let output = std::fs::read_to_string(dir.join("compositions.json")).unwrap();
+    let compositions: Vec<Composition> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(count, 2);
+    assert_eq!(compositions[0].composer_name, "Franz Liszt");
+    assert_eq!(compositions[0].title, "Transcendental Études");
+    assert_eq!(compositions[1].title, "Piano Sonata in B minor");
+    assert_eq!(compositions[1].year.as_deref(), Some("1853"));
+}